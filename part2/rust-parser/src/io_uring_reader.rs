@@ -0,0 +1,538 @@
+use std::collections::VecDeque;
+use std::fs::File;
+use std::io::{self, Read};
+use std::os::unix::io::AsRawFd;
+use std::ptr;
+use std::sync::atomic::{AtomicU32, Ordering};
+
+use libc::{
+    c_int, c_long, c_uint, c_void, MAP_FAILED, MAP_POPULATE, MAP_SHARED, PROT_READ, PROT_WRITE,
+};
+
+// what the parser and the read strategies use, 8 reads of 1MB in flight
+pub const CHUNK_SIZE: usize = 1024 * 1024;
+pub const QUEUE_DEPTH: usize = 8;
+
+// linux/io_uring.h, only the parts a read loop needs
+const IORING_OFF_SQ_RING: i64 = 0;
+const IORING_OFF_CQ_RING: i64 = 0x8000000;
+const IORING_OFF_SQES: i64 = 0x10000000;
+const IORING_ENTER_GETEVENTS: c_uint = 1;
+// Linux 5.6+
+const IORING_OP_READ: u8 = 22;
+
+#[repr(C)]
+#[derive(Default)]
+struct SqringOffsets {
+    head: u32,
+    tail: u32,
+    ring_mask: u32,
+    ring_entries: u32,
+    flags: u32,
+    dropped: u32,
+    array: u32,
+    resv1: u32,
+    user_addr: u64,
+}
+
+#[repr(C)]
+#[derive(Default)]
+struct CqringOffsets {
+    head: u32,
+    tail: u32,
+    ring_mask: u32,
+    ring_entries: u32,
+    overflow: u32,
+    cqes: u32,
+    flags: u32,
+    resv1: u32,
+    user_addr: u64,
+}
+
+#[repr(C)]
+#[derive(Default)]
+struct IoUringParams {
+    sq_entries: u32,
+    cq_entries: u32,
+    flags: u32,
+    sq_thread_cpu: u32,
+    sq_thread_idle: u32,
+    features: u32,
+    wq_fd: u32,
+    resv: [u32; 3],
+    sq_off: SqringOffsets,
+    cq_off: CqringOffsets,
+}
+
+#[repr(C)]
+struct IoUringSqe {
+    opcode: u8,
+    flags: u8,
+    ioprio: u16,
+    fd: i32,
+    off: u64,
+    addr: u64,
+    len: u32,
+    rw_flags: u32,
+    user_data: u64,
+    buf_index: u16,
+    personality: u16,
+    splice_fd_in: i32,
+    addr3: u64,
+    pad: u64,
+}
+
+#[repr(C)]
+struct IoUringCqe {
+    user_data: u64,
+    res: i32,
+    flags: u32,
+}
+
+// One mmap'd region of the ring, unmapped on drop.
+struct Mapping {
+    addr: *mut u8,
+    size: usize,
+}
+
+impl Mapping {
+    fn new(ring_fd: c_int, size: usize, offset: i64) -> io::Result<Mapping> {
+        let addr = unsafe {
+            libc::mmap(
+                ptr::null_mut(),
+                size,
+                PROT_READ | PROT_WRITE,
+                MAP_SHARED | MAP_POPULATE,
+                ring_fd,
+                offset,
+            )
+        };
+        if addr == MAP_FAILED {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(Mapping {
+            addr: addr as *mut u8,
+            size,
+        })
+    }
+
+    fn at<T>(&self, offset: u32) -> *mut T {
+        unsafe { self.addr.add(offset as usize) as *mut T }
+    }
+}
+
+impl Drop for Mapping {
+    fn drop(&mut self) {
+        unsafe {
+            libc::munmap(self.addr as *mut c_void, self.size);
+        }
+    }
+}
+
+// One io_uring instance: the submission and completion rings, mapped, and
+// the reads still in flight through them. Dropping it waits those out, the
+// kernel may still be writing to their buffers.
+pub struct Ring {
+    ring_fd: c_int,
+    sq_ring: Mapping,
+    cq_ring: Mapping,
+    sqes: Mapping,
+    params: IoUringParams,
+    unsubmitted: u32,
+    // reads the kernel still has to complete, queued or submitted
+    in_flight: u32,
+}
+
+impl Ring {
+    pub fn new(entries: usize) -> io::Result<Ring> {
+        let mut params = IoUringParams::default();
+        let ring_fd = unsafe {
+            libc::syscall(
+                libc::SYS_io_uring_setup,
+                entries as c_uint,
+                &mut params as *mut IoUringParams,
+            )
+        } as c_int;
+        if ring_fd < 0 {
+            return Err(io::Error::last_os_error());
+        }
+        // there is no ring to close the fd on drop until the end
+        let close_on_error = |error: io::Error| {
+            unsafe {
+                libc::close(ring_fd);
+            }
+            error
+        };
+
+        let sq_size = params.sq_off.array as usize + params.sq_entries as usize * 4;
+        let cq_size = params.cq_off.cqes as usize
+            + params.cq_entries as usize * std::mem::size_of::<IoUringCqe>();
+        let sqes_size = params.sq_entries as usize * std::mem::size_of::<IoUringSqe>();
+        let sq_ring = Mapping::new(ring_fd, sq_size, IORING_OFF_SQ_RING).map_err(close_on_error)?;
+        let cq_ring = Mapping::new(ring_fd, cq_size, IORING_OFF_CQ_RING).map_err(close_on_error)?;
+        let sqes = Mapping::new(ring_fd, sqes_size, IORING_OFF_SQES).map_err(close_on_error)?;
+
+        Ok(Ring {
+            ring_fd,
+            sq_ring,
+            cq_ring,
+            sqes,
+            params,
+            unsubmitted: 0,
+            in_flight: 0,
+        })
+    }
+
+    // Reads that can be in flight at once, what the rings hold.
+    fn capacity(&self) -> usize {
+        self.params.sq_entries as usize
+    }
+
+    // Writes a read of `len` bytes at `offset` into `destination` to the next
+    // submission queue entry. The caller keeps `destination` alive until the
+    // read completes and never queues more than capacity() reads.
+    fn queue_read(
+        &mut self,
+        fd: c_int,
+        offset: u64,
+        destination: *mut u8,
+        len: usize,
+        user_data: u64,
+    ) {
+        let sq_off = &self.params.sq_off;
+        unsafe {
+            let tail = &*self.sq_ring.at::<AtomicU32>(sq_off.tail);
+            let mask = *self.sq_ring.at::<u32>(sq_off.ring_mask);
+            let array = self.sq_ring.at::<u32>(sq_off.array);
+
+            let index = tail.load(Ordering::Acquire) & mask;
+            let sqe = &mut *self.sqes.at::<IoUringSqe>(0).add(index as usize);
+            *sqe = IoUringSqe {
+                opcode: IORING_OP_READ,
+                flags: 0,
+                ioprio: 0,
+                fd,
+                off: offset,
+                addr: destination as u64,
+                len: len as u32,
+                rw_flags: 0,
+                user_data,
+                buf_index: 0,
+                personality: 0,
+                splice_fd_in: 0,
+                addr3: 0,
+                pad: 0,
+            };
+            *array.add(index as usize) = index;
+            // the entry has to be written before the kernel can see the tail
+            tail.fetch_add(1, Ordering::Release);
+        }
+        self.unsubmitted += 1;
+        self.in_flight += 1;
+    }
+
+    // Submits the queued reads and waits for `min_complete` of them.
+    fn enter(&mut self, min_complete: u32) -> io::Result<()> {
+        loop {
+            let result = unsafe {
+                libc::syscall(
+                    libc::SYS_io_uring_enter,
+                    self.ring_fd,
+                    self.unsubmitted as c_uint,
+                    min_complete as c_uint,
+                    IORING_ENTER_GETEVENTS,
+                    ptr::null::<c_void>(),
+                    0 as c_long,
+                )
+            };
+            if result >= 0 {
+                self.unsubmitted -= result as u32;
+                return Ok(());
+            }
+            let error = io::Error::last_os_error();
+            if error.kind() != io::ErrorKind::Interrupted {
+                return Err(error);
+            }
+        }
+    }
+
+    // Takes every finished read off the completion queue, as (user_data,
+    // result) pairs.
+    fn reap(&mut self) -> Vec<(u64, i32)> {
+        let cq_off = &self.params.cq_off;
+        let (head, tail, mask, cqes) = unsafe {
+            (
+                &*self.cq_ring.at::<AtomicU32>(cq_off.head),
+                &*self.cq_ring.at::<AtomicU32>(cq_off.tail),
+                *self.cq_ring.at::<u32>(cq_off.ring_mask),
+                self.cq_ring.at::<IoUringCqe>(cq_off.cqes),
+            )
+        };
+
+        let mut current = head.load(Ordering::Relaxed);
+        let end = tail.load(Ordering::Acquire);
+        let mut completions = Vec::new();
+        while current != end {
+            let cqe = unsafe { &*cqes.add((current & mask) as usize) };
+            completions.push((cqe.user_data, cqe.res));
+            current = current.wrapping_add(1);
+        }
+        head.store(current, Ordering::Release);
+        self.in_flight -= completions.len() as u32;
+        completions
+    }
+
+    // Waits until nothing is in flight, a failed read is as finished as any
+    // other here.
+    fn drain(&mut self) {
+        while self.in_flight > 0 {
+            if self.enter(1).is_err() {
+                break;
+            }
+            self.reap();
+        }
+    }
+
+    // Reads `file` from its start straight into `destination`, `chunk_size`
+    // bytes per read with as many in flight as the rings hold. Short reads are
+    // queued again for the rest of their chunk. Returns the bytes read, which
+    // fall short of the destination only when the file does; a failed read is
+    // reported once the others are done.
+    pub fn read_file(
+        &mut self,
+        file: &File,
+        destination: &mut [u8],
+        chunk_size: usize,
+    ) -> io::Result<u64> {
+        let fd = file.as_raw_fd();
+        let size = destination.len();
+        let pointer = destination.as_mut_ptr();
+        let chunk_len = |chunk: usize| chunk_size.min(size - chunk * chunk_size);
+        let chunk_count = size.div_ceil(chunk_size);
+
+        let mut filled = vec![0; chunk_count];
+        let mut next_chunk = 0;
+        let mut error = None;
+        loop {
+            while error.is_none()
+                && next_chunk < chunk_count
+                && (self.in_flight as usize) < self.capacity()
+            {
+                let offset = next_chunk * chunk_size;
+                let destination = unsafe { pointer.add(offset) };
+                self.queue_read(
+                    fd,
+                    offset as u64,
+                    destination,
+                    chunk_len(next_chunk),
+                    next_chunk as u64,
+                );
+                next_chunk += 1;
+            }
+            if self.in_flight == 0 {
+                break;
+            }
+            if let Err(e) = self.enter(1) {
+                self.drain();
+                return Err(e);
+            }
+            for (chunk, result) in self.reap() {
+                let chunk = chunk as usize;
+                if result < 0 {
+                    error.get_or_insert(io::Error::from_raw_os_error(-result));
+                    continue;
+                }
+                filled[chunk] += result as usize;
+                // 0 is the end of the file
+                if result > 0 && filled[chunk] < chunk_len(chunk) && error.is_none() {
+                    let offset = chunk * chunk_size + filled[chunk];
+                    let destination = unsafe { pointer.add(offset) };
+                    self.queue_read(
+                        fd,
+                        offset as u64,
+                        destination,
+                        chunk_len(chunk) - filled[chunk],
+                        chunk as u64,
+                    );
+                }
+            }
+        }
+
+        match error {
+            Some(error) => Err(error),
+            None => Ok(filled.iter().sum::<usize>() as u64),
+        }
+    }
+}
+
+impl Drop for Ring {
+    fn drop(&mut self) {
+        self.drain();
+        unsafe {
+            libc::close(self.ring_fd);
+        }
+    }
+}
+
+// A chunk buffer and the part of the file it is reading.
+struct Slot {
+    buffer: Vec<u8>,
+    offset: u64,
+    len: usize,
+    filled: usize,
+    // read in full, or as far as the file went
+    done: bool,
+}
+
+// Reads a file through io_uring with up to `queue_depth` chunk reads in
+// flight, and hands the data back in file order through io::Read. Every
+// chunk that gets drained is submitted again for the next part of the file,
+// so the disk stays busy while the caller works on what it already has.
+pub struct IoUringReader {
+    // first, so dropping it waits out the reads before the slots go
+    ring: Ring,
+    file: File,
+    file_size: u64,
+    slots: Vec<Slot>,
+    // slots in file order, the front one is the next to hand out
+    pending: VecDeque<usize>,
+    // read position inside the front slot
+    position: usize,
+    next_offset: u64,
+}
+
+impl IoUringReader {
+    pub fn open(
+        file_name: &str,
+        chunk_size: usize,
+        queue_depth: usize,
+    ) -> io::Result<IoUringReader> {
+        let file = File::open(file_name)?;
+        let file_size = file.metadata()?.len();
+        let ring = Ring::new(queue_depth)?;
+
+        // never more in flight than the rings hold
+        let slot_count = queue_depth.min(ring.capacity()).max(1);
+        let slots = (0..slot_count)
+            .map(|_| Slot {
+                buffer: vec![0; chunk_size],
+                offset: 0,
+                len: 0,
+                filled: 0,
+                done: false,
+            })
+            .collect();
+
+        let mut reader = IoUringReader {
+            ring,
+            file,
+            file_size,
+            slots,
+            pending: VecDeque::with_capacity(slot_count),
+            position: 0,
+            next_offset: 0,
+        };
+        for slot in 0..slot_count {
+            reader.start_chunk(slot);
+        }
+        reader.ring.enter(0)?;
+
+        Ok(reader)
+    }
+
+    // Points `slot` at the next chunk of the file and queues its read,
+    // nothing happens once the whole file is queued.
+    fn start_chunk(&mut self, slot: usize) {
+        if self.next_offset >= self.file_size {
+            return;
+        }
+        let chunk_size = self.slots[slot].buffer.len();
+        let len = chunk_size.min((self.file_size - self.next_offset) as usize);
+        let chunk = &mut self.slots[slot];
+        chunk.offset = self.next_offset;
+        chunk.len = len;
+        chunk.filled = 0;
+        chunk.done = false;
+        self.next_offset += len as u64;
+        self.pending.push_back(slot);
+        self.queue_read(slot);
+    }
+
+    // Queues the read of what is still missing from `slot`.
+    fn queue_read(&mut self, slot: usize) {
+        let chunk = &mut self.slots[slot];
+        let destination = unsafe { chunk.buffer.as_mut_ptr().add(chunk.filled) };
+        self.ring.queue_read(
+            self.file.as_raw_fd(),
+            chunk.offset + chunk.filled as u64,
+            destination,
+            chunk.len - chunk.filled,
+            slot as u64,
+        );
+    }
+
+    // Moves every finished read into its slot, the short ones are queued
+    // again for the rest of their chunk. A failed read is reported once the
+    // rest of the batch is accounted for.
+    fn reap(&mut self) -> io::Result<()> {
+        let mut error = None;
+        for (slot, result) in self.ring.reap() {
+            let slot = slot as usize;
+            let chunk = &mut self.slots[slot];
+            if result < 0 {
+                error.get_or_insert(io::Error::from_raw_os_error(-result));
+                continue;
+            }
+            chunk.filled += result as usize;
+            if result == 0 || chunk.filled == chunk.len {
+                chunk.done = true;
+            } else {
+                self.queue_read(slot);
+            }
+        }
+        match error {
+            Some(error) => Err(error),
+            None => Ok(()),
+        }
+    }
+}
+
+impl Read for IoUringReader {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let Some(&front) = self.pending.front() else {
+            return Ok(0);
+        };
+
+        // returns right away when a completion is already waiting
+        while !self.slots[front].done {
+            // nothing left to wait for, the chunk's read failed on an earlier
+            // call
+            if self.ring.in_flight == 0 {
+                return Err(io::Error::other("io_uring read failed"));
+            }
+            self.ring.enter(1)?;
+            self.reap()?;
+        }
+
+        // an empty chunk means the file got shorter since it was opened,
+        // nothing after it is worth handing out
+        let filled = self.slots[front].filled;
+        if filled == 0 {
+            self.pending.clear();
+            return Ok(0);
+        }
+
+        let available = &self.slots[front].buffer[self.position..filled];
+        let count = available.len().min(buf.len());
+        buf[..count].copy_from_slice(&available[..count]);
+        self.position += count;
+
+        if self.position == filled {
+            self.pending.pop_front();
+            self.position = 0;
+            self.start_chunk(front);
+            self.ring.enter(0)?;
+        }
+        Ok(count)
+    }
+}
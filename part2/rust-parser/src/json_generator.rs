@@ -77,8 +77,8 @@ pub fn generate_json(
     let mut binary_file = std::fs::File::create(binary_file_name).expect("Unable to create file");
 
     let mut file = std::fs::File::create(output_file).expect("Unable to create file");
-    let mut line = format!("{{\"pairs\":[\n");
-    file.write(line.as_bytes())
+    let mut line = "{\"pairs\":[\n".to_string();
+    file.write_all(line.as_bytes())
         .expect("Unable to write data to file");
 
     let sum_coefficient = 1.0 / count as f64;
@@ -99,8 +99,8 @@ pub fn generate_json(
 
         total += distance * sum_coefficient;
     }
-    line = format!("]}}\n");
-    file.write(line.as_bytes())?;
+    line = "]}\n".to_string();
+    file.write_all(line.as_bytes())?;
     binary_file.write_all(&total.to_be_bytes())?;
 
     println!("Generated {} points", count);
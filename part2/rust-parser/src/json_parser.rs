@@ -1,8 +1,10 @@
 use std::error::Error;
 use std::fs::File;
-use std::io::{BufReader, Read};
+use std::io::{self, BufReader, Read};
 use std::mem;
 
+#[cfg(target_os = "linux")]
+use crate::io_uring_reader::{self, IoUringReader};
 use crate::listing_0065_haversine_formula::reference_haversine;
 use crate::naive_profiler;
 
@@ -113,7 +115,7 @@ impl ParsingData {
     }
 
     fn update_obj(&mut self) {
-        if self.key.len() == 0 || self.val.len() == 0 {
+        if self.key.is_empty() || self.val.is_empty() {
             return;
         }
 
@@ -124,7 +126,46 @@ impl ParsingData {
     }
 }
 
-pub fn parse(input_file: &str, validate: bool) -> Result<(), Box<dyn Error>> {
+// Where the parser gets the file's bytes from.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum InputSource {
+    // blocking reads through a BufReader
+    Read,
+    // io_uring with several chunk reads in flight, Linux only
+    IoUring,
+}
+
+impl InputSource {
+    pub fn parse(name: &str) -> Option<InputSource> {
+        match name {
+            "read" => Some(InputSource::Read),
+            "io_uring" => Some(InputSource::IoUring),
+            _ => None,
+        }
+    }
+
+    fn open(&self, file: File, input_file: &str) -> io::Result<Box<dyn Read>> {
+        match self {
+            InputSource::Read => Ok(Box::new(BufReader::new(file))),
+            #[cfg(target_os = "linux")]
+            InputSource::IoUring => Ok(Box::new(IoUringReader::open(
+                input_file,
+                io_uring_reader::CHUNK_SIZE,
+                io_uring_reader::QUEUE_DEPTH,
+            )?)),
+            #[cfg(not(target_os = "linux"))]
+            InputSource::IoUring => {
+                let _ = input_file;
+                Err(io::Error::new(
+                    io::ErrorKind::Unsupported,
+                    "io_uring is Linux only",
+                ))
+            }
+        }
+    }
+}
+
+pub fn parse(input_file: &str, validate: bool, source: InputSource) -> Result<(), Box<dyn Error>> {
     naive_profiler::start_profiling();
 
     // let start = naive_profiler::start_span();
@@ -137,7 +178,7 @@ pub fn parse(input_file: &str, validate: bool) -> Result<(), Box<dyn Error>> {
     let f = File::open(input_file)?;
     let file_size = f.metadata().unwrap().len();
     println!("File size: {}", file_size);
-    let mut reader = BufReader::new(f);
+    let mut reader = source.open(f, input_file)?;
 
     // const BUFFER_SIZE: usize = 8096;
     const BUFFER_SIZE: usize = 1024 * 1024 * 4;
@@ -189,7 +230,7 @@ pub fn parse(input_file: &str, validate: bool) -> Result<(), Box<dyn Error>> {
                 }
                 '"' => {
                     let state_update = naive_profiler::start_span("State");
-                    if parse_data.key.len() == 0 {
+                    if parse_data.key.is_empty() {
                         parse_data.state = State::Key;
                     } else if parse_data.state == State::Key {
                         parse_data.state = State::Value;
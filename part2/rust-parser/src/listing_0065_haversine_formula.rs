@@ -1,7 +1,7 @@
 const EARTH_RADIUS: f64 = 6372.8;
 
 fn degree_to_radian(degree: f64) -> f64 {
-    degree * 0.01745329251994329577
+    degree * 0.017_453_292_519_943_295
 }
 
 pub fn reference_haversine(lon1: f64, lat1: f64, lon2: f64, lat2: f64) -> f64 {
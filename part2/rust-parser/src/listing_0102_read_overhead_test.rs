@@ -1,8 +1,15 @@
+use crate::read_strategies::{
+    read_in_chunks, run_strategy_test, AlignedBuffer, Cache, ReadStrategy,
+};
 use crate::repetition_tester::repetition_tester::RepetitionTester;
-use std::cell::RefCell;
 use std::fs::File;
-use std::io::Read;
-use std::rc::Rc;
+
+// the buffered read test's chunk, and the range the sweep covers
+const BUFFER_SIZE: usize = 8192;
+const MIN_CHUNK_SIZE: usize = 4 * 1024;
+const MAX_CHUNK_SIZE: usize = 64 * 1024 * 1024;
+// a chunk size counts as good enough within this fraction of the best one
+const BANDWIDTH_TOLERANCE: f64 = 0.05;
 
 pub struct TestParams {
     pub file_name: &'static str,
@@ -10,116 +17,124 @@ pub struct TestParams {
     pub seconds_to_try: u64,
 }
 
-fn run_full_read_test(tester: &mut RepetitionTester, params: &TestParams) {
-    tester.start_test_wave(params.seconds_to_try, params.expected_bytes);
-
-    while tester.is_testing() {
-        let mut verify_file = File::open(params.file_name).unwrap();
-        let mut buffer = Vec::new();
-
-        tester.begin_time();
-        match verify_file.read_to_end(&mut buffer) {
-            Ok(_) => {}
-            Err(e) => {
-                tester.set_error(&format!("Error reading file: {:?}", e));
-            }
-        }
-        tester.end_time();
+pub(crate) fn get_test_params() -> TestParams {
+    let file_name = "haversine_data.json";
+    let file = File::open(file_name).unwrap();
+    let expected_bytes = file.metadata().unwrap().len();
+    TestParams {
+        file_name,
+        expected_bytes,
+        seconds_to_try: 3,
+    }
+}
 
-        tester.count_bytes(buffer.len() as u64);
+fn format_size(bytes: usize) -> String {
+    if bytes >= 1024 * 1024 {
+        format!("{}MB", bytes / (1024 * 1024))
+    } else {
+        format!("{}KB", bytes / 1024)
     }
 }
 
-fn run_buffered_read_test(tester: &mut RepetitionTester, params: &TestParams) {
-    tester.start_test_wave(params.seconds_to_try, params.expected_bytes);
+// The buffered read test at every power of two chunk size from 4KB to 64MB,
+// one second per size, then the sizes side by side. The smallest chunk within
+// BANDWIDTH_TOLERANCE of the fastest is the one worth using, bigger ones only
+// cost memory.
+fn run_chunk_size_sweep(params: &TestParams) {
+    let sweep_params = TestParams {
+        file_name: params.file_name,
+        expected_bytes: params.expected_bytes,
+        seconds_to_try: 1,
+    };
 
-    while tester.is_testing() {
-        let mut reader = File::open(params.file_name).unwrap();
-        const BUFFER_SIZE: usize = 8096;
-        let mut buffer = [0_u8; BUFFER_SIZE];
-        let mut total_bytes_read = 0;
+    // big enough for the biggest chunk, the reads only ever use its start
+    let mut buffer = AlignedBuffer::new((params.expected_bytes as usize).max(MAX_CHUNK_SIZE));
+    let mut results = Vec::new();
+    let mut chunk_size = MIN_CHUNK_SIZE;
+    while chunk_size <= MAX_CHUNK_SIZE {
+        println!(
+            "\n----- Buffered Read Test ({}) -----",
+            format_size(chunk_size)
+        );
+        let strategy = ReadStrategy::BufferedRead { chunk_size };
+        let mut tester = RepetitionTester::new();
+        run_strategy_test(
+            &mut tester,
+            &sweep_params,
+            strategy,
+            Cache::Warm,
+            &mut buffer,
+        );
 
-        tester.begin_time();
-        let mut count = reader.read(&mut buffer).unwrap();
-        total_bytes_read += count;
-        while count > 0 {
-            count = reader.read(&mut buffer).unwrap();
-            total_bytes_read += count;
-        }
-        tester.end_time();
-        tester.count_bytes(total_bytes_read as u64);
+        // counted on one more read outside the wave, the calls don't change
+        // from run to run
+        let mut file = File::open(params.file_name).unwrap();
+        let (_, read_calls) =
+            read_in_chunks(&mut file, &mut buffer.as_mut_slice()[..chunk_size]).unwrap();
+        results.push((chunk_size, tester.min_bandwidth(), read_calls));
+        chunk_size *= 2;
     }
-}
-
-fn run_read_file_to_string_test(tester: &mut RepetitionTester, params: &TestParams) {
-    tester.start_test_wave(params.seconds_to_try, params.expected_bytes);
 
-    while tester.is_testing() {
-        tester.begin_time();
-        let str = std::fs::read_to_string(params.file_name).unwrap();
-        tester.end_time();
-        tester.count_bytes(str.len() as u64);
+    println!("\n===== Buffered Read Chunk Sizes =====");
+    println!("Chunk Size, GB/s, Read Calls, Bytes Per Call");
+    for (chunk_size, bandwidth, read_calls) in &results {
+        println!(
+            "{}, {:.2}, {}, {:.0}",
+            format_size(*chunk_size),
+            bandwidth,
+            read_calls,
+            params.expected_bytes as f64 / (*read_calls).max(1) as f64
+        );
     }
-}
 
-pub(crate) fn get_test_params() -> TestParams {
-    let file_name = "haversine_data.json";
-    let file = File::open(file_name).unwrap();
-    let expected_bytes = file.metadata().unwrap().len();
-    TestParams {
-        file_name,
-        expected_bytes,
-        seconds_to_try: 3,
+    let best = results
+        .iter()
+        .map(|(_, bandwidth, _)| *bandwidth)
+        .fold(0.0, f64::max);
+    if let Some((chunk_size, bandwidth, _)) = results
+        .iter()
+        .find(|(_, bandwidth, _)| *bandwidth >= best * (1.0 - BANDWIDTH_TOLERANCE))
+    {
+        println!(
+            "\nSmallest chunk within {:.0}% of the best {:.2} GB/s: {} ({:.2} GB/s)",
+            BANDWIDTH_TOLERANCE * 100.0,
+            best,
+            format_size(*chunk_size),
+            bandwidth
+        );
     }
 }
 
-pub fn run() {
+pub fn run(sweep: bool) {
     println!("Listing 1-2: Read Overhead Test");
     let params = get_test_params();
+    if sweep {
+        run_chunk_size_sweep(&params);
+        return;
+    }
 
-    let testers = vec![
-        Rc::new(RefCell::new(RepetitionTester::new())),
-        Rc::new(RefCell::new(RepetitionTester::new())),
-        Rc::new(RefCell::new(RepetitionTester::new())),
-    ];
-
-    let mut test_functions: Vec<(&str, Box<dyn FnMut()>)> = vec![
+    // the listing's three tests, run from the read strategy table
+    let tests = [
         (
             "Buffered Read Test",
-            Box::new({
-                let tester = Rc::clone(&testers[1]);
-                let params = &params;
-                move || {
-                    run_buffered_read_test(&mut *tester.borrow_mut(), params);
-                }
-            }),
-        ),
-        (
-            "Full Read Test",
-            Box::new({
-                let tester = Rc::clone(&testers[0]);
-                let params = &params;
-                move || {
-                    run_full_read_test(&mut *tester.borrow_mut(), params);
-                }
-            }),
-        ),
-        (
-            "Read To String Test",
-            Box::new({
-                let tester = Rc::clone(&testers[2]);
-                let params = &params;
-                move || {
-                    run_read_file_to_string_test(&mut *tester.borrow_mut(), params);
-                }
-            }),
+            ReadStrategy::BufferedRead {
+                chunk_size: BUFFER_SIZE,
+            },
         ),
+        ("Full Read Test", ReadStrategy::ReadToEnd),
+        ("Read To String Test", ReadStrategy::ReadToString),
+    ];
+    let mut testers = [
+        RepetitionTester::new(),
+        RepetitionTester::new(),
+        RepetitionTester::new(),
     ];
+    let mut buffer = AlignedBuffer::new(params.expected_bytes as usize);
 
     loop {
-        for (test_name, test_function) in test_functions.iter_mut() {
+        for ((test_name, strategy), tester) in tests.iter().zip(testers.iter_mut()) {
             println!("\n----- {} -----", test_name);
-            test_function();
+            run_strategy_test(tester, &params, *strategy, Cache::Warm, &mut buffer);
         }
     }
 }
@@ -34,7 +34,7 @@ fn run_full_read_test(tester: &mut RepetitionTester, params: &TestParams) {
 
 fn run_buffered_read_test(tester: &mut RepetitionTester, params: &TestParams) {
     tester.start_test_wave(params.seconds_to_try, params.expected_bytes);
-    const BUFFER_SIZE: usize = 8096 * 100;
+    const BUFFER_SIZE: usize = 8192 * 100;
     let mut buffer = [0_u8; BUFFER_SIZE];
 
     while tester.is_testing() {
@@ -75,23 +75,26 @@ fn get_test_params() -> TestParams {
     }
 }
 
+// a test name and the closure running its wave
+type NamedTest<'a> = (&'static str, Box<dyn FnMut() + 'a>);
+
 pub fn run() {
     let params = get_test_params();
 
-    let testers = vec![
+    let testers = [
         Rc::new(RefCell::new(RepetitionTester::new())),
         Rc::new(RefCell::new(RepetitionTester::new())),
         Rc::new(RefCell::new(RepetitionTester::new())),
     ];
 
-    let mut test_functions: Vec<(&str, Box<dyn FnMut()>)> = vec![
+    let mut test_functions: Vec<NamedTest<'_>> = vec![
         (
             "Buffered Read Test",
             Box::new({
                 let tester = Rc::clone(&testers[1]);
                 let params = &params;
                 move || {
-                    run_buffered_read_test(&mut *tester.borrow_mut(), params);
+                    run_buffered_read_test(&mut tester.borrow_mut(), params);
                 }
             }),
         ),
@@ -101,7 +104,7 @@ pub fn run() {
                 let tester = Rc::clone(&testers[0]);
                 let params = &params;
                 move || {
-                    run_full_read_test(&mut *tester.borrow_mut(), params);
+                    run_full_read_test(&mut tester.borrow_mut(), params);
                 }
             }),
         ),
@@ -111,7 +114,7 @@ pub fn run() {
                 let tester = Rc::clone(&testers[2]);
                 let params = &params;
                 move || {
-                    run_read_file_to_string_test(&mut *tester.borrow_mut(), params);
+                    run_read_file_to_string_test(&mut tester.borrow_mut(), params);
                 }
             }),
         ),
@@ -2,12 +2,11 @@ use std::cell::RefCell;
 use std::ptr;
 use std::rc::Rc;
 
-use libc::{MAP_ANON, MAP_PRIVATE, mmap, PROT_READ, PROT_WRITE, size_t};
+use libc::{mmap, size_t, MAP_ANON, MAP_PRIVATE, PROT_READ, PROT_WRITE};
 
 use crate::repetition_tester::repetition_tester::RepetitionTester;
 
 struct TestParams {
-    file_name: &'static str,
     expected_bytes: u64,
     seconds_to_try: u64,
     buffer: *mut u8,
@@ -19,12 +18,9 @@ fn write_all_bytes(tester: &mut RepetitionTester, params: &TestParams) {
     // let buffer = params.buffer;
     let buffer = unsafe { std::slice::from_raw_parts_mut(params.buffer, total_size) };
     while tester.is_testing() {
-        let mut i = 0;
         tester.begin_time();
-        for i in 0..total_size {
-            unsafe {
-                buffer[i] = i as u8;
-            }
+        for (i, byte) in buffer.iter_mut().enumerate() {
+            *byte = i as u8;
         }
         // while i < total_size {
         //     unsafe {
@@ -38,42 +34,40 @@ fn write_all_bytes(tester: &mut RepetitionTester, params: &TestParams) {
     }
 }
 
+// a test name and the closure running its wave
+type NamedTest<'a> = (&'static str, Box<dyn FnMut() + 'a>);
+
 pub fn run() {
     let total_size = 1024 * 1024 * 1024;
     let addr = unsafe {
         mmap(
-            ptr::null_mut(),   // Address at which to start the mapping (nullptr lets the OS choose)
-            total_size,        // Number of bytes to map
+            ptr::null_mut(), // Address at which to start the mapping (nullptr lets the OS choose)
+            total_size,      // Number of bytes to map
             PROT_READ | PROT_WRITE, // Enable read and write access
             MAP_PRIVATE | MAP_ANON,
-            -1,                // File descriptor not used with MAP_ANON
-            0,                 // Offset not used with MAP_ANON
+            -1, // File descriptor not used with MAP_ANON
+            0,  // Offset not used with MAP_ANON
         )
     };
 
     let params = TestParams {
-        file_name: "test_file",
         expected_bytes: total_size as u64,
         seconds_to_try: 2,
         buffer: addr as *mut u8,
     };
 
-    let testers = vec![
-        Rc::new(RefCell::new(RepetitionTester::new())),
-    ];
+    let testers = [Rc::new(RefCell::new(RepetitionTester::new()))];
 
-    let mut test_functions: Vec<(&str, Box<dyn FnMut()>)> = vec![
-        (
-            "Write All Bytes Test",
-            Box::new({
-                let tester = Rc::clone(&testers[0]);
-                let params = &params;
-                move || {
-                    write_all_bytes(&mut *tester.borrow_mut(), params);
-                }
-            }),
-        ),
-    ];
+    let mut test_functions: Vec<NamedTest<'_>> = vec![(
+        "Write All Bytes Test",
+        Box::new({
+            let tester = Rc::clone(&testers[0]);
+            let params = &params;
+            move || {
+                write_all_bytes(&mut tester.borrow_mut(), params);
+            }
+        }),
+    )];
 
     loop {
         for (test_name, test_function) in test_functions.iter_mut() {
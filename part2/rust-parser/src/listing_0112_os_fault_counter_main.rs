@@ -1,7 +1,7 @@
 extern crate libc;
-use libc::{mmap, munmap, PROT_READ, PROT_WRITE, MAP_PRIVATE, MAP_ANON, MAP_FAILED};
-use std::ptr;
 use crate::perf_metrics::{get_page_faults, VirtualAddress};
+use libc::{mmap, munmap, MAP_ANON, MAP_FAILED, MAP_PRIVATE, PROT_READ, PROT_WRITE};
+use std::ptr;
 
 pub fn run() {
     let page_size = 4096 * 4;
@@ -15,12 +15,12 @@ pub fn run() {
 
         let addr = unsafe {
             mmap(
-                ptr::null_mut(),   // Address at which to start the mapping (nullptr lets the OS choose)
-                total_size,       // Number of bytes to map
+                ptr::null_mut(), // Address at which to start the mapping (nullptr lets the OS choose)
+                total_size,      // Number of bytes to map
                 PROT_READ | PROT_WRITE, // Enable read and write access
                 MAP_PRIVATE | MAP_ANON,
-                -1,                // File descriptor not used with MAP_ANON
-                0,                 // Offset not used with MAP_ANON
+                -1, // File descriptor not used with MAP_ANON
+                0,  // Offset not used with MAP_ANON
             )
         };
 
@@ -32,22 +32,26 @@ pub fn run() {
         let start_faults = get_page_faults(pid);
         unsafe {
             let byte_slice = std::slice::from_raw_parts_mut(addr as *mut u8, touch_size);
-            for i in 0..touch_size {
-                byte_slice[i] = i as u8; // Set each byte to 'i'.
+            for (i, byte) in byte_slice.iter_mut().enumerate() {
+                *byte = i as u8; // Set each byte to 'i'.
             }
         }
         let end_faults = get_page_faults(pid);
         let fault_count = end_faults - start_faults;
 
-        println!("{}, {}, {}, {}", page_count, touch_count, fault_count, fault_count - touch_count as i32);
+        println!(
+            "{}, {}, {}, {}",
+            page_count,
+            touch_count,
+            fault_count,
+            fault_count - touch_count as i32
+        );
         if fault_count > 0 {
             let vaddr = VirtualAddress::from_pointer(addr as usize + touch_size);
             vaddr.print();
         }
 
-        let result = unsafe {
-            munmap(addr, total_size)
-        };
+        let result = unsafe { munmap(addr, total_size) };
 
         if result != 0 {
             eprintln!("munmap failed");
@@ -1,18 +1,21 @@
 use std::env;
 use std::error::Error;
 
-use json_parser::parse;
+use json_parser::{parse, InputSource};
 
+#[cfg(target_os = "linux")]
+mod io_uring_reader;
 mod json_generator;
 mod json_parser;
 mod listing_0065_haversine_formula;
 mod listing_0102_read_overhead_test;
 mod listing_0106_mallocread_overhead_test;
+mod listing_0110_pagefault_overhead_test;
+mod listing_0112_os_fault_counter_main;
 pub mod naive_profiler;
 pub mod perf_metrics;
+mod read_strategies;
 pub mod repetition_tester;
-mod listing_0112_os_fault_counter_main;
-mod listing_0110_pagefault_overhead_test;
 
 fn main() -> Result<(), Box<dyn Error>> {
     let args: Vec<String> = env::args().collect();
@@ -26,13 +29,15 @@ fn main() -> Result<(), Box<dyn Error>> {
     match &args[1][..] {
         "run" => {
             if args.len() < 3 {
-                eprintln!("Usage: {} run <listing>", args[0]);
+                eprintln!("Usage: {} run <listing> [listing args]", args[0]);
                 std::process::exit(1);
             }
 
             match &args[2][..] {
                 "102" => {
-                    listing_0102_read_overhead_test::run();
+                    // `run 102 sweep` times the buffered read at every chunk size
+                    let sweep = args.get(3).map(|s| s == "sweep").unwrap_or(false);
+                    listing_0102_read_overhead_test::run(sweep);
                 }
                 "106" => {
                     listing_0106_mallocread_overhead_test::run();
@@ -43,6 +48,9 @@ fn main() -> Result<(), Box<dyn Error>> {
                 "110" => {
                     listing_0110_pagefault_overhead_test::run();
                 }
+                "read_strategies" => {
+                    read_strategies::run();
+                }
                 _ => {
                     eprintln!("Invalid listing");
                     std::process::exit(1);
@@ -79,7 +87,12 @@ fn main() -> Result<(), Box<dyn Error>> {
 
         "parse" => {
             let verify = args.get(3).map(|s| s == "true").unwrap_or(false);
-            parse(&args[2], verify)?;
+            // read (default) or io_uring
+            let source = args
+                .get(4)
+                .map(|s| InputSource::parse(s).expect("Invalid input source"))
+                .unwrap_or(InputSource::Read);
+            parse(&args[2], verify, source)?;
         }
         _ => {
             panic!("Invalid option");
@@ -1,12 +1,25 @@
 extern crate libc;
+#[cfg(target_os = "macos")]
 extern crate mach;
 
+#[cfg(target_os = "macos")]
 use std::mem;
 use std::time::Duration;
 
-use libc::{c_int, c_void, pid_t};
+use libc::pid_t;
+#[cfg(target_os = "macos")]
+use libc::{c_int, c_void};
+#[cfg(target_os = "macos")]
 use mach::mach_time::{mach_absolute_time, mach_timebase_info};
 
+// ticks * numer / denom = nanoseconds
+#[derive(Debug, Copy, Clone)]
+pub struct TimebaseInfo {
+    pub numer: u32,
+    pub denom: u32,
+}
+
+#[cfg(target_os = "macos")]
 #[repr(C)]
 #[repr(C)]
 struct ProcTaskInfo {
@@ -30,6 +43,7 @@ struct ProcTaskInfo {
     pti_priority: i32,          // task priority
 }
 
+#[cfg(target_os = "macos")]
 extern "C" {
     fn proc_pidinfo(
         pid: pid_t,
@@ -40,6 +54,7 @@ extern "C" {
     ) -> c_int;
 }
 
+#[cfg(target_os = "macos")]
 pub fn get_page_faults(pid: pid_t) -> i32 {
     let mut task_info = ProcTaskInfo {
         pti_virtual_size: 0,
@@ -84,28 +99,59 @@ pub fn get_page_faults(pid: pid_t) -> i32 {
     }
 }
 
-pub fn high_resolution_info() -> mach_timebase_info {
+// getrusage only reports on the calling process, the pid is kept for the
+// macOS signature
+#[cfg(not(target_os = "macos"))]
+pub fn get_page_faults(_pid: pid_t) -> i32 {
+    let mut usage: libc::rusage = unsafe { std::mem::zeroed() };
+    if unsafe { libc::getrusage(libc::RUSAGE_SELF, &mut usage) } == 0 {
+        (usage.ru_minflt + usage.ru_majflt) as i32
+    } else {
+        eprintln!("Failed to get process info");
+        -1
+    }
+}
+
+#[cfg(target_os = "macos")]
+pub fn high_resolution_info() -> TimebaseInfo {
     unsafe {
         let mut info = mach_timebase_info { numer: 0, denom: 0 };
         mach_timebase_info(&mut info);
-        info
+        TimebaseInfo {
+            numer: info.numer,
+            denom: info.denom,
+        }
     }
 }
 
+#[cfg(target_os = "macos")]
 pub fn high_resolution_time() -> u64 {
     unsafe { mach_absolute_time() }
 }
 
-pub fn high_resolution_clock() -> Duration {
+// clock_gettime already counts in nanoseconds
+#[cfg(not(target_os = "macos"))]
+pub fn high_resolution_info() -> TimebaseInfo {
+    TimebaseInfo { numer: 1, denom: 1 }
+}
+
+#[cfg(not(target_os = "macos"))]
+pub fn high_resolution_time() -> u64 {
+    let mut time = libc::timespec {
+        tv_sec: 0,
+        tv_nsec: 0,
+    };
     unsafe {
-        let time = mach_absolute_time();
-        let mut info = mach_timebase_info { numer: 0, denom: 0 };
-        mach_timebase_info(&mut info);
-        let nanos = time * info.numer as u64 / info.denom as u64;
-        Duration::from_nanos(nanos)
+        libc::clock_gettime(libc::CLOCK_MONOTONIC_RAW, &mut time);
     }
+    time.tv_sec as u64 * 1_000_000_000 + time.tv_nsec as u64
 }
 
+pub fn high_resolution_clock() -> Duration {
+    let info = high_resolution_info();
+    let nanos = high_resolution_time() * info.numer as u64 / info.denom as u64;
+    Duration::from_nanos(nanos)
+}
 
 #[derive(Debug)]
 pub struct VirtualAddress {
@@ -128,12 +174,9 @@ impl VirtualAddress {
     }
 
     pub fn print(&self) {
-        println!("{} | {} | {} | {} | {}",
-                 self.l1_index,
-                 self.l2_index,
-                 self.l3_index,
-                 self.l4_index,
-                 self.offset
+        println!(
+            "{} | {} | {} | {} | {}",
+            self.l1_index, self.l2_index, self.l3_index, self.l4_index, self.offset
         );
     }
 }
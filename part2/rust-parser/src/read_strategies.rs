@@ -0,0 +1,413 @@
+use std::alloc::{alloc, dealloc, Layout};
+use std::fs::{File, OpenOptions};
+use std::io::{self, Read};
+#[cfg(target_os = "linux")]
+use std::os::unix::fs::OpenOptionsExt;
+use std::os::unix::io::AsRawFd;
+
+use memmap2::Mmap;
+
+#[cfg(target_os = "linux")]
+use crate::io_uring_reader::{self, Ring};
+use crate::listing_0102_read_overhead_test::{get_test_params, TestParams};
+use crate::repetition_tester::repetition_tester::RepetitionTester;
+
+// bytes per read call for the chunked strategies, a multiple of every block
+// size direct I/O could ask for
+const CHUNK_SIZE: usize = 1024 * 1024;
+// chunks handed to one readv call
+const IOVEC_COUNT: usize = 16;
+// direct I/O wants the buffer, offsets and sizes on logical block boundaries,
+// a page covers all the devices we run on
+const DIRECT_ALIGNMENT: usize = 4096;
+
+// io_uring is Linux only, everywhere else there is never a ring to read with
+#[cfg(not(target_os = "linux"))]
+enum Ring {}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub(crate) enum ReadStrategy {
+    // listing 102's three, which runs them from this table
+    ReadToEnd,
+    // File::read into the first `chunk_size` bytes of the buffer until EOF
+    BufferedRead { chunk_size: usize },
+    ReadToString,
+    // map the file and copy it into the buffer
+    Mmap,
+    // positional chunks straight to their place in the buffer, no file offset
+    Pread,
+    // around the page cache, O_DIRECT on Linux and F_NOCACHE on macOS
+    Direct,
+    // IOVEC_COUNT chunks per call
+    Readv,
+    // io_uring_reader's chunk reads in flight, straight to their place in
+    // the buffer
+    IoUring,
+}
+
+const STRATEGIES: [ReadStrategy; 8] = [
+    ReadStrategy::ReadToEnd,
+    ReadStrategy::BufferedRead {
+        chunk_size: CHUNK_SIZE,
+    },
+    ReadStrategy::ReadToString,
+    ReadStrategy::Mmap,
+    ReadStrategy::Pread,
+    ReadStrategy::Direct,
+    ReadStrategy::Readv,
+    ReadStrategy::IoUring,
+];
+
+impl ReadStrategy {
+    pub(crate) fn name(&self) -> &'static str {
+        match self {
+            ReadStrategy::ReadToEnd => "read_to_end",
+            ReadStrategy::BufferedRead { .. } => "read loop",
+            ReadStrategy::ReadToString => "read_to_string",
+            ReadStrategy::Mmap => "mmap",
+            ReadStrategy::Pread => "pread",
+            ReadStrategy::Direct => "direct",
+            ReadStrategy::Readv => "readv",
+            ReadStrategy::IoUring => "io_uring",
+        }
+    }
+
+    // Opened outside the timed block, except for read_to_string which opens
+    // the file itself.
+    fn open(&self, file_name: &str) -> io::Result<File> {
+        match self {
+            ReadStrategy::Direct => open_direct(file_name),
+            _ => File::open(file_name),
+        }
+    }
+
+    // io_uring's ring, set up once per wave outside the timed block like the
+    // file is opened, None for every other strategy.
+    #[cfg(target_os = "linux")]
+    fn ring(&self) -> io::Result<Option<Ring>> {
+        match self {
+            ReadStrategy::IoUring => Ring::new(io_uring_reader::QUEUE_DEPTH).map(Some),
+            _ => Ok(None),
+        }
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    fn ring(&self) -> io::Result<Option<Ring>> {
+        Ok(None)
+    }
+
+    // Reads the whole file into `buffer` (or maps it), returns the bytes read.
+    fn read(
+        &self,
+        file: &mut File,
+        file_name: &str,
+        buffer: &mut AlignedBuffer,
+        ring: Option<&mut Ring>,
+    ) -> io::Result<u64> {
+        let (pointer, capacity) = (buffer.pointer, buffer.capacity);
+        let bytes = buffer.as_mut_slice();
+        match self {
+            // a fresh Vec every run, growing as it goes, what listing 106's
+            // preallocated buffers are measured against
+            ReadStrategy::ReadToEnd => {
+                let mut data = Vec::new();
+                file.read_to_end(&mut data).map(|count| count as u64)
+            }
+            ReadStrategy::BufferedRead { chunk_size } => {
+                // one chunk's worth of the buffer, reused for every read
+                let chunk_size = (*chunk_size).min(bytes.len());
+                read_in_chunks(file, &mut bytes[..chunk_size]).map(|(bytes, _)| bytes)
+            }
+            ReadStrategy::ReadToString => {
+                std::fs::read_to_string(file_name).map(|text| text.len() as u64)
+            }
+            // copied out like every read copies out of the page cache, touching
+            // a byte per page would only time mapping the pages in
+            ReadStrategy::Mmap => {
+                let map = unsafe { Mmap::map(&*file)? };
+                let size = map.len().min(bytes.len());
+                bytes[..size].copy_from_slice(&map[..size]);
+                Ok(size as u64)
+            }
+            ReadStrategy::Pread => {
+                let fd = file.as_raw_fd();
+                let mut offset = 0;
+                while offset < bytes.len() {
+                    let size = CHUNK_SIZE.min(bytes.len() - offset);
+                    let count = unsafe {
+                        libc::pread(
+                            fd,
+                            bytes[offset..].as_mut_ptr() as *mut libc::c_void,
+                            size,
+                            offset as libc::off_t,
+                        )
+                    };
+                    if count < 0 {
+                        return Err(io::Error::last_os_error());
+                    }
+                    if count == 0 {
+                        break;
+                    }
+                    offset += count as usize;
+                }
+                Ok(offset as u64)
+            }
+            // the buffer is rounded up to whole blocks, so every read asks for
+            // an aligned size even past the end of the file
+            ReadStrategy::Direct => {
+                let mut total_bytes_read = 0;
+                loop {
+                    let size = CHUNK_SIZE.min(capacity - total_bytes_read);
+                    if size == 0 {
+                        break;
+                    }
+                    let count = unsafe {
+                        libc::read(
+                            file.as_raw_fd(),
+                            pointer.add(total_bytes_read) as *mut libc::c_void,
+                            size,
+                        )
+                    };
+                    if count < 0 {
+                        return Err(io::Error::last_os_error());
+                    }
+                    if count == 0 {
+                        break;
+                    }
+                    total_bytes_read += count as usize;
+                }
+                Ok(total_bytes_read as u64)
+            }
+            ReadStrategy::Readv => {
+                let fd = file.as_raw_fd();
+                let mut offset = 0;
+                while offset < bytes.len() {
+                    let iovecs: Vec<libc::iovec> = bytes[offset..]
+                        .chunks_mut(CHUNK_SIZE)
+                        .take(IOVEC_COUNT)
+                        .map(|chunk| libc::iovec {
+                            iov_base: chunk.as_mut_ptr() as *mut libc::c_void,
+                            iov_len: chunk.len(),
+                        })
+                        .collect();
+                    let count =
+                        unsafe { libc::readv(fd, iovecs.as_ptr(), iovecs.len() as libc::c_int) };
+                    if count < 0 {
+                        return Err(io::Error::last_os_error());
+                    }
+                    if count == 0 {
+                        break;
+                    }
+                    offset += count as usize;
+                }
+                Ok(offset as u64)
+            }
+            ReadStrategy::IoUring => match ring {
+                #[cfg(target_os = "linux")]
+                Some(ring) => ring.read_file(file, bytes, io_uring_reader::CHUNK_SIZE),
+                _ => Err(io::Error::new(
+                    io::ErrorKind::Unsupported,
+                    "io_uring is Linux only",
+                )),
+            },
+        }
+    }
+}
+
+// Reads `file` to the end through `chunk`, returns the bytes read and the read
+// calls that returned data, every File::read is one read syscall. The last
+// call finding EOF isn't counted.
+pub(crate) fn read_in_chunks(file: &mut File, chunk: &mut [u8]) -> io::Result<(u64, u64)> {
+    let mut total_bytes_read = 0;
+    let mut read_calls = 0;
+    loop {
+        let count = file.read(chunk)?;
+        if count == 0 {
+            break;
+        }
+        read_calls += 1;
+        total_bytes_read += count;
+    }
+    Ok((total_bytes_read as u64, read_calls))
+}
+
+#[cfg(target_os = "linux")]
+fn open_direct(file_name: &str) -> io::Result<File> {
+    OpenOptions::new()
+        .read(true)
+        .custom_flags(libc::O_DIRECT)
+        .open(file_name)
+}
+
+#[cfg(target_os = "macos")]
+fn open_direct(file_name: &str) -> io::Result<File> {
+    let file = OpenOptions::new().read(true).open(file_name)?;
+    if unsafe { libc::fcntl(file.as_raw_fd(), libc::F_NOCACHE, 1) } != 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(file)
+}
+
+// Drops the file's pages from the page cache so the next read has to go to
+// the disk. Clean pages only, which is all a file we only read has.
+#[cfg(target_os = "linux")]
+fn evict_from_page_cache(file_name: &str) -> io::Result<()> {
+    let file = File::open(file_name)?;
+    let result = unsafe { libc::posix_fadvise(file.as_raw_fd(), 0, 0, libc::POSIX_FADV_DONTNEED) };
+    if result != 0 {
+        return Err(io::Error::from_raw_os_error(result));
+    }
+    Ok(())
+}
+
+// macOS has no way to drop one file from the cache short of `purge`
+#[cfg(target_os = "macos")]
+fn evict_from_page_cache(_file_name: &str) -> io::Result<()> {
+    Err(io::Error::new(
+        io::ErrorKind::Unsupported,
+        "evicting a single file isn't supported here",
+    ))
+}
+
+// Page aligned and rounded up to whole pages, what direct I/O needs. Every
+// strategy reads into the same one so none of them pays for its page faults.
+pub(crate) struct AlignedBuffer {
+    pointer: *mut u8,
+    size: usize,
+    capacity: usize,
+}
+
+impl AlignedBuffer {
+    pub(crate) fn new(size: usize) -> AlignedBuffer {
+        let capacity = size
+            .next_multiple_of(DIRECT_ALIGNMENT)
+            .max(DIRECT_ALIGNMENT);
+        let layout = Layout::from_size_align(capacity, DIRECT_ALIGNMENT).unwrap();
+        let pointer = unsafe { alloc(layout) };
+        assert!(!pointer.is_null(), "Allocation failed");
+        let buffer = AlignedBuffer {
+            pointer,
+            size,
+            capacity,
+        };
+        // fault it in now, outside any timed read
+        unsafe {
+            std::ptr::write_bytes(pointer, 0, capacity);
+        }
+        buffer
+    }
+
+    pub(crate) fn as_mut_slice(&mut self) -> &mut [u8] {
+        unsafe { std::slice::from_raw_parts_mut(self.pointer, self.size) }
+    }
+}
+
+impl Drop for AlignedBuffer {
+    fn drop(&mut self) {
+        let layout = Layout::from_size_align(self.capacity, DIRECT_ALIGNMENT).unwrap();
+        unsafe { dealloc(self.pointer, layout) };
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub(crate) enum Cache {
+    // read once before the wave, every run is served from memory
+    Warm,
+    // evicted before every run, every run goes to the disk
+    Cold,
+}
+
+impl Cache {
+    fn name(&self) -> &'static str {
+        match self {
+            Cache::Warm => "warm",
+            Cache::Cold => "cold",
+        }
+    }
+}
+
+pub(crate) fn run_strategy_test(
+    tester: &mut RepetitionTester,
+    params: &TestParams,
+    strategy: ReadStrategy,
+    cache: Cache,
+    buffer: &mut AlignedBuffer,
+) {
+    tester.start_test_wave(params.seconds_to_try, params.expected_bytes);
+    if cache == Cache::Warm {
+        let _ = std::fs::read(params.file_name);
+    }
+    let mut ring = match strategy.ring() {
+        Ok(ring) => ring,
+        Err(e) => {
+            tester.set_error(&format!("Error setting up io_uring: {:?}", e));
+            return;
+        }
+    };
+
+    while tester.is_testing() {
+        if cache == Cache::Cold {
+            if let Err(e) = evict_from_page_cache(params.file_name) {
+                tester.set_error(&format!("Error evicting file: {:?}", e));
+                continue;
+            }
+        }
+        let mut file = match strategy.open(params.file_name) {
+            Ok(file) => file,
+            Err(e) => {
+                tester.set_error(&format!("Error opening file: {:?}", e));
+                continue;
+            }
+        };
+
+        tester.begin_time();
+        let result = strategy.read(&mut file, params.file_name, buffer, ring.as_mut());
+        tester.end_time();
+
+        match result {
+            Ok(bytes) => tester.count_bytes(bytes),
+            Err(e) => tester.set_error(&format!("Error reading file: {:?}", e)),
+        }
+    }
+}
+
+// Every read strategy on haversine_data.json, first with the file already in
+// the page cache and then evicted before every read.
+pub fn run() {
+    println!("Read Strategies");
+    let params = get_test_params();
+    let mut buffer = AlignedBuffer::new(params.expected_bytes as usize);
+
+    let mut results = Vec::new();
+    for cache in [Cache::Warm, Cache::Cold] {
+        for strategy in STRATEGIES {
+            println!("\n----- {} ({}) -----", strategy.name(), cache.name());
+            let mut tester = RepetitionTester::new();
+            run_strategy_test(&mut tester, &params, strategy, cache, &mut buffer);
+            results.push((strategy, cache, tester.min_bandwidth()));
+        }
+    }
+
+    println!(
+        "\n===== Read Strategies ({} bytes) =====",
+        params.expected_bytes
+    );
+    println!("Strategy, Warm GB/s, Cold GB/s");
+    for strategy in STRATEGIES {
+        let bandwidth = |cache: Cache| {
+            results
+                .iter()
+                .find(|(s, c, _)| *s == strategy && *c == cache)
+                .map(|(_, _, bandwidth)| *bandwidth)
+                .filter(|bandwidth| *bandwidth > 0.0)
+                .map(|bandwidth| format!("{:.2}", bandwidth))
+                .unwrap_or("-".to_string())
+        };
+        println!(
+            "{}, {}, {}",
+            strategy.name(),
+            bandwidth(Cache::Warm),
+            bandwidth(Cache::Cold)
+        );
+    }
+}
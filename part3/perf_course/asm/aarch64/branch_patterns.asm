@@ -0,0 +1,21 @@
+.text
+.global _ConditionalNOP
+
+; Parameters:
+; x0 - the number of bytes in the pattern buffer
+; x1 - the pattern buffer, one byte per branch
+;
+; The branch over the nop is taken when the low bit of the pattern byte is set,
+; so the pattern buffer decides what the predictor has to learn.
+
+_ConditionalNOP:
+    eor    x8, x8, x8
+loop:
+    ldrb   w9, [x1, x8]
+    add    x8, x8, #0x1
+    tbnz   w9, #0, skip
+    nop
+skip:
+    cmp    x8, x0
+    b.lo   loop
+    ret
@@ -8,6 +8,8 @@
 .global _Read_32x3
 .global _Read_64x3
 .global _Read_128x3
+.global _Read_256x3
+.global _Read_512x3
 
 _Read_4x3:
 loop1:
@@ -88,3 +90,26 @@ loop4:
     subs x0, x0, 384
     bhi loop4
     ret
+
+_Read_256x3:
+loop10:
+    ldp Q0, Q1, [x1]
+    ldp Q0, Q1, [x1, #256]
+    ldp Q0, Q1, [x1, #512]
+    subs x0, x0, 768
+    bhi loop10
+    ret
+
+; ldp only reaches 1008 bytes, the third read goes through x4
+_Read_512x3:
+    add x4, x1, #1024
+loop11:
+    ldp Q0, Q1, [x1]
+    ldp Q2, Q3, [x1, #32]
+    ldp Q0, Q1, [x1, #512]
+    ldp Q2, Q3, [x1, #544]
+    ldp Q0, Q1, [x4]
+    ldp Q2, Q3, [x4, #32]
+    subs x0, x0, 1536
+    bhi loop11
+    ret
@@ -1,5 +1,6 @@
 .text
 .global _CacheSetTest
+.global _CacheStrideTest
 
 _CacheSetTest:
 ;    ldr x6, =0x7F00
@@ -27,4 +28,30 @@ outter_loop3:
 
     subs x0, x0, 1
     bhi outter_loop3
-    ret
\ No newline at end of file
+    ret
+
+
+; Parameters:
+; x0 - the number of passes over the lines
+; x1 - the buffer address
+; x2 - the number of lines touched per pass
+; x3 - the stride between two touched lines
+;
+; Tmp registers:
+; x4 - the current line address
+; x5 - lines left in this pass
+
+_CacheStrideTest:
+outter_loop4:
+    mov x5, x2
+    mov x4, x1
+
+    inner_loop4:
+        ldr Q0, [x4]
+        add x4, x4, x3
+        subs x5, x5, 1
+        bhi inner_loop4
+
+    subs x0, x0, 1
+    bhi outter_loop4
+    ret
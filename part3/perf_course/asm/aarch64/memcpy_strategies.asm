@@ -0,0 +1,59 @@
+.text
+.global _CopySIMD
+.global _CopyNT
+
+; Parameters, for every kernel:
+; x0 - the number of passes
+; x1 - destination, any alignment
+; x2 - source, any alignment
+; x3 - bytes to copy, a multiple of 64 and at least 128
+;
+; Tmp registers:
+; x4 - the current destination
+; x5 - the current source
+; x6 - bytes left in this pass
+;
+; There is no rep movsb, the closest thing (the FEAT_MOPS cpy instructions) is
+; missing on the M1/M2, so only the SIMD and non-temporal copies exist here.
+
+_CopySIMD:
+outter_loop1:
+    mov x4, x1
+    mov x5, x2
+    mov x6, x3
+
+    inner_loop1:
+        ldp Q0, Q1, [x5]
+        ldp Q2, Q3, [x5, #32]
+        stp Q0, Q1, [x4]
+        stp Q2, Q3, [x4, #32]
+        add x4, x4, 64
+        add x5, x5, 64
+        subs x6, x6, 64
+        bhi inner_loop1
+
+    subs x0, x0, 1
+    bhi outter_loop1
+    ret
+
+; stnp has no alignment requirement on normal memory, so unlike x86 the loop
+; doesn't need to line the destination up first
+_CopyNT:
+outter_loop2:
+    mov x4, x1
+    mov x5, x2
+    mov x6, x3
+
+    inner_loop2:
+        ldnp Q0, Q1, [x5]
+        ldnp Q2, Q3, [x5, #32]
+        stnp Q0, Q1, [x4]
+        stnp Q2, Q3, [x4, #32]
+        add x4, x4, 64
+        add x5, x5, 64
+        subs x6, x6, 64
+        bhi inner_loop2
+
+    subs x0, x0, 1
+    bhi outter_loop2
+    ret
@@ -0,0 +1,26 @@
+.text
+.global _ReadOffsets
+
+; Parameters:
+; x0 - the number of loads, a multiple of 4
+; x1 - the buffer address
+; x2 - a table of 32 bit offsets into the buffer, one per load
+;
+; The loads don't depend on each other, so how fast they go is down to how many
+; lines are already on their way, which is what the prefetchers decide. The
+; offset table itself is read sequentially and stays ahead of them.
+
+_ReadOffsets:
+    cbz x0, done
+loop1:
+    ldp w4, w5, [x2]
+    ldp w6, w7, [x2, #8]
+    ldr x8, [x1, w4, uxtw]
+    ldr x8, [x1, w5, uxtw]
+    ldr x8, [x1, w6, uxtw]
+    ldr x8, [x1, w7, uxtw]
+    add x2, x2, 16
+    subs x0, x0, 4
+    bhi loop1
+done:
+    ret
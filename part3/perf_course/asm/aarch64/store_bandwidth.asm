@@ -0,0 +1,204 @@
+.text
+.global _Write_8
+.global _Write_16
+.global _Write_32
+.global _Write_64
+.global _WriteNT_8
+.global _WriteNT_16
+.global _WriteNT_32
+.global _WriteNT_64
+.global _WriteFill
+
+; Parameters, for every kernel:
+; x0 - the number of passes over the region
+; x1 - the region address, 64 byte aligned
+; x2 - the region size, a multiple of 64
+;
+; Tmp registers:
+; x4 - the current address
+; x5 - bytes left in this pass
+;
+; Every inner iteration writes 64 bytes of zeros.
+
+_Write_8:
+outter_loop1:
+    mov x5, x2
+    mov x4, x1
+
+    inner_loop1:
+        str xzr, [x4]
+        str xzr, [x4, #8]
+        str xzr, [x4, #16]
+        str xzr, [x4, #24]
+        str xzr, [x4, #32]
+        str xzr, [x4, #40]
+        str xzr, [x4, #48]
+        str xzr, [x4, #56]
+        add x4, x4, 64
+        subs x5, x5, 64
+        bhi inner_loop1
+
+    subs x0, x0, 1
+    bhi outter_loop1
+    ret
+
+_Write_16:
+    movi v0.16b, #0
+outter_loop2:
+    mov x5, x2
+    mov x4, x1
+
+    inner_loop2:
+        str Q0, [x4]
+        str Q0, [x4, #16]
+        str Q0, [x4, #32]
+        str Q0, [x4, #48]
+        add x4, x4, 64
+        subs x5, x5, 64
+        bhi inner_loop2
+
+    subs x0, x0, 1
+    bhi outter_loop2
+    ret
+
+; there is no 32 byte register, a pair of Q registers stands in for it
+_Write_32:
+    movi v0.16b, #0
+    movi v1.16b, #0
+outter_loop3:
+    mov x5, x2
+    mov x4, x1
+
+    inner_loop3:
+        stp Q0, Q1, [x4]
+        stp Q0, Q1, [x4, #32]
+        add x4, x4, 64
+        subs x5, x5, 64
+        bhi inner_loop3
+
+    subs x0, x0, 1
+    bhi outter_loop3
+    ret
+
+; same stores as _Write_32 with four registers, kept for the x86 table
+_Write_64:
+    movi v0.16b, #0
+    movi v1.16b, #0
+    movi v2.16b, #0
+    movi v3.16b, #0
+outter_loop4:
+    mov x5, x2
+    mov x4, x1
+
+    inner_loop4:
+        stp Q0, Q1, [x4]
+        stp Q2, Q3, [x4, #32]
+        add x4, x4, 64
+        subs x5, x5, 64
+        bhi inner_loop4
+
+    subs x0, x0, 1
+    bhi outter_loop4
+    ret
+
+; stnp is a hint that the lines won't be read again soon, only pair forms exist
+; so the 8 byte version stores two W registers
+
+_WriteNT_8:
+outter_loop5:
+    mov x5, x2
+    mov x4, x1
+
+    inner_loop5:
+        stnp wzr, wzr, [x4]
+        stnp wzr, wzr, [x4, #8]
+        stnp wzr, wzr, [x4, #16]
+        stnp wzr, wzr, [x4, #24]
+        stnp wzr, wzr, [x4, #32]
+        stnp wzr, wzr, [x4, #40]
+        stnp wzr, wzr, [x4, #48]
+        stnp wzr, wzr, [x4, #56]
+        add x4, x4, 64
+        subs x5, x5, 64
+        bhi inner_loop5
+
+    subs x0, x0, 1
+    bhi outter_loop5
+    ret
+
+_WriteNT_16:
+outter_loop6:
+    mov x5, x2
+    mov x4, x1
+
+    inner_loop6:
+        stnp xzr, xzr, [x4]
+        stnp xzr, xzr, [x4, #16]
+        stnp xzr, xzr, [x4, #32]
+        stnp xzr, xzr, [x4, #48]
+        add x4, x4, 64
+        subs x5, x5, 64
+        bhi inner_loop6
+
+    subs x0, x0, 1
+    bhi outter_loop6
+    ret
+
+_WriteNT_32:
+    movi v0.16b, #0
+    movi v1.16b, #0
+outter_loop7:
+    mov x5, x2
+    mov x4, x1
+
+    inner_loop7:
+        stnp Q0, Q1, [x4]
+        stnp Q0, Q1, [x4, #32]
+        add x4, x4, 64
+        subs x5, x5, 64
+        bhi inner_loop7
+
+    subs x0, x0, 1
+    bhi outter_loop7
+    ret
+
+_WriteNT_64:
+    movi v0.16b, #0
+    movi v1.16b, #0
+    movi v2.16b, #0
+    movi v3.16b, #0
+outter_loop8:
+    mov x5, x2
+    mov x4, x1
+
+    inner_loop8:
+        stnp Q0, Q1, [x4]
+        stnp Q2, Q3, [x4, #32]
+        add x4, x4, 64
+        subs x5, x5, 64
+        bhi inner_loop8
+
+    subs x0, x0, 1
+    bhi outter_loop8
+    ret
+
+; There is no rep stosb, dc zva is the closest thing: it zeroes a whole block
+; without reading it first. The block size is 4 << DCZID_EL0[3:0] bytes.
+_WriteFill:
+    mrs x6, dczid_el0
+    and x6, x6, #15
+    mov x7, #4
+    lsl x7, x7, x6
+outter_loop9:
+    mov x5, x2
+    mov x4, x1
+
+    inner_loop9:
+        dc zva, x4
+        add x4, x4, x7
+        subs x5, x5, x7
+        bhi inner_loop9
+
+    subs x0, x0, 1
+    bhi outter_loop9
+    ret
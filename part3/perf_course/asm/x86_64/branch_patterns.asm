@@ -0,0 +1,24 @@
+.intel_syntax noprefix
+.text
+.global _ConditionalNOP, ConditionalNOP
+
+# Parameters:
+# rdi - the number of bytes in the pattern buffer
+# rsi - the pattern buffer, one byte per branch
+#
+# The branch over the nop is taken when the low bit of the pattern byte is set,
+# so the pattern buffer decides what the predictor has to learn.
+
+_ConditionalNOP:
+ConditionalNOP:
+    xor    rax, rax
+loop:
+    movzx  r9d, byte ptr [rsi + rax]
+    inc    rax
+    test   r9d, 1
+    jnz    skip
+    nop
+skip:
+    cmp    rax, rdi
+    jb     loop
+    ret
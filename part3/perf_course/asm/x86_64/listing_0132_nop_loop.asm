@@ -0,0 +1,66 @@
+.intel_syntax noprefix
+.text
+
+# Every kernel is exported with and without the leading underscore so the same
+# file links on macOS and on Linux.
+.global _GarbageLoopExample, GarbageLoopExample
+.global _FullLoopExample, FullLoopExample
+.global _NopLoopExample, NopLoopExample
+.global _JustLoopExample, JustLoopExample
+.global _DecLoopExample, DecLoopExample
+
+_GarbageLoopExample:
+GarbageLoopExample:
+    xor    rax, rax         # Initialize rax to 0
+g_loop:
+    # Garbage instructions to break parallelism
+    mov    r9, rax          # Move rax to r9, creating a dependency
+    mov    r10, r9          # Move r9 to r10, creating another dependency
+    mov    r11, r10         # Move r10 to r11, creating yet another dependency
+
+    mov    [rsi + rax], al  # Store byte from al into memory at address rsi + rax
+    inc    rax              # Increment rax by 1
+
+    # More garbage instructions
+    lea    rcx, [r11 + r10] # Add r11 and r10, store in rcx
+    sub    rcx, r9          # Subtract r9 from rcx
+
+    cmp    rax, rdi         # Compare rax with rdi
+    jb     g_loop           # If rax is less than rdi, jump to loop
+    ret                     # Return from function
+
+_FullLoopExample:
+FullLoopExample:
+    xor    rax, rax
+loop:
+    mov    [rsi + rax], al
+    inc    rax
+    cmp    rax, rdi
+    jb     loop
+    ret
+
+_NopLoopExample:
+NopLoopExample:
+    xor    rax, rax
+loop1:
+    nop
+    inc    rax
+    cmp    rax, rdi
+    jb     loop1
+    ret
+
+_JustLoopExample:
+JustLoopExample:
+    xor    rax, rax
+loop2:
+    inc    rax
+    cmp    rax, rdi
+    jb     loop2
+    ret
+
+_DecLoopExample:
+DecLoopExample:
+loop3:
+    dec    rdi
+    jnz    loop3
+    ret
@@ -0,0 +1,39 @@
+.intel_syntax noprefix
+.text
+.global _NOP1AllBytes, NOP1AllBytes
+.global _NOP3AllBytes, NOP3AllBytes
+.global _NOP9AllBytes, NOP9AllBytes
+
+_NOP1AllBytes:
+NOP1AllBytes:
+    xor    rax, rax
+loop1:
+    .byte 0x90
+    inc    rax
+    cmp    rax, rdi
+    jb     loop1
+    ret
+
+
+# one 3 byte nop
+_NOP3AllBytes:
+NOP3AllBytes:
+    xor    rax, rax
+loop2:
+    .byte 0x0f, 0x1f, 0x00
+    inc    rax
+    cmp    rax, rdi
+    jb     loop2
+    ret
+
+
+# one 9 byte nop
+_NOP9AllBytes:
+NOP9AllBytes:
+    xor    rax, rax
+loop3:
+    .byte 0x66, 0x0f, 0x1f, 0x84, 0x00, 0x00, 0x00, 0x00, 0x00
+    inc    rax
+    cmp    rax, rdi
+    jb     loop3
+    ret
@@ -0,0 +1,48 @@
+.intel_syntax noprefix
+.text
+.global _NOPAligned64, NOPAligned64
+.global _NOPAligned31, NOPAligned31
+.global _NOPAligned63, NOPAligned63
+
+_NOPAligned64:
+NOPAligned64:
+    xor    rax, rax
+# Align the following code to a 64-byte boundary
+.p2align 6
+loop1:
+    mov    [rsi + rax], al
+    inc    rax
+    cmp    rax, rdi
+    jb     loop1
+    ret
+
+_NOPAligned31:
+NOPAligned31:
+    xor    rax, rax
+# Align to a 64-byte boundary, then start the loop 31 bytes past it
+.p2align 6
+.rept 31
+    nop
+.endr
+loop2:
+    mov    [rsi + rax], al
+    inc    rax
+    cmp    rax, rdi
+    jb     loop2
+    ret
+
+
+_NOPAligned63:
+NOPAligned63:
+    xor    rax, rax
+# Align to a 64-byte boundary, then start the loop 63 bytes past it
+.p2align 6
+.rept 63
+    nop
+.endr
+loop3:
+    mov    [rsi + rax], al
+    inc    rax
+    cmp    rax, rdi
+    jb     loop3
+    ret
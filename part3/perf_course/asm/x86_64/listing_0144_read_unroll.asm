@@ -0,0 +1,80 @@
+.intel_syntax noprefix
+.text
+.global _Read_x1, Read_x1
+.global _Read_x2, Read_x2
+.global _Read_x3, Read_x3
+.global _Read_x4, Read_x4
+
+.global _Write_x1, Write_x1
+.global _Write_x2, Write_x2
+.global _Write_x3, Write_x3
+
+
+_Read_x1:
+Read_x1:
+loop1:
+    mov rax, [rsi]
+    sub rdi, 1
+    ja loop1
+    ret
+
+_Read_x2:
+Read_x2:
+loop2:
+    mov rax, [rsi]
+    mov rax, [rsi]
+    sub rdi, 2
+    ja loop2
+    ret
+
+_Read_x3:
+Read_x3:
+loop3:
+    mov rax, [rsi]
+    mov rax, [rsi]
+    mov rax, [rsi]
+    sub rdi, 3
+    ja loop3
+    ret
+
+_Read_x4:
+Read_x4:
+loop4:
+    mov rax, [rsi]
+    mov rax, [rsi]
+    mov rax, [rsi]
+    mov rax, [rsi]
+    sub rdi, 4
+    ja loop4
+    ret
+
+
+_Write_x1:
+Write_x1:
+    xor rax, rax
+loop5:
+    mov [rsi], rax
+    sub rdi, 1
+    ja loop5
+    ret
+
+_Write_x2:
+Write_x2:
+    xor rax, rax
+loop6:
+    mov [rsi], rax
+    mov [rsi], rax
+    sub rdi, 2
+    ja loop6
+    ret
+
+_Write_x3:
+Write_x3:
+    xor rax, rax
+loop7:
+    mov [rsi], rax
+    mov [rsi], rax
+    mov [rsi], rax
+    sub rdi, 3
+    ja loop7
+    ret
@@ -0,0 +1,125 @@
+.intel_syntax noprefix
+.text
+.global _Read_4x3, Read_4x3
+.global _Read_8x3, Read_8x3
+.global _Read_16x1, Read_16x1
+.global _Read_16x2, Read_16x2
+.global _Read_16x3, Read_16x3
+.global _Read_16x4, Read_16x4
+.global _Read_32x3, Read_32x3
+.global _Read_64x3, Read_64x3
+.global _Read_128x3, Read_128x3
+.global _Read_256x3, Read_256x3
+.global _Read_512x3, Read_512x3
+
+_Read_4x3:
+Read_4x3:
+loop1:
+    mov eax, [rsi]
+    mov eax, [rsi + 4]
+    mov eax, [rsi + 8]
+    sub rdi, 12
+    ja loop1
+    ret
+
+_Read_8x3:
+Read_8x3:
+loop2:
+    mov rax, [rsi]
+    mov rax, [rsi + 8]
+    mov rax, [rsi + 16]
+    sub rdi, 24
+    ja loop2
+    ret
+
+_Read_16x1:
+Read_16x1:
+loop6:
+    movzx eax, word ptr [rsi]
+    sub rdi, 16
+    ja loop6
+    ret
+
+_Read_16x2:
+Read_16x2:
+loop7:
+    movzx eax, word ptr [rsi]
+    movzx eax, word ptr [rsi + 16]
+    sub rdi, 32
+    ja loop7
+    ret
+
+_Read_16x3:
+Read_16x3:
+loop8:
+    movzx eax, word ptr [rsi]
+    movzx eax, word ptr [rsi + 16]
+    movzx eax, word ptr [rsi + 32]
+    sub rdi, 48
+    ja loop8
+    ret
+
+_Read_16x4:
+Read_16x4:
+loop9:
+    movzx eax, word ptr [rsi]
+    movzx eax, word ptr [rsi + 16]
+    movzx eax, word ptr [rsi + 32]
+    movzx eax, word ptr [rsi + 48]
+    sub rdi, 64
+    ja loop9
+    ret
+
+_Read_32x3:
+Read_32x3:
+loop5:
+    mov eax, [rsi]
+    mov eax, [rsi + 32]
+    mov eax, [rsi + 64]
+    sub rdi, 96
+    ja loop5
+    ret
+
+_Read_64x3:
+Read_64x3:
+loop3:
+    mov rax, [rsi]
+    mov rax, [rsi + 64]
+    mov rax, [rsi + 128]
+    sub rdi, 192
+    ja loop3
+    ret
+
+_Read_128x3:
+Read_128x3:
+loop4:
+    movdqu xmm0, [rsi]
+    movdqu xmm0, [rsi + 128]
+    movdqu xmm0, [rsi + 256]
+    sub rdi, 384
+    ja loop4
+    ret
+
+# AVX2
+_Read_256x3:
+Read_256x3:
+loop10:
+    vmovdqu ymm0, [rsi]
+    vmovdqu ymm0, [rsi + 256]
+    vmovdqu ymm0, [rsi + 512]
+    sub rdi, 768
+    ja loop10
+    vzeroupper
+    ret
+
+# AVX-512, check for avx512f before calling
+_Read_512x3:
+Read_512x3:
+loop11:
+    vmovdqu64 zmm0, [rsi]
+    vmovdqu64 zmm0, [rsi + 512]
+    vmovdqu64 zmm0, [rsi + 1024]
+    sub rdi, 1536
+    ja loop11
+    vzeroupper
+    ret
@@ -0,0 +1,98 @@
+.intel_syntax noprefix
+.text
+.global _ReadBufferTest, ReadBufferTest
+.global _ReadBufferDoubleLoopTest, ReadBufferDoubleLoopTest
+.global _ReadBufferDoubleLoopTest2, ReadBufferDoubleLoopTest2
+.global _ReadBufferDoubleLoopTest3, ReadBufferDoubleLoopTest3
+
+# Parameters:
+# rdi - the number of bytes to read
+# rsi - the buffer address
+# rdx - the mask applied to the offset, region size - 1
+_ReadBufferTest:
+ReadBufferTest:
+    xor r8, r8
+    mov r9, rsi
+loop:
+    movdqu xmm0, [r9]
+    movdqu xmm0, [r9 + 128]
+    movdqu xmm0, [r9 + 256]
+    movdqu xmm0, [r9 + 384]
+    movdqu xmm0, [r9 + 512]
+    movdqu xmm0, [r9 + 640]
+    add r8, 768
+    and r8, rdx                 # mask the counter
+    lea r9, [rsi + r8]
+    sub rdi, 768                # the main loop counter
+    ja loop
+    ret
+
+
+# Parameters:
+# rdi - the number of iterations
+# rsi - the buffer address
+# rdx - inner loop run count
+#
+# Tmp registers:
+# r8 - being reset to rdx at the beginning of the outer loop
+
+_ReadBufferDoubleLoopTest:
+ReadBufferDoubleLoopTest:
+outter_loop:
+    mov r8, rdx
+    mov r9, rsi
+
+    inner_loop:
+        movdqu xmm0, [r9]
+        movdqu xmm0, [r9 + 128]
+        movdqu xmm0, [r9 + 256]
+        movdqu xmm0, [r9 + 384]
+        movdqu xmm0, [r9 + 512]
+        movdqu xmm0, [r9 + 640]
+        add r9, 768
+        sub r8, 768
+        ja inner_loop
+
+    sub rdi, 1
+    ja outter_loop
+    ret
+
+_ReadBufferDoubleLoopTest2:
+ReadBufferDoubleLoopTest2:
+outter_loop2:
+    mov r8, rdx
+    mov r9, rsi
+
+    inner_loop2:
+        movdqu xmm0, [r9]
+        movdqu xmm0, [r9 + 128]
+        add r9, 256
+        sub r8, 256
+        ja inner_loop2
+
+    sub rdi, 1
+    ja outter_loop2
+    ret
+
+_ReadBufferDoubleLoopTest3:
+ReadBufferDoubleLoopTest3:
+outter_loop3:
+    mov r8, rdx
+    mov r9, rsi
+
+    inner_loop3:
+        movdqu xmm0, [r9]
+        movdqu xmm0, [r9 + 128]
+        movdqu xmm0, [r9 + 256]
+        movdqu xmm0, [r9 + 384]
+        movdqu xmm0, [r9 + 512]
+        movdqu xmm0, [r9 + 640]
+        movdqu xmm0, [r9 + 768]
+        movdqu xmm0, [r9 + 896]
+        add r9, 1024
+        sub r8, 1024
+        ja inner_loop3
+
+    sub rdi, 1
+    ja outter_loop3
+    ret
@@ -0,0 +1,54 @@
+.intel_syntax noprefix
+.text
+.global _CacheSetTest, CacheSetTest
+.global _CacheStrideTest, CacheStrideTest
+
+_CacheSetTest:
+CacheSetTest:
+outter_loop3:
+    mov r8, rdx
+    mov r9, rsi
+
+    inner_loop3:
+        movdqu xmm0, [r9]
+        movdqu xmm0, [r9 + 128]
+        movdqu xmm0, [r9 + 256]
+        movdqu xmm0, [r9 + 384]
+        movdqu xmm0, [r9 + 512]
+        movdqu xmm0, [r9 + 640]
+        movdqu xmm0, [r9 + 768]
+        movdqu xmm0, [r9 + 896]
+        add r9, 16384
+        sub r8, 1024
+        ja inner_loop3
+
+    sub rdi, 1
+    ja outter_loop3
+    ret
+
+
+# Parameters:
+# rdi - the number of passes over the lines
+# rsi - the buffer address
+# rdx - the number of lines touched per pass
+# rcx - the stride between two touched lines
+#
+# Tmp registers:
+# r9 - the current line address
+# r8 - lines left in this pass
+
+_CacheStrideTest:
+CacheStrideTest:
+outter_loop4:
+    mov r8, rdx
+    mov r9, rsi
+
+    inner_loop4:
+        movdqu xmm0, [r9]
+        add r9, rcx
+        sub r8, 1
+        ja inner_loop4
+
+    sub rdi, 1
+    ja outter_loop4
+    ret
@@ -0,0 +1,103 @@
+.intel_syntax noprefix
+.text
+.global _CopySIMD, CopySIMD
+.global _CopyNT, CopyNT
+.global _CopyRepMovsb, CopyRepMovsb
+
+# Parameters, for every kernel:
+# rdi - the number of passes
+# rsi - destination, any alignment
+# rdx - source, any alignment
+# rcx - bytes to copy, a multiple of 64 and at least 128
+#
+# Tmp registers:
+# r8 - the current destination
+# r9 - the current source
+# r10 - bytes left in this pass
+
+# AVX, check for avx before calling
+_CopySIMD:
+CopySIMD:
+outter_loop1:
+    mov r8, rsi
+    mov r9, rdx
+    mov r10, rcx
+
+    inner_loop1:
+        vmovdqu ymm0, [r9]
+        vmovdqu ymm1, [r9 + 32]
+        vmovdqu [r8], ymm0
+        vmovdqu [r8 + 32], ymm1
+        add r8, 64
+        add r9, 64
+        sub r10, 64
+        ja inner_loop1
+
+    sub rdi, 1
+    ja outter_loop1
+    vzeroupper
+    ret
+
+# AVX, check for avx before calling
+#
+# vmovntdq needs an aligned destination, so the first 64 bytes are copied with
+# regular stores and the loop starts at the next line boundary of the
+# destination. The last 64 bytes are copied the same way to cover the part the
+# loop didn't reach, overlapping it is harmless.
+_CopyNT:
+CopyNT:
+outter_loop2:
+    vmovdqu ymm0, [rdx]
+    vmovdqu ymm1, [rdx + 32]
+    vmovdqu [rsi], ymm0
+    vmovdqu [rsi + 32], ymm1
+
+    # r11 = 64 - (destination & 63), the distance to the next line
+    mov r11, rsi
+    and r11, 63
+    neg r11
+    add r11, 64
+    lea r8, [rsi + r11]
+    lea r9, [rdx + r11]
+    mov r10, rcx
+    sub r10, r11
+
+    inner_loop2:
+        vmovdqu ymm0, [r9]
+        vmovdqu ymm1, [r9 + 32]
+        vmovntdq [r8], ymm0
+        vmovntdq [r8 + 32], ymm1
+        add r8, 64
+        add r9, 64
+        sub r10, 64
+        cmp r10, 64
+        jae inner_loop2
+
+    vmovdqu ymm0, [rdx + rcx - 64]
+    vmovdqu ymm1, [rdx + rcx - 32]
+    vmovdqu [rsi + rcx - 64], ymm0
+    vmovdqu [rsi + rcx - 32], ymm1
+
+    sub rdi, 1
+    ja outter_loop2
+    sfence
+    vzeroupper
+    ret
+
+# rep movsb needs the destination in rdi, the source in rsi and the count in
+# rcx so the parameters move out of the way first
+_CopyRepMovsb:
+CopyRepMovsb:
+    mov r8, rdi
+    mov r9, rsi
+    mov r10, rdx
+    mov r11, rcx
+outter_loop3:
+    mov rdi, r9
+    mov rsi, r10
+    mov rcx, r11
+    rep movsb
+
+    sub r8, 1
+    ja outter_loop3
+    ret
@@ -0,0 +1,31 @@
+.intel_syntax noprefix
+.text
+.global _ReadOffsets, ReadOffsets
+
+# Parameters:
+# rdi - the number of loads, a multiple of 4
+# rsi - the buffer address
+# rdx - a table of 32 bit offsets into the buffer, one per load
+#
+# The loads don't depend on each other, so how fast they go is down to how many
+# lines are already on their way, which is what the prefetchers decide. The
+# offset table itself is read sequentially and stays ahead of them.
+
+_ReadOffsets:
+ReadOffsets:
+    test rdi, rdi
+    jz done
+loop1:
+    mov r8d, [rdx]
+    mov r9d, [rdx + 4]
+    mov r10d, [rdx + 8]
+    mov r11d, [rdx + 12]
+    mov rax, [rsi + r8]
+    mov rax, [rsi + r9]
+    mov rax, [rsi + r10]
+    mov rax, [rsi + r11]
+    add rdx, 16
+    sub rdi, 4
+    ja loop1
+done:
+    ret
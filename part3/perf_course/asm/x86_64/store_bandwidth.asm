@@ -0,0 +1,211 @@
+.intel_syntax noprefix
+.text
+.global _Write_8, Write_8
+.global _Write_16, Write_16
+.global _Write_32, Write_32
+.global _Write_64, Write_64
+.global _WriteNT_8, WriteNT_8
+.global _WriteNT_16, WriteNT_16
+.global _WriteNT_32, WriteNT_32
+.global _WriteNT_64, WriteNT_64
+.global _WriteFill, WriteFill
+
+# Parameters, for every kernel:
+# rdi - the number of passes over the region
+# rsi - the region address, 64 byte aligned
+# rdx - the region size, a multiple of 64
+#
+# Tmp registers:
+# r8 - bytes left in this pass
+# r9 - the current address
+#
+# Every inner iteration writes 64 bytes of zeros.
+
+_Write_8:
+Write_8:
+    xor eax, eax
+outter_loop1:
+    mov r8, rdx
+    mov r9, rsi
+
+    inner_loop1:
+        mov [r9], rax
+        mov [r9 + 8], rax
+        mov [r9 + 16], rax
+        mov [r9 + 24], rax
+        mov [r9 + 32], rax
+        mov [r9 + 40], rax
+        mov [r9 + 48], rax
+        mov [r9 + 56], rax
+        add r9, 64
+        sub r8, 64
+        ja inner_loop1
+
+    sub rdi, 1
+    ja outter_loop1
+    ret
+
+_Write_16:
+Write_16:
+    pxor xmm0, xmm0
+outter_loop2:
+    mov r8, rdx
+    mov r9, rsi
+
+    inner_loop2:
+        movdqa [r9], xmm0
+        movdqa [r9 + 16], xmm0
+        movdqa [r9 + 32], xmm0
+        movdqa [r9 + 48], xmm0
+        add r9, 64
+        sub r8, 64
+        ja inner_loop2
+
+    sub rdi, 1
+    ja outter_loop2
+    ret
+
+# AVX, check for avx before calling (vxorps, vpxor on ymm is AVX2)
+_Write_32:
+Write_32:
+    vxorps ymm0, ymm0, ymm0
+outter_loop3:
+    mov r8, rdx
+    mov r9, rsi
+
+    inner_loop3:
+        vmovdqa [r9], ymm0
+        vmovdqa [r9 + 32], ymm0
+        add r9, 64
+        sub r8, 64
+        ja inner_loop3
+
+    sub rdi, 1
+    ja outter_loop3
+    vzeroupper
+    ret
+
+# AVX-512, check for avx512f before calling
+_Write_64:
+Write_64:
+    vpxord zmm0, zmm0, zmm0
+outter_loop4:
+    mov r8, rdx
+    mov r9, rsi
+
+    inner_loop4:
+        vmovdqa64 [r9], zmm0
+        add r9, 64
+        sub r8, 64
+        ja inner_loop4
+
+    sub rdi, 1
+    ja outter_loop4
+    vzeroupper
+    ret
+
+# The non-temporal versions go around the caches through the write combining
+# buffers, the sfence drains them before returning.
+
+_WriteNT_8:
+WriteNT_8:
+    xor eax, eax
+outter_loop5:
+    mov r8, rdx
+    mov r9, rsi
+
+    inner_loop5:
+        movnti [r9], rax
+        movnti [r9 + 8], rax
+        movnti [r9 + 16], rax
+        movnti [r9 + 24], rax
+        movnti [r9 + 32], rax
+        movnti [r9 + 40], rax
+        movnti [r9 + 48], rax
+        movnti [r9 + 56], rax
+        add r9, 64
+        sub r8, 64
+        ja inner_loop5
+
+    sub rdi, 1
+    ja outter_loop5
+    sfence
+    ret
+
+_WriteNT_16:
+WriteNT_16:
+    pxor xmm0, xmm0
+outter_loop6:
+    mov r8, rdx
+    mov r9, rsi
+
+    inner_loop6:
+        movntdq [r9], xmm0
+        movntdq [r9 + 16], xmm0
+        movntdq [r9 + 32], xmm0
+        movntdq [r9 + 48], xmm0
+        add r9, 64
+        sub r8, 64
+        ja inner_loop6
+
+    sub rdi, 1
+    ja outter_loop6
+    sfence
+    ret
+
+# AVX, check for avx before calling
+_WriteNT_32:
+WriteNT_32:
+    vxorps ymm0, ymm0, ymm0
+outter_loop7:
+    mov r8, rdx
+    mov r9, rsi
+
+    inner_loop7:
+        vmovntdq [r9], ymm0
+        vmovntdq [r9 + 32], ymm0
+        add r9, 64
+        sub r8, 64
+        ja inner_loop7
+
+    sub rdi, 1
+    ja outter_loop7
+    sfence
+    vzeroupper
+    ret
+
+# AVX-512, check for avx512f before calling
+_WriteNT_64:
+WriteNT_64:
+    vpxord zmm0, zmm0, zmm0
+outter_loop8:
+    mov r8, rdx
+    mov r9, rsi
+
+    inner_loop8:
+        vmovntdq [r9], zmm0
+        add r9, 64
+        sub r8, 64
+        ja inner_loop8
+
+    sub rdi, 1
+    ja outter_loop8
+    sfence
+    vzeroupper
+    ret
+
+# rep stosb over the whole region, rep stosb needs the destination in rdi and
+# the count in rcx so the parameters move out of the way first
+_WriteFill:
+WriteFill:
+    mov r8, rdi
+    mov r9, rdx
+    xor eax, eax
+outter_loop9:
+    mov rdi, rsi
+    mov rcx, r9
+    rep stosb
+
+    sub r8, 1
+    ja outter_loop9
+    ret
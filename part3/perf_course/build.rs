@@ -1,15 +1,142 @@
 use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
 
+struct Kernel {
+    object_file: PathBuf,
+    // exported names without the Mach-O underscore
+    symbols: Vec<String>,
+}
+
+// Symbols named by the `.global` lines of an assembly source.
+fn global_symbols(source: &str) -> Vec<String> {
+    let mut symbols = Vec::new();
+    for line in source.lines() {
+        let line = line.trim();
+        if let Some(names) = line.strip_prefix(".global") {
+            for name in names.split(',') {
+                let name = name.trim().trim_start_matches('_').to_string();
+                if !name.is_empty() && !symbols.contains(&name) {
+                    symbols.push(name);
+                }
+            }
+        }
+    }
+    symbols
+}
+
+// Name and source of a binary in src/bin, either a single file or a directory
+// with a main.rs whose modules all count as its source.
+fn binary_source(path: &Path) -> Option<(String, String)> {
+    if path.is_dir() {
+        if !path.join("main.rs").exists() {
+            return None;
+        }
+        let mut source = String::new();
+        for entry in fs::read_dir(path).unwrap() {
+            let file = entry.unwrap().path();
+            if file.extension().is_some_and(|ext| ext == "rs") {
+                source.push_str(&fs::read_to_string(&file).unwrap());
+            }
+        }
+        let name = path.file_name().unwrap().to_string_lossy().to_string();
+        return Some((name, source));
+    }
+
+    if path.extension().is_some_and(|ext| ext == "rs") {
+        let name = path.file_stem().unwrap().to_string_lossy().to_string();
+        return Some((name, fs::read_to_string(path).unwrap()));
+    }
+    None
+}
+
+fn assemble(source_file: &Path, object_file: &Path, arch: &str, os: &str) {
+    let mut command = Command::new("as");
+    if os == "macos" {
+        let arch = if arch == "aarch64" { "arm64" } else { arch };
+        command.args(["-arch", arch]);
+    } else {
+        command.arg("--noexecstack");
+    }
+    command.arg("-o").arg(object_file).arg(source_file);
+
+    let output = command
+        .output()
+        .unwrap_or_else(|e| panic!("Failed to run as for {}: {}", source_file.display(), e));
+    if !output.status.success() {
+        panic!(
+            "Assembling {} failed:\n{}",
+            source_file.display(),
+            String::from_utf8_lossy(&output.stderr)
+        );
+    }
+}
+
+// Assembles every kernel in asm/<target arch> and links each object into the
+// binaries that declare one of its symbols, so `cargo build --bins` builds
+// every listing.
 fn main() {
+    let arch = env::var("CARGO_CFG_TARGET_ARCH").unwrap();
+    let os = env::var("CARGO_CFG_TARGET_OS").unwrap();
+    let out_dir = PathBuf::from(env::var("OUT_DIR").unwrap());
+    let asm_dir = Path::new("asm").join(&arch);
+
+    println!("cargo:rerun-if-changed=asm");
+    println!("cargo:rerun-if-changed=src/bin");
+
+    let mut kernels = Vec::new();
+    match fs::read_dir(&asm_dir) {
+        Ok(entries) => {
+            let mut sources: Vec<PathBuf> = entries
+                .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+                .filter(|path| path.extension().is_some_and(|ext| ext == "asm"))
+                .collect();
+            sources.sort();
+
+            for source_file in sources {
+                let source = fs::read_to_string(&source_file).unwrap();
+                let object_file = out_dir
+                    .join(source_file.file_stem().unwrap())
+                    .with_extension("o");
+                assemble(&source_file, &object_file, &arch, &os);
+                kernels.push(Kernel {
+                    object_file,
+                    symbols: global_symbols(&source),
+                });
+            }
+        }
+        Err(_) => {
+            println!(
+                "cargo:warning=No kernels for {} in {}, binaries using them will not link",
+                arch,
+                asm_dir.display()
+            );
+        }
+    }
+
+    let mut binaries: Vec<PathBuf> = fs::read_dir("src/bin")
+        .unwrap()
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .collect();
+    binaries.sort();
 
-    match env::var("OBJ_FILE") {
-        Ok(object_file) => {
-            println!("cargo:rustc-link-arg={}", object_file);
-        },
-        Err(_) => {}
+    for binary in binaries {
+        let Some((name, source)) = binary_source(&binary) else {
+            continue;
+        };
+        for kernel in &kernels {
+            let declared = kernel
+                .symbols
+                .iter()
+                .any(|symbol| source.contains(&format!("fn {}(", symbol)));
+            if declared {
+                println!(
+                    "cargo:rustc-link-arg-bin={}={}",
+                    name,
+                    kernel.object_file.display()
+                );
+            }
+        }
     }
-    //
-    // let object_file = env::var("OBJ_FILE").unwrap();
-    // // println!("cargo:rustc-link-arg=listing_0132_nop_loop.o");
-    // println!("cargo:rustc-link-arg={}", object_file);
 }
@@ -0,0 +1,55 @@
+extern crate perf_course;
+
+use std::env;
+use std::error::Error;
+use std::path::Path;
+
+use perf_course::fault_pattern::{analyze_faults, print_fault_patterns, read_fault_csv};
+
+fn usage(program: &str) -> ! {
+    eprintln!("Usage: {} <file.csv>[=<label>]...", program);
+    std::process::exit(1);
+}
+
+// fault_counter.csv was the forward walk before the orders had names,
+// fault_counter_backward.csv -> backward, anything else keeps its file name
+fn default_label(csv_file: &str) -> String {
+    let stem = Path::new(csv_file)
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().to_string())
+        .unwrap_or(csv_file.to_string());
+    match stem.strip_prefix("fault_counter") {
+        Some("") => "forward".to_string(),
+        Some(order) => order.trim_start_matches('_').replace('_', " "),
+        None => stem,
+    }
+}
+
+// Reads the fault counter CSVs of listings 112 and 119 and works out how many
+// pages the kernel maps per fault and how often a page costs an extra one:
+//
+//   fault_pattern ../../part2/rust-parser/fault_counter.csv ../../part2/rust-parser/fault_counter_backward.csv
+//   fault_pattern run1.csv=cold run2.csv=warm
+fn main() -> Result<(), Box<dyn Error>> {
+    let args: Vec<String> = env::args().collect();
+    if args.len() < 2 {
+        usage(&args[0]);
+    }
+
+    let mut patterns = Vec::new();
+    for arg in &args[1..] {
+        if arg.starts_with("--") {
+            usage(&args[0]);
+        }
+        let (csv_file, label) = match arg.split_once('=') {
+            Some((csv_file, label)) => (csv_file, label.to_string()),
+            None => (&arg[..], default_label(arg)),
+        };
+        let rows = read_fault_csv(csv_file)?;
+        patterns.push((label, analyze_faults(&rows)));
+    }
+
+    print_fault_patterns(&patterns);
+
+    Ok(())
+}
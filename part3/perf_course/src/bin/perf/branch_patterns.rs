@@ -0,0 +1,234 @@
+use std::cell::RefCell;
+use std::error::Error;
+use std::fs::File;
+use std::io::Write;
+use std::ptr;
+use std::rc::Rc;
+
+use libc::{mmap, MAP_ANON, MAP_PRIVATE, PROT_READ, PROT_WRITE};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+use perf_course::perf_metrics::estimate_cpu_frequency;
+use perf_course::repetition_tester::repetition_tester::RepetitionTester;
+use perf_course::report::record_wave;
+use perf_course::svg_chart::{svg_file_name, AxisFormat, LineChart, Series};
+use perf_course::sweep::{ParameterGenerator, Sweep, SweepMetric};
+
+use crate::Params;
+
+extern "C" {
+    fn ConditionalNOP(count: u64, data: *mut u8);
+}
+
+#[derive(Debug, Copy, Clone)]
+enum BranchPattern {
+    Always,
+    Never,
+    Alternating,
+    // a random pattern of `period` branches, repeated over the whole buffer
+    Period(usize),
+    Random,
+}
+
+impl BranchPattern {
+    fn fill(&self, buffer: &mut [u8], seed: u64) {
+        let mut rng = StdRng::seed_from_u64(seed);
+        match self {
+            BranchPattern::Always => buffer.fill(1),
+            BranchPattern::Never => buffer.fill(0),
+            BranchPattern::Alternating => {
+                for (i, byte) in buffer.iter_mut().enumerate() {
+                    *byte = (i & 1) as u8;
+                }
+            }
+            BranchPattern::Period(period) => {
+                let pattern: Vec<u8> = (0..*period).map(|_| rng.gen_range(0..2)).collect();
+                for (i, byte) in buffer.iter_mut().enumerate() {
+                    *byte = pattern[i % period];
+                }
+            }
+            BranchPattern::Random => {
+                for byte in buffer.iter_mut() {
+                    *byte = rng.gen_range(0..2);
+                }
+            }
+        }
+    }
+}
+
+struct TesterFunction {
+    name: &'static str,
+    pattern: BranchPattern,
+    tester: Rc<RefCell<RepetitionTester>>,
+}
+
+// branch_patterns [max_period], --size sets the buffer size
+pub fn run(params: &Params) -> Result<(), Box<dyn Error>> {
+    let args = &params.args;
+    let max_period = args
+        .get(1)
+        .map(|s| s.parse::<u64>().expect("Invalid max period"))
+        .unwrap_or(64 * 1024);
+    // a period of 1 is Always or Never, there is nothing to sweep below 2
+    if max_period < 2 {
+        return Err("The max period can't be smaller than 2".into());
+    }
+    let total_size = params.size_or(16 * 1024 * 1024) as usize;
+
+    let addr = unsafe {
+        mmap(
+            ptr::null_mut(), // Address at which to start the mapping (nullptr lets the OS choose)
+            total_size,      // Number of bytes to map
+            PROT_READ | PROT_WRITE, // Enable read and write access
+            MAP_PRIVATE | MAP_ANON,
+            -1, // File descriptor not used with MAP_ANON
+            0,  // Offset not used with MAP_ANON
+        )
+    };
+    let buffer = unsafe { std::slice::from_raw_parts_mut(addr as *mut u8, total_size) };
+
+    let frequency = estimate_cpu_frequency();
+    println!("CPU frequency: {:.2} GHz", frequency / 1e9);
+
+    let functions = vec![
+        TesterFunction {
+            name: "Never Taken",
+            pattern: BranchPattern::Never,
+            tester: Rc::new(RefCell::new(RepetitionTester::new())),
+        },
+        TesterFunction {
+            name: "Always Taken",
+            pattern: BranchPattern::Always,
+            tester: Rc::new(RefCell::new(RepetitionTester::new())),
+        },
+        TesterFunction {
+            name: "Alternating",
+            pattern: BranchPattern::Alternating,
+            tester: Rc::new(RefCell::new(RepetitionTester::new())),
+        },
+        TesterFunction {
+            name: "Period 8",
+            pattern: BranchPattern::Period(8),
+            tester: Rc::new(RefCell::new(RepetitionTester::new())),
+        },
+        TesterFunction {
+            name: "Random",
+            pattern: BranchPattern::Random,
+            tester: Rc::new(RefCell::new(RepetitionTester::new())),
+        },
+    ];
+
+    let cycles_per_branch = |seconds: f64| seconds * frequency / total_size as f64;
+
+    let mut fixed_results = Vec::new();
+    for ft in &functions {
+        println!("\n----- {} -----", ft.name);
+        ft.pattern.fill(buffer, 1);
+
+        let mut tester = ft.tester.borrow_mut();
+        tester.start_test_wave(params.seconds_or(2), total_size as u64);
+        while tester.is_testing() {
+            tester.begin_time();
+            unsafe {
+                ConditionalNOP(total_size as u64, buffer.as_mut_ptr());
+            }
+            tester.end_time();
+            tester.count_bytes(total_size as u64);
+        }
+        record_wave(ft.name, &tester);
+
+        fixed_results.push((ft.name, cycles_per_branch(tester.min_seconds())));
+    }
+
+    // the periodic patterns, each one freshly randomized
+    let sweep = Sweep {
+        name: "Periodic Pattern".to_string(),
+        parameter_label: "Period".to_string(),
+        generator: ParameterGenerator::LogSpaced {
+            start: 2,
+            end: max_period,
+            points: 48,
+        },
+        metric: SweepMetric::Seconds,
+        seconds_to_try: params.seconds_or(1),
+    };
+
+    let mut built_for = 0;
+    let results = sweep.run(|period| {
+        if period != built_for {
+            BranchPattern::Period(period as usize).fill(buffer, period);
+            built_for = period;
+        }
+        unsafe {
+            ConditionalNOP(total_size as u64, buffer.as_mut_ptr());
+        }
+        total_size as u64
+    });
+
+    let periodic: Vec<(u64, f64)> = results
+        .points
+        .iter()
+        .map(|point| (point.parameter, cycles_per_branch(point.seconds)))
+        .collect();
+
+    println!("\n===== Cycles Per Branch =====");
+    for (name, cycles) in &fixed_results {
+        println!("{}: {:.3}", name, cycles);
+    }
+    for (period, cycles) in &periodic {
+        println!("Period {}: {:.3}", period, cycles);
+    }
+
+    let csv_file = "branch_patterns.csv";
+    let mut file = File::create(csv_file)?;
+    writeln!(file, "Period, Cycles Per Branch")?;
+    for (period, cycles) in &periodic {
+        writeln!(file, "{}, {}", period, cycles)?;
+    }
+
+    LineChart {
+        title: "Periodic Branch Patterns".to_string(),
+        x_label: "Period".to_string(),
+        y_label: "Cycles Per Branch".to_string(),
+        x_format: AxisFormat::Number,
+        log_x: true,
+        series: vec![Series {
+            name: "Random Period".to_string(),
+            points: periodic.iter().map(|(p, c)| (*p as f64, *c)).collect(),
+        }],
+    }
+    .save(&svg_file_name(csv_file))?;
+    println!("\nResults written to {}", csv_file);
+
+    // Taken branches cost more than not taken ones, and the periodic patterns
+    // take about half of them, so a predictable mix sits halfway between
+    // always and never taken. A random branch is mispredicted half of the time.
+    let cycles_of = |name: &str| {
+        fixed_results
+            .iter()
+            .find(|(result_name, _)| *result_name == name)
+            .map(|(_, cycles)| *cycles)
+            .unwrap_or(0.0)
+    };
+    let predictable = (cycles_of("Always Taken") + cycles_of("Never Taken")) / 2.0;
+    let random = cycles_of("Random").max(predictable);
+    let mispredict_cost = (random - predictable) * 2.0;
+
+    // learned = within a quarter of the way from predictable to random
+    let threshold = predictable + (random - predictable) * 0.25;
+    let longest_learned = periodic
+        .iter()
+        .take_while(|(_, cycles)| *cycles <= threshold)
+        .last()
+        .map(|(period, _)| *period);
+
+    println!("\n===== Branch Predictor =====");
+    println!("Mispredict cost: ~{:.1} cycles", mispredict_cost);
+    match longest_learned {
+        Some(period) => println!("Longest periodic pattern learned: ~{} branches", period),
+        None => println!("No periodic pattern was learned"),
+    }
+
+    Ok(())
+}
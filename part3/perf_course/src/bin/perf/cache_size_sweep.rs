@@ -0,0 +1,77 @@
+use std::error::Error;
+use std::ptr;
+
+use libc::{mmap, MAP_ANON, MAP_PRIVATE, PROT_READ, PROT_WRITE};
+
+use perf_course::svg_chart::{svg_file_name, AxisFormat, LineChart};
+use perf_course::sweep::{ParameterGenerator, Sweep, SweepMetric};
+
+use crate::Params;
+
+extern "C" {
+    fn ReadBufferDoubleLoopTest(outer_loop_count: u64, data: *mut u8, inner_loop_count: u64);
+}
+
+// ReadBufferDoubleLoopTest reads 768 bytes per inner iteration
+const READ_BLOCK_SIZE: u64 = 768;
+
+pub fn run(params: &Params) -> Result<(), Box<dyn Error>> {
+    let args = &params.args;
+    let csv_file = args
+        .get(1)
+        .map(|s| s.to_string())
+        .unwrap_or("cache_size_sweep.csv".to_string());
+    let points = args
+        .get(2)
+        .map(|s| s.parse::<usize>().expect("Invalid point count"))
+        .unwrap_or(64);
+
+    let total_size = params.size_or(1024 * 1024 * 1024) as usize;
+    let addr = unsafe {
+        mmap(
+            ptr::null_mut(), // Address at which to start the mapping (nullptr lets the OS choose)
+            total_size,      // Number of bytes to map
+            PROT_READ | PROT_WRITE, // Enable read and write access
+            MAP_PRIVATE | MAP_ANON,
+            -1, // File descriptor not used with MAP_ANON
+            0,  // Offset not used with MAP_ANON
+        )
+    };
+
+    let buffer = unsafe { std::slice::from_raw_parts_mut(addr as *mut u8, total_size) };
+    for (i, byte) in buffer.iter_mut().enumerate() {
+        *byte = i as u8;
+    }
+
+    let generator = ParameterGenerator::LogSpaced {
+        start: 4 * 1024,
+        // the kernel reads a whole region, it can't be bigger than the buffer
+        end: (256 * 1024 * 1024).min(total_size as u64),
+        points,
+    };
+    let sweep = Sweep {
+        name: "Cache Size".to_string(),
+        parameter_label: "Region Size".to_string(),
+        generator: ParameterGenerator::List(generator.aligned_values(READ_BLOCK_SIZE)),
+        metric: SweepMetric::Bandwidth,
+        seconds_to_try: params.seconds_or(2),
+    };
+
+    let results = sweep.run(|region_size| {
+        let outer_loop_count = total_size as u64 / region_size;
+        unsafe {
+            ReadBufferDoubleLoopTest(outer_loop_count, buffer.as_mut_ptr(), region_size);
+        }
+        outer_loop_count * region_size
+    });
+
+    results.print_table();
+    results.write_csv(&csv_file)?;
+    println!("Results written to {}", csv_file);
+
+    let chart_file = svg_file_name(&csv_file);
+    LineChart::from_sweep(&results, AxisFormat::Size, true).save(&chart_file)?;
+    println!("Chart written to {}", chart_file);
+
+    Ok(())
+}
@@ -0,0 +1,245 @@
+use std::error::Error;
+use std::fs::File;
+use std::io::Write;
+use std::ptr;
+
+use perf_course::jit::{ChainOp, JitKernel, KernelShape};
+use perf_course::perf_metrics::estimate_cpu_frequency;
+use perf_course::svg_chart::{svg_file_name, AxisFormat, LineChart, Series};
+use perf_course::sweep::{format_size, ParameterGenerator, Sweep, SweepMetric};
+
+use crate::Params;
+
+// loop iterations per timed run
+const ITERATIONS: u64 = 1024 * 1024;
+// fetch works on whole cache lines, the uop cache of most Intel cores maps
+// 32 byte windows
+const LINE_SIZE: usize = 64;
+const FETCH_WINDOW: usize = 32;
+// arm64 instructions have to sit on 4 byte boundaries
+const INSTRUCTION_ALIGNMENT: usize = if cfg!(target_arch = "aarch64") { 4 } else { 1 };
+
+// Loops small enough to fit one fetch window up to ones spanning several
+// lines. The 4 byte nops encode on both architectures.
+fn shapes() -> Vec<KernelShape> {
+    vec![
+        KernelShape::NopLoop {
+            nop_count: 1,
+            nop_width: 4,
+        },
+        KernelShape::NopLoop {
+            nop_count: 8,
+            nop_width: 4,
+        },
+        KernelShape::NopLoop {
+            nop_count: 32,
+            nop_width: 4,
+        },
+        KernelShape::DependencyChain {
+            op: ChainOp::Add,
+            length: 4,
+            chains: 4,
+        },
+    ]
+}
+
+// how many `block` byte blocks `size` bytes starting at `offset` touch
+fn blocks_spanned(offset: usize, size: usize, block: usize) -> usize {
+    (offset + size - 1) / block - offset / block + 1
+}
+
+struct Placement {
+    name: String,
+    code_size: usize,
+    // (offset, cycles per loop iteration)
+    points: Vec<(u64, f64)>,
+}
+
+impl Placement {
+    // average cycles per iteration grouped by how many `block` byte blocks the
+    // code spans
+    fn by_blocks_spanned(&self, block: usize) -> Vec<(usize, f64)> {
+        let mut groups: Vec<(usize, f64, u64)> = Vec::new();
+        for (offset, cycles) in &self.points {
+            let spanned = blocks_spanned(*offset as usize, self.code_size, block);
+            match groups.iter_mut().find(|(count, _, _)| *count == spanned) {
+                Some(group) => {
+                    group.1 += cycles;
+                    group.2 += 1;
+                }
+                None => groups.push((spanned, *cycles, 1)),
+            }
+        }
+        groups.sort_by_key(|(count, _, _)| *count);
+        groups
+            .iter()
+            .map(|(count, total, points)| (*count, total / *points as f64))
+            .collect()
+    }
+}
+
+// Times every kernel shape with its code copied `offset` bytes into a fresh
+// page, for every offset of the window. Nothing about the code changes but
+// where it sits, so any difference is fetch, decode or the uop cache.
+//
+// code_alignment [window] [step] [csv_file]
+pub fn run(params: &Params) -> Result<(), Box<dyn Error>> {
+    let args = &params.args;
+    let window = args
+        .get(1)
+        .map(|s| s.parse::<usize>().expect("Invalid window"))
+        .unwrap_or(LINE_SIZE);
+    if window < INSTRUCTION_ALIGNMENT {
+        return Err(format!(
+            "The window can't be smaller than the {}b instruction alignment",
+            INSTRUCTION_ALIGNMENT
+        )
+        .into());
+    }
+    // 64 offsets whatever the window, a 4Kb window in single bytes would take
+    // hours
+    let step = args
+        .get(2)
+        .map(|s| s.parse::<usize>().expect("Invalid step"))
+        .unwrap_or(window / 64)
+        .next_multiple_of(INSTRUCTION_ALIGNMENT)
+        .max(INSTRUCTION_ALIGNMENT);
+    let csv_file = args
+        .get(3)
+        .cloned()
+        .unwrap_or("code_alignment.csv".to_string());
+
+    let frequency = estimate_cpu_frequency();
+    println!("CPU frequency: {:.2} GHz", frequency / 1e9);
+    println!(
+        "Offsets 0 to {} every {} bytes",
+        format_size(window as u64),
+        step
+    );
+
+    let offsets: Vec<u64> = (0..window).step_by(step).map(|o| o as u64).collect();
+
+    let mut placements = Vec::new();
+    for shape in shapes() {
+        let Some(code) = shape.emit() else {
+            println!("\n{} not supported here", shape.name());
+            continue;
+        };
+        let name = format!("{} ({}b)", shape.name(), code.len());
+
+        let sweep = Sweep {
+            name: name.clone(),
+            parameter_label: "Offset".to_string(),
+            generator: ParameterGenerator::List(offsets.clone()),
+            metric: SweepMetric::Seconds,
+            seconds_to_try: params.seconds_or(1),
+        };
+
+        // the kernel is placed on the untimed warm-up call of every point
+        let mut built_for = None;
+        let mut kernel: Option<JitKernel> = None;
+        let results = sweep.run(|offset| {
+            if built_for != Some(offset) {
+                kernel = JitKernel::at_offset(&code, offset as usize);
+                built_for = Some(offset);
+            }
+
+            // none of the shapes touch memory
+            if let Some(kernel) = &kernel {
+                unsafe {
+                    (kernel.function())(ITERATIONS, ptr::null_mut());
+                }
+            }
+            ITERATIONS
+        });
+
+        placements.push(Placement {
+            name,
+            code_size: code.len(),
+            points: results
+                .points
+                .iter()
+                .map(|point| {
+                    (
+                        point.parameter,
+                        point.seconds * frequency / ITERATIONS as f64,
+                    )
+                })
+                .collect(),
+        });
+    }
+
+    let names: Vec<&str> = placements.iter().map(|p| p.name.as_str()).collect();
+    let mut file = File::create(&csv_file)?;
+    writeln!(file, "Offset, {}", names.join(", "))?;
+    println!("\n===== Cycles Per Iteration =====");
+    println!("Offset, {}", names.join(", "));
+    for (index, offset) in offsets.iter().enumerate() {
+        let printed: Vec<String> = placements
+            .iter()
+            .map(|p| format!("{:.2}", p.points[index].1))
+            .collect();
+        let written: Vec<String> = placements
+            .iter()
+            .map(|p| p.points[index].1.to_string())
+            .collect();
+        println!("{}, {}", offset, printed.join(", "));
+        writeln!(file, "{}, {}", offset, written.join(", "))?;
+    }
+
+    LineChart {
+        title: "Cycles Per Iteration By Code Offset".to_string(),
+        x_label: "Offset".to_string(),
+        y_label: "Cycles/iteration".to_string(),
+        x_format: AxisFormat::Number,
+        log_x: false,
+        series: placements
+            .iter()
+            .map(|p| Series {
+                name: p.name.clone(),
+                points: p.points.iter().map(|(o, c)| (*o as f64, *c)).collect(),
+            })
+            .collect(),
+    }
+    .save(&svg_file_name(&csv_file))?;
+    println!("\nResults written to {}", csv_file);
+
+    println!("\n===== Placement Sensitivity =====");
+    for placement in &placements {
+        let fastest = placement
+            .points
+            .iter()
+            .min_by(|a, b| a.1.total_cmp(&b.1))
+            .unwrap();
+        let slowest = placement
+            .points
+            .iter()
+            .max_by(|a, b| a.1.total_cmp(&b.1))
+            .unwrap();
+        println!(
+            "{}: fastest at offset {} ({:.2} cycles), slowest at {} ({:.2} cycles), {:.2}x spread",
+            placement.name,
+            fastest.0,
+            fastest.1,
+            slowest.0,
+            slowest.1,
+            slowest.1 / fastest.1
+        );
+    }
+
+    // Offsets where the same code straddles one more boundary are where the
+    // front end has to fetch or look up one more block per iteration.
+    for (label, block) in [("Cache Lines", LINE_SIZE), ("32b Windows", FETCH_WINDOW)] {
+        println!("\n===== Average Cycles By {} Spanned =====", label);
+        for placement in &placements {
+            let groups: Vec<String> = placement
+                .by_blocks_spanned(block)
+                .iter()
+                .map(|(count, cycles)| format!("{}: {:.2}", count, cycles))
+                .collect();
+            println!("{}: {}", placement.name, groups.join(", "));
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,238 @@
+use std::error::Error;
+use std::fs::File;
+use std::io::Write;
+use std::ptr;
+
+use libc::{mmap, MAP_ANON, MAP_PRIVATE, PROT_READ, PROT_WRITE};
+
+use perf_course::jit::{JitKernel, KernelShape};
+use perf_course::svg_chart::{svg_file_name, HeatMap};
+use perf_course::sweep::{ParameterGenerator, Sweep, SweepMetric};
+
+use crate::Params;
+
+// loop iterations per timed run
+const ITERATIONS: u64 = 1024 * 1024;
+const LINE_SIZE: usize = 64;
+const WIDTHS: [usize; 7] = [1, 2, 4, 8, 16, 32, 64];
+
+// Where the swept line starts: in the middle of a page, so only the line
+// boundary can be crossed, or as the last line of a page, so the loads past
+// its end cross into the next page as well.
+#[derive(Debug, Copy, Clone, PartialEq)]
+enum Boundary {
+    Line,
+    Page,
+}
+
+impl Boundary {
+    fn name(&self) -> &'static str {
+        match self {
+            Boundary::Line => "Line",
+            Boundary::Page => "Page",
+        }
+    }
+
+    fn base(&self, page_size: usize) -> usize {
+        match self {
+            Boundary::Line => page_size / 2,
+            Boundary::Page => page_size - LINE_SIZE,
+        }
+    }
+}
+
+struct AlignmentRow {
+    boundary: Boundary,
+    width: usize,
+    // (offset into the line, GB/s)
+    points: Vec<(u64, f64)>,
+}
+
+impl AlignmentRow {
+    fn splits(&self, offset: u64) -> bool {
+        offset as usize % LINE_SIZE + self.width > LINE_SIZE
+    }
+
+    // average GB/s of the loads that stay in one line and of the ones that
+    // don't, None when every offset falls on the same side
+    fn split_averages(&self) -> (Option<f64>, Option<f64>) {
+        let average = |split: bool| {
+            let values: Vec<f64> = self
+                .points
+                .iter()
+                .filter(|(offset, _)| self.splits(*offset) == split)
+                .map(|(_, bandwidth)| *bandwidth)
+                .collect();
+            if values.is_empty() {
+                None
+            } else {
+                Some(values.iter().sum::<f64>() / values.len() as f64)
+            }
+        };
+        (average(false), average(true))
+    }
+}
+
+// Every read width loading over and over from one address at each offset of a
+// 64 byte line, once with the line in the middle of a page and once with it
+// ending the page. Loads that run past the line pay the split-line penalty,
+// past the page the split-page one.
+//
+// At one second per point the full sweep takes a while, --seconds can't go
+// lower but the step can skip offsets.
+//
+// data_alignment [step] [csv_file]
+pub fn run(params: &Params) -> Result<(), Box<dyn Error>> {
+    let args = &params.args;
+    let step = args
+        .get(1)
+        .map(|s| s.parse::<usize>().expect("Invalid step"))
+        .unwrap_or(1)
+        .max(1);
+    let csv_file = args
+        .get(2)
+        .cloned()
+        .unwrap_or("data_alignment.csv".to_string());
+
+    let page_size = unsafe { libc::sysconf(libc::_SC_PAGESIZE) } as usize;
+    let total_size = page_size * 2;
+    let addr = unsafe {
+        mmap(
+            ptr::null_mut(), // Address at which to start the mapping (nullptr lets the OS choose)
+            total_size,      // Number of bytes to map
+            PROT_READ | PROT_WRITE, // Enable read and write access
+            MAP_PRIVATE | MAP_ANON,
+            -1, // File descriptor not used with MAP_ANON
+            0,  // Offset not used with MAP_ANON
+        )
+    };
+    let buffer = unsafe { std::slice::from_raw_parts_mut(addr as *mut u8, total_size) };
+    // both pages have to be there, the page split otherwise measures a fault
+    buffer.fill(1);
+
+    let offsets: Vec<u64> = (0..LINE_SIZE as u64).step_by(step).collect();
+
+    let mut rows = Vec::new();
+    for boundary in [Boundary::Line, Boundary::Page] {
+        for width in WIDTHS {
+            let shape = KernelShape::Load { width, unroll: 1 };
+            let Some(kernel) = shape.emit().and_then(|code| JitKernel::new(&code)) else {
+                println!("\n{} byte loads not supported here", width);
+                continue;
+            };
+
+            let sweep = Sweep {
+                name: shape.name(),
+                parameter_label: "Offset".to_string(),
+                generator: ParameterGenerator::List(offsets.clone()),
+                metric: SweepMetric::Bandwidth,
+                seconds_to_try: params.seconds_or(1),
+            };
+            println!("\n{} boundary, {} byte loads", boundary.name(), width);
+            let base = boundary.base(page_size);
+            let count = ITERATIONS * shape.count_per_iteration();
+            let results = sweep.run(|offset| {
+                unsafe {
+                    let data = buffer.as_mut_ptr().add(base + offset as usize);
+                    (kernel.function())(count, data);
+                }
+                count
+            });
+
+            rows.push(AlignmentRow {
+                boundary,
+                width,
+                points: results
+                    .points
+                    .iter()
+                    .map(|point| (point.parameter, point.metric))
+                    .collect(),
+            });
+        }
+    }
+
+    let mut file = File::create(&csv_file)?;
+    writeln!(file, "Boundary, Width, Offset, GB/s")?;
+    for row in &rows {
+        for (offset, bandwidth) in &row.points {
+            writeln!(
+                file,
+                "{}, {}, {}, {}",
+                row.boundary.name(),
+                row.width,
+                offset,
+                bandwidth
+            )?;
+        }
+    }
+
+    for boundary in [Boundary::Line, Boundary::Page] {
+        let boundary_rows: Vec<&AlignmentRow> =
+            rows.iter().filter(|row| row.boundary == boundary).collect();
+
+        println!("\n===== {} Boundary GB/s =====", boundary.name());
+        let header: Vec<String> = offsets.iter().map(|o| o.to_string()).collect();
+        println!("Width, {}", header.join(", "));
+        for row in &boundary_rows {
+            let values: Vec<String> = row
+                .points
+                .iter()
+                .map(|(_, v)| format!("{:.1}", v))
+                .collect();
+            println!("{}, {}", row.width, values.join(", "));
+        }
+
+        HeatMap {
+            title: format!("Load Bandwidth Across A {} Boundary", boundary.name()),
+            x_label: "Offset Into The Line".to_string(),
+            y_label: "Load Width".to_string(),
+            // every width against its own aligned loads, or the wide loads
+            // would wash out the rest of the scale
+            value_label: "x Aligned GB/s".to_string(),
+            x_values: header,
+            y_values: boundary_rows
+                .iter()
+                .map(|row| format!("{}b", row.width))
+                .collect(),
+            cells: boundary_rows
+                .iter()
+                .map(|row| {
+                    let aligned = row.points[0].1;
+                    row.points.iter().map(|(_, v)| Some(v / aligned)).collect()
+                })
+                .collect(),
+        }
+        .save(&svg_file_name(
+            &csv_file.replace(".csv", &format!("_{}.csv", boundary.name().to_lowercase())),
+        ))?;
+    }
+    println!("\nResults written to {}", csv_file);
+
+    // Offsets that keep a load inside its line against the ones that split it.
+    // On the page boundary the split loads also touch the next page and its
+    // TLB entry.
+    println!("\n===== Split Penalties =====");
+    println!("Boundary, Width, Aligned GB/s, Unsplit GB/s, Split GB/s, Split Cost");
+    for row in &rows {
+        let (unsplit, split) = row.split_averages();
+        let format = |value: Option<f64>| match value {
+            Some(value) => format!("{:.2}", value),
+            None => "-".to_string(),
+        };
+        let cost = match (unsplit, split) {
+            (Some(unsplit), Some(split)) => format!("{:.2}x", unsplit / split),
+            _ => "never splits".to_string(),
+        };
+        println!(
+            "{}, {}, {:.2}, {}, {}, {}",
+            row.boundary.name(),
+            row.width,
+            row.points[0].1,
+            format(unsplit),
+            format(split),
+            cost
+        );
+    }
+
+    Ok(())
+}
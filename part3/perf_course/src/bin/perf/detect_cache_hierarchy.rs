@@ -0,0 +1,104 @@
+use std::error::Error;
+use std::ptr;
+
+use libc::{mmap, MAP_ANON, MAP_PRIVATE, PROT_READ, PROT_WRITE};
+
+use perf_course::cache_hierarchy::{detect_cache_levels, print_report, system_caches};
+use perf_course::svg_chart::{read_csv, svg_file_name, AxisFormat, LineChart};
+use perf_course::sweep::{ParameterGenerator, Sweep, SweepMetric};
+
+use crate::Params;
+
+extern "C" {
+    fn ReadBufferDoubleLoopTest(outer_loop_count: u64, data: *mut u8, inner_loop_count: u64);
+}
+
+// ReadBufferDoubleLoopTest reads 768 bytes per inner iteration
+const READ_BLOCK_SIZE: u64 = 768;
+
+fn run_sweep(csv_file: &str, params: &Params) -> Result<Vec<(u64, f64)>, Box<dyn Error>> {
+    let total_size = params.size_or(1024 * 1024 * 1024) as usize;
+    let addr = unsafe {
+        mmap(
+            ptr::null_mut(), // Address at which to start the mapping (nullptr lets the OS choose)
+            total_size,      // Number of bytes to map
+            PROT_READ | PROT_WRITE, // Enable read and write access
+            MAP_PRIVATE | MAP_ANON,
+            -1, // File descriptor not used with MAP_ANON
+            0,  // Offset not used with MAP_ANON
+        )
+    };
+
+    let buffer = unsafe { std::slice::from_raw_parts_mut(addr as *mut u8, total_size) };
+    for (i, byte) in buffer.iter_mut().enumerate() {
+        *byte = i as u8;
+    }
+
+    // ~12 points per doubling is enough to place a knee within 6%
+    let generator = ParameterGenerator::LogSpaced {
+        start: 4 * 1024,
+        end: total_size as u64 / 2,
+        points: 200,
+    };
+    let sweep = Sweep {
+        name: "Cache Hierarchy".to_string(),
+        parameter_label: "Region Size".to_string(),
+        generator: ParameterGenerator::List(generator.aligned_values(READ_BLOCK_SIZE)),
+        metric: SweepMetric::Bandwidth,
+        seconds_to_try: params.seconds_or(1),
+    };
+
+    let results = sweep.run(|region_size| {
+        let outer_loop_count = total_size as u64 / region_size;
+        unsafe {
+            ReadBufferDoubleLoopTest(outer_loop_count, buffer.as_mut_ptr(), region_size);
+        }
+        outer_loop_count * region_size
+    });
+
+    results.write_csv(csv_file)?;
+    LineChart::from_sweep(&results, AxisFormat::Size, true).save(&svg_file_name(csv_file))?;
+    println!("\nResults written to {}", csv_file);
+
+    Ok(results
+        .points
+        .iter()
+        .map(|point| (point.parameter, point.metric))
+        .collect())
+}
+
+fn load_sweep(csv_file: &str) -> Result<Vec<(u64, f64)>, Box<dyn Error>> {
+    let (_, rows) = read_csv(csv_file)?;
+    Ok(rows
+        .iter()
+        .filter_map(|row| {
+            let size = row.first()?.parse::<u64>().ok()?;
+            let bandwidth = row.get(1)?.parse::<f64>().ok()?;
+            Some((size, bandwidth))
+        })
+        .collect())
+}
+
+// detect_cache_hierarchy                  runs a fresh sweep into cache_hierarchy.csv
+// detect_cache_hierarchy --csv <file>     analyzes a sweep saved by cache_size_sweep
+pub fn run(params: &Params) -> Result<(), Box<dyn Error>> {
+    let args = &params.args;
+
+    let mut points = match args.get(1).map(|s| &s[..]) {
+        Some("--csv") => {
+            let csv_file = args.get(2).expect("Usage: --csv <file>");
+            load_sweep(csv_file)?
+        }
+        Some(_) => {
+            eprintln!("Usage: {} [--csv <file>]", args[0]);
+            std::process::exit(1);
+        }
+        None => run_sweep("cache_hierarchy.csv", params)?,
+    };
+    points.sort_by_key(|(size, _)| *size);
+
+    let levels = detect_cache_levels(&points);
+    print_report(&levels, &system_caches());
+
+    Ok(())
+}
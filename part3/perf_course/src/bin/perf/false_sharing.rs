@@ -0,0 +1,328 @@
+use std::error::Error;
+use std::fs::File;
+use std::io::Write;
+use std::ptr;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::thread::{self, JoinHandle};
+
+use libc::{mmap, MAP_ANON, MAP_PRIVATE, PROT_READ, PROT_WRITE};
+
+use perf_course::svg_chart::{svg_file_name, AxisFormat, LineChart, Series};
+use perf_course::sweep::{format_size, ParameterGenerator, Sweep, SweepMetric};
+
+use crate::Params;
+
+// increments per thread per timed run
+const INCREMENTS: u64 = 1024 * 1024;
+// distance between two threads' counters: the same counter, the same line,
+// neighbouring lines, the other line of a 128 byte pair and separate pages
+const DISTANCES: [u64; 5] = [0, 8, 64, 128, 4096];
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+enum StoreMode {
+    // load, add, store through volatile pointers, the line still has to move
+    // to whichever core stores to it but nothing is locked
+    Plain,
+    // lock xadd / ldadd, the line is held exclusively for the whole update
+    Atomic,
+}
+
+impl StoreMode {
+    fn name(&self) -> &'static str {
+        match self {
+            StoreMode::Plain => "Plain",
+            StoreMode::Atomic => "Atomic",
+        }
+    }
+}
+
+fn distance_name(distance: u64) -> String {
+    match distance {
+        0 => "Shared Counter".to_string(),
+        d if d < 64 => format!("Same Line ({}b apart)", d),
+        d => format!("{} apart", format_size(d)),
+    }
+}
+
+// One thread's share of a run, INCREMENTS on its own counter.
+fn increment_counter(counter: usize, mode: StoreMode) {
+    match mode {
+        StoreMode::Plain => {
+            let counter = counter as *mut u64;
+            for _ in 0..INCREMENTS {
+                unsafe {
+                    ptr::write_volatile(counter, ptr::read_volatile(counter) + 1);
+                }
+            }
+        }
+        StoreMode::Atomic => {
+            let counter = unsafe { &*(counter as *const AtomicU64) };
+            for _ in 0..INCREMENTS {
+                counter.fetch_add(1, Ordering::Relaxed);
+            }
+        }
+    }
+}
+
+// The threads of one sweep point, their counters `distance` bytes apart from
+// `base`. They are spawned on the untimed warm-up call so the timed runs hold
+// nothing but the counting: the threads wait on a generation number to all
+// start hammering at the same time, and count themselves off when done.
+// Waiting yields, with more threads than cores a spinning one would take a
+// whole time slice from one that still has counting to do.
+struct CounterThreads {
+    threads: u64,
+    generation: Arc<AtomicU64>,
+    finished: Arc<AtomicU64>,
+    handles: Vec<JoinHandle<()>>,
+}
+
+// the generation that tells the threads to exit
+const STOP: u64 = u64::MAX;
+
+impl CounterThreads {
+    fn spawn(base: usize, distance: u64, threads: u64, mode: StoreMode) -> CounterThreads {
+        let generation = Arc::new(AtomicU64::new(0));
+        let finished = Arc::new(AtomicU64::new(0));
+        let handles = (0..threads)
+            .map(|index| {
+                let counter = base + (index * distance) as usize;
+                let generation = Arc::clone(&generation);
+                let finished = Arc::clone(&finished);
+                thread::spawn(move || {
+                    let mut seen = 0;
+                    loop {
+                        let current = generation.load(Ordering::Acquire);
+                        if current == seen {
+                            thread::yield_now();
+                            continue;
+                        }
+                        if current == STOP {
+                            break;
+                        }
+                        seen = current;
+                        increment_counter(counter, mode);
+                        finished.fetch_add(1, Ordering::Release);
+                    }
+                })
+            })
+            .collect();
+
+        CounterThreads {
+            threads,
+            generation,
+            finished,
+            handles,
+        }
+    }
+
+    // One round of INCREMENTS on every thread, returns once they are all done.
+    fn run(&self) {
+        let target = self.finished.load(Ordering::Acquire) + self.threads;
+        self.generation.fetch_add(1, Ordering::Release);
+        while self.finished.load(Ordering::Acquire) < target {
+            thread::yield_now();
+        }
+    }
+}
+
+impl Drop for CounterThreads {
+    fn drop(&mut self) {
+        self.generation.store(STOP, Ordering::Release);
+        for handle in self.handles.drain(..) {
+            let _ = handle.join();
+        }
+    }
+}
+
+struct Contention {
+    mode: StoreMode,
+    distance: u64,
+    // (threads, nanoseconds per increment seen by each thread)
+    points: Vec<(u64, f64)>,
+}
+
+// false_sharing [max_threads] [csv_file]
+pub fn run(params: &Params) -> Result<(), Box<dyn Error>> {
+    let args = &params.args;
+    let max_threads = args
+        .get(1)
+        .map(|s| s.parse::<u64>().expect("Invalid thread count"))
+        .unwrap_or_else(|| {
+            thread::available_parallelism()
+                .map(|count| count.get() as u64)
+                .unwrap_or(4)
+        });
+    let csv_file = args
+        .get(2)
+        .cloned()
+        .unwrap_or("false_sharing.csv".to_string());
+
+    let mut thread_counts: Vec<u64> = ParameterGenerator::PowerOfTwo {
+        start: 1,
+        end: max_threads,
+    }
+    .values();
+    if thread_counts.last() != Some(&max_threads) {
+        thread_counts.push(max_threads);
+    }
+    let cores = thread::available_parallelism()
+        .map(|count| count.get() as u64)
+        .unwrap_or(max_threads);
+    if max_threads > cores {
+        eprintln!(
+            "[false_sharing] {} threads on {} cores, the threads will take turns instead of contending",
+            max_threads, cores
+        );
+    }
+
+    // page aligned, so the first counter starts a line and a page
+    let total_size = (max_threads * DISTANCES[DISTANCES.len() - 1]) as usize;
+    let addr = unsafe {
+        mmap(
+            ptr::null_mut(), // Address at which to start the mapping (nullptr lets the OS choose)
+            total_size,      // Number of bytes to map
+            PROT_READ | PROT_WRITE, // Enable read and write access
+            MAP_PRIVATE | MAP_ANON,
+            -1, // File descriptor not used with MAP_ANON
+            0,  // Offset not used with MAP_ANON
+        )
+    };
+    let base = addr as usize;
+
+    let mut contentions = Vec::new();
+    for mode in [StoreMode::Atomic, StoreMode::Plain] {
+        for distance in DISTANCES {
+            // plain stores to one shared counter would just be a data race
+            if mode == StoreMode::Plain && distance == 0 {
+                continue;
+            }
+
+            // one name per setup, every wave in the report says which it was
+            let sweep = Sweep {
+                name: format!("{} {}", mode.name(), distance_name(distance)),
+                parameter_label: "Threads".to_string(),
+                generator: ParameterGenerator::List(thread_counts.clone()),
+                metric: SweepMetric::Seconds,
+                seconds_to_try: params.seconds_or(1),
+            };
+            let mut counter_threads: Option<CounterThreads> = None;
+            let results = sweep.run(|threads| {
+                if counter_threads.as_ref().map(|c| c.threads) != Some(threads) {
+                    // the old threads have to exit before the new ones spin up
+                    counter_threads = None;
+                    counter_threads = Some(CounterThreads::spawn(base, distance, threads, mode));
+                }
+                counter_threads.as_ref().unwrap().run();
+                threads * INCREMENTS * std::mem::size_of::<u64>() as u64
+            });
+            drop(counter_threads);
+
+            contentions.push(Contention {
+                mode,
+                distance,
+                points: results
+                    .points
+                    .iter()
+                    .map(|point| (point.parameter, point.seconds * 1e9 / INCREMENTS as f64))
+                    .collect(),
+            });
+        }
+    }
+
+    let mut file = File::create(&csv_file)?;
+    writeln!(
+        file,
+        "Mode, Distance, Threads, Nanoseconds Per Increment, Million Increments Per Second"
+    )?;
+    println!("\n===== Counter Throughput =====");
+    println!("Mode, Counters, Threads, ns/increment, M increments/s");
+    for contention in &contentions {
+        for (threads, nanoseconds) in &contention.points {
+            // every thread does its increments in parallel with the others
+            let throughput = *threads as f64 * 1e3 / nanoseconds;
+            println!(
+                "{}, {}, {}, {:.2}, {:.1}",
+                contention.mode.name(),
+                distance_name(contention.distance),
+                threads,
+                nanoseconds,
+                throughput
+            );
+            writeln!(
+                file,
+                "{}, {}, {}, {}, {}",
+                contention.mode.name(),
+                contention.distance,
+                threads,
+                nanoseconds,
+                throughput
+            )?;
+        }
+    }
+
+    for mode in [StoreMode::Atomic, StoreMode::Plain] {
+        let series: Vec<Series> = contentions
+            .iter()
+            .filter(|contention| contention.mode == mode)
+            .map(|contention| Series {
+                name: distance_name(contention.distance),
+                points: contention
+                    .points
+                    .iter()
+                    .map(|(threads, nanoseconds)| {
+                        (*threads as f64, *threads as f64 * 1e3 / nanoseconds)
+                    })
+                    .collect(),
+            })
+            .collect();
+        LineChart {
+            title: format!("{} Increments", mode.name()),
+            x_label: "Threads".to_string(),
+            y_label: "Million increments/s".to_string(),
+            x_format: AxisFormat::Number,
+            log_x: false,
+            series,
+        }
+        .save(&svg_file_name(
+            &csv_file.replace(".csv", &format!("_{}.csv", mode.name().to_lowercase())),
+        ))?;
+    }
+    println!("\nResults written to {}", csv_file);
+
+    // Counters a page apart never share a line, whatever they cost on top of
+    // that is the line moving between cores.
+    println!("\n===== Cache Line Ping-Pong =====");
+    if max_threads < 2 {
+        println!("Needs at least 2 threads, one thread has no one to bounce the line with");
+    }
+    for mode in [StoreMode::Atomic, StoreMode::Plain] {
+        let mode_contentions: Vec<&Contention> = contentions
+            .iter()
+            .filter(|contention| contention.mode == mode)
+            .collect();
+        let separate = mode_contentions.last().unwrap();
+        for contention in &mode_contentions[..mode_contentions.len() - 1] {
+            let Some(((threads, shared), (_, alone))) = contention
+                .points
+                .iter()
+                .zip(separate.points.iter())
+                .rfind(|((threads, _), _)| *threads > 1)
+            else {
+                continue;
+            };
+            println!(
+                "{} {}: {:.2} ns per increment with {} threads, {:.2} ns of it ping-pong ({:.1}x separate pages)",
+                mode.name(),
+                distance_name(contention.distance),
+                shared,
+                threads,
+                (shared - alone).max(0.0),
+                shared / alone
+            );
+        }
+    }
+
+    Ok(())
+}
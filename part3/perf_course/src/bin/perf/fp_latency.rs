@@ -0,0 +1,96 @@
+use std::error::Error;
+use std::fs::File;
+use std::io::Write;
+
+use perf_course::fp_chains::{run_chains, ChainResults, FLOAT_OPS};
+use perf_course::perf_metrics::estimate_cpu_frequency;
+use perf_course::svg_chart::{svg_file_name, AxisFormat, LineChart, Series};
+
+use crate::Params;
+
+// Rust take on part5's listing-202: FMA, mul, add, div and sqrt dependency
+// chains with more and more independent accumulators.
+//
+// fp_latency [max_chains] [csv_file]
+pub fn run(params: &Params) -> Result<(), Box<dyn Error>> {
+    let args = &params.args;
+    let max_chains = args
+        .get(1)
+        .map(|s| s.parse::<u64>().expect("Invalid chain count"))
+        .unwrap_or(24);
+    let seconds_to_try = params.seconds_or(1);
+    let csv_file = args.get(2).cloned().unwrap_or("fp_latency.csv".to_string());
+
+    let frequency = estimate_cpu_frequency();
+    println!("CPU frequency: {:.2} GHz", frequency / 1e9);
+
+    let mut results = Vec::new();
+    for op in FLOAT_OPS {
+        match run_chains(op, max_chains, seconds_to_try, frequency) {
+            Some(chains) => results.push(chains),
+            None => println!("\n{:?} chains not supported here", op),
+        }
+    }
+
+    let mut file = File::create(&csv_file)?;
+    writeln!(file, "Op, Chains, Cycles Per Iteration, Ops Per Cycle")?;
+    println!("\n===== Ops Per Cycle =====");
+    for chains in &results {
+        let points: Vec<String> = chains
+            .points
+            .iter()
+            .map(|(count, cycles)| format!("{:.2}", ChainResults::ops_per_cycle(*count, *cycles)))
+            .collect();
+        println!("{:>6}: {}", format!("{:?}", chains.op), points.join(" "));
+        for (count, cycles) in &chains.points {
+            writeln!(
+                file,
+                "{:?}, {}, {}, {}",
+                chains.op,
+                count,
+                cycles,
+                ChainResults::ops_per_cycle(*count, *cycles)
+            )?;
+        }
+    }
+
+    // Little's law: keeping every port busy takes latency * throughput ops in
+    // flight, the measured count should land close to it
+    println!("\n===== FP Latency And Throughput =====");
+    println!("Op, Latency (cycles), Reciprocal Throughput (cycles), Chains To Saturate, Latency x Throughput");
+    for chains in &results {
+        println!(
+            "{:?}, {:.2}, {:.2}, {}, {:.1}",
+            chains.op,
+            chains.latency(),
+            chains.reciprocal_throughput(),
+            chains.saturating_chains(),
+            chains.latency() * chains.throughput()
+        );
+    }
+
+    LineChart {
+        title: "FP Ops Per Cycle".to_string(),
+        x_label: "Chains".to_string(),
+        y_label: "Ops/cycle".to_string(),
+        x_format: AxisFormat::Number,
+        log_x: false,
+        series: results
+            .iter()
+            .map(|chains| Series {
+                name: format!("{:?}", chains.op),
+                points: chains
+                    .points
+                    .iter()
+                    .map(|(count, cycles)| {
+                        (*count as f64, ChainResults::ops_per_cycle(*count, *cycles))
+                    })
+                    .collect(),
+            })
+            .collect(),
+    }
+    .save(&svg_file_name(&csv_file))?;
+    println!("\nResults written to {}", csv_file);
+
+    Ok(())
+}
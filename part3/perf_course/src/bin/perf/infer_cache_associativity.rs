@@ -0,0 +1,117 @@
+use std::error::Error;
+use std::ptr;
+
+use libc::{mmap, MAP_ANON, MAP_PRIVATE, PROT_READ, PROT_WRITE};
+
+use perf_course::cache_hierarchy::{
+    infer_associativity, print_associativity_report, print_stride_matrix, system_caches,
+    StrideCurve,
+};
+use perf_course::sweep::{format_size, ParameterGenerator, Sweep, SweepMetric};
+
+use crate::Params;
+
+extern "C" {
+    fn CacheStrideTest(pass_count: u64, data: *mut u8, line_count: u64, stride: u64);
+}
+
+const LINE_SIZE: u64 = 64;
+// CacheStrideTest loads 16 bytes from every line it touches
+const BYTES_PER_LINE: u64 = 16;
+// loads per timed run, enough to make the loop overhead noise
+const LOADS_PER_RUN: u64 = 1024 * 1024;
+
+// infer_cache_associativity [max_lines] [max_stride]
+pub fn run(params: &Params) -> Result<(), Box<dyn Error>> {
+    let args = &params.args;
+    let max_lines = args
+        .get(1)
+        .map(|s| s.parse::<u64>().expect("Invalid max lines"))
+        .unwrap_or(40);
+    let max_stride = args
+        .get(2)
+        .map(|s| s.parse::<u64>().expect("Invalid max stride"))
+        .unwrap_or(1024 * 1024);
+
+    let total_size = (max_lines * max_stride) as usize;
+    let addr = unsafe {
+        mmap(
+            ptr::null_mut(), // Address at which to start the mapping (nullptr lets the OS choose)
+            total_size,      // Number of bytes to map
+            PROT_READ | PROT_WRITE, // Enable read and write access
+            MAP_PRIVATE | MAP_ANON,
+            -1, // File descriptor not used with MAP_ANON
+            0,  // Offset not used with MAP_ANON
+        )
+    };
+
+    // the index bits of the outer levels go past the 4k page offset, huge pages
+    // keep them the same between virtual and physical addresses
+    #[cfg(target_os = "linux")]
+    unsafe {
+        libc::madvise(addr, total_size, libc::MADV_HUGEPAGE);
+    }
+
+    let buffer = unsafe { std::slice::from_raw_parts_mut(addr as *mut u8, total_size) };
+    for (i, byte) in buffer.iter_mut().enumerate() {
+        *byte = i as u8;
+    }
+
+    let strides = ParameterGenerator::PowerOfTwo {
+        start: LINE_SIZE,
+        end: max_stride,
+    }
+    .values();
+
+    let mut curves = Vec::new();
+    let mut latencies = Vec::new();
+    for stride in strides {
+        let sweep = Sweep {
+            name: "Cache Stride".to_string(),
+            parameter_label: "Lines Touched".to_string(),
+            generator: ParameterGenerator::Linear {
+                start: 1,
+                end: max_lines,
+                step: 1,
+            },
+            metric: SweepMetric::Bandwidth,
+            seconds_to_try: params.seconds_or(1),
+        };
+
+        println!("\n===== Stride {} =====", format_size(stride));
+        let results = sweep.run(|line_count| {
+            let pass_count = (LOADS_PER_RUN / line_count).max(1);
+            unsafe {
+                CacheStrideTest(pass_count, buffer.as_mut_ptr(), line_count, stride);
+            }
+            pass_count * line_count * BYTES_PER_LINE
+        });
+
+        curves.push(StrideCurve {
+            stride,
+            points: results
+                .points
+                .iter()
+                .map(|point| (point.parameter, point.metric))
+                .collect(),
+        });
+        latencies.push(StrideCurve {
+            stride,
+            points: results
+                .points
+                .iter()
+                .map(|point| {
+                    let loads = point.bytes / BYTES_PER_LINE;
+                    (point.parameter, point.seconds * 1e9 / loads.max(1) as f64)
+                })
+                .collect(),
+        });
+    }
+
+    print_stride_matrix(&latencies, "ns per load");
+
+    let estimates = infer_associativity(&curves, LINE_SIZE);
+    print_associativity_report(&estimates, &system_caches());
+
+    Ok(())
+}
@@ -0,0 +1,252 @@
+use std::error::Error;
+use std::fs::File;
+use std::io::Write;
+
+use perf_course::jit::{ChainOp, JitKernel, KernelShape};
+use perf_course::perf_metrics::estimate_cpu_frequency;
+use perf_course::svg_chart::{svg_file_name, AxisFormat, LineChart, Series};
+use perf_course::sweep::{ParameterGenerator, Sweep, SweepMetric};
+
+use crate::Params;
+
+// loop iterations per timed run
+const ITERATIONS: u64 = 1024 * 1024;
+const WIDTHS: [u64; 7] = [1, 2, 4, 8, 16, 32, 64];
+const UNROLLS: [u64; 5] = [1, 2, 4, 8, 16];
+const NOPS_PER_ITERATION: usize = 256;
+const CHAIN_LENGTH: usize = 16;
+
+// Cycles per loop iteration for every parameter whose shape can be generated
+// on this machine.
+fn run_shapes<F>(
+    name: &'static str,
+    parameter_label: &'static str,
+    parameters: &[u64],
+    seconds_to_try: u64,
+    frequency: f64,
+    data: &mut [u8],
+    make_shape: F,
+) -> Vec<(u64, f64)>
+where
+    F: Fn(u64) -> KernelShape,
+{
+    let supported: Vec<u64> = parameters
+        .iter()
+        .copied()
+        .filter(|parameter| make_shape(*parameter).emit().is_some())
+        .collect();
+    if supported.is_empty() {
+        return Vec::new();
+    }
+
+    let sweep = Sweep {
+        name: name.to_string(),
+        parameter_label: parameter_label.to_string(),
+        generator: ParameterGenerator::List(supported),
+        metric: SweepMetric::Seconds,
+        seconds_to_try,
+    };
+
+    // the kernel is generated on the untimed warm-up call of every point
+    let mut built_for = None;
+    let mut kernel: Option<JitKernel> = None;
+    let results = sweep.run(|parameter| {
+        let shape = make_shape(parameter);
+        if built_for != Some(parameter) {
+            kernel = shape.emit().and_then(|code| JitKernel::new(&code));
+            built_for = Some(parameter);
+        }
+
+        let count = ITERATIONS * shape.count_per_iteration();
+        if let Some(kernel) = &kernel {
+            unsafe {
+                (kernel.function())(count, data.as_mut_ptr());
+            }
+        }
+        count
+    });
+
+    results
+        .points
+        .iter()
+        .map(|point| {
+            (
+                point.parameter,
+                point.seconds * frequency / ITERATIONS as f64,
+            )
+        })
+        .collect()
+}
+
+// jit_kernels [csv_file]
+pub fn run(params: &Params) -> Result<(), Box<dyn Error>> {
+    let args = &params.args;
+    let seconds_to_try = params.seconds_or(1);
+    let csv_file = args
+        .get(1)
+        .cloned()
+        .unwrap_or("jit_kernels.csv".to_string());
+
+    let frequency = estimate_cpu_frequency();
+    println!("CPU frequency: {:.2} GHz", frequency / 1e9);
+
+    // big enough for the widest load times the largest unroll
+    let mut data = vec![0u8; 64 * 1024];
+
+    let mut file = File::create(&csv_file)?;
+    writeln!(file, "Kernel, Parameter, Cycles Per Iteration, Value")?;
+
+    // bytes per cycle over the unroll factor, one series per width
+    let mut load_series = Vec::new();
+    let mut store_series = Vec::new();
+    for width in WIDTHS {
+        for store in [false, true] {
+            let (name, series) = if store {
+                ("Store Unroll", &mut store_series)
+            } else {
+                ("Load Unroll", &mut load_series)
+            };
+            let points = run_shapes(
+                name,
+                "Unroll",
+                &UNROLLS,
+                seconds_to_try,
+                frequency,
+                &mut data,
+                |unroll| {
+                    let (width, unroll) = (width as usize, unroll as usize);
+                    if store {
+                        KernelShape::Store { width, unroll }
+                    } else {
+                        KernelShape::Load { width, unroll }
+                    }
+                },
+            );
+            if points.is_empty() {
+                println!("\n{} bytes wide {} not supported here", width, name);
+                continue;
+            }
+
+            let bytes_per_cycle: Vec<(f64, f64)> = points
+                .iter()
+                .map(|(unroll, cycles)| (*unroll as f64, (width * unroll) as f64 / cycles))
+                .collect();
+            for ((unroll, cycles), (_, bytes)) in points.iter().zip(bytes_per_cycle.iter()) {
+                writeln!(
+                    file,
+                    "{} {}b, {}, {}, {}",
+                    name, width, unroll, cycles, bytes
+                )?;
+            }
+            series.push(Series {
+                name: format!("{}b", width),
+                points: bytes_per_cycle,
+            });
+        }
+    }
+
+    let nops = run_shapes(
+        "NOP Width",
+        "Bytes",
+        &(1..=15).collect::<Vec<u64>>(),
+        seconds_to_try,
+        frequency,
+        &mut data,
+        |width| KernelShape::NopLoop {
+            nop_count: NOPS_PER_ITERATION,
+            nop_width: width as usize,
+        },
+    );
+    for (width, cycles) in &nops {
+        let nops_per_cycle = NOPS_PER_ITERATION as f64 / cycles;
+        writeln!(file, "NOP Width, {}, {}, {}", width, cycles, nops_per_cycle)?;
+    }
+
+    let mut chains = Vec::new();
+    for op in [ChainOp::Add, ChainOp::Mul] {
+        let points = run_shapes(
+            "Dependency Chains",
+            "Chains",
+            &(1..=12).collect::<Vec<u64>>(),
+            seconds_to_try,
+            frequency,
+            &mut data,
+            |count| KernelShape::DependencyChain {
+                op,
+                length: CHAIN_LENGTH,
+                chains: count as usize,
+            },
+        );
+        for (count, cycles) in &points {
+            let ops_per_cycle = (CHAIN_LENGTH as u64 * count) as f64 / cycles;
+            writeln!(
+                file,
+                "{:?} Chain, {}, {}, {}",
+                op, count, cycles, ops_per_cycle
+            )?;
+        }
+        chains.push((op, points));
+    }
+
+    println!("\n===== Bytes Per Cycle =====");
+    for (name, series) in [("Load", &load_series), ("Store", &store_series)] {
+        print!("{:>6}", name);
+        for unroll in UNROLLS {
+            print!("{:>8}", format!("x{}", unroll));
+        }
+        println!();
+        for s in series.iter() {
+            print!("{:>6}", s.name);
+            for (_, bytes) in &s.points {
+                print!("{:>8.2}", bytes);
+            }
+            println!();
+        }
+    }
+
+    println!("\n===== NOPs =====");
+    println!("Width, NOPs/cycle, Bytes/cycle");
+    for (width, cycles) in &nops {
+        let nops_per_cycle = NOPS_PER_ITERATION as f64 / cycles;
+        println!(
+            "{}, {:.2}, {:.2}",
+            width,
+            nops_per_cycle,
+            nops_per_cycle * *width as f64
+        );
+    }
+
+    // one chain gives the latency, enough chains give the throughput
+    println!("\n===== Dependency Chains =====");
+    for (op, points) in &chains {
+        let latency = points
+            .first()
+            .map(|(_, cycles)| cycles / CHAIN_LENGTH as f64)
+            .unwrap_or(0.0);
+        let throughput = points
+            .iter()
+            .map(|(count, cycles)| (CHAIN_LENGTH as u64 * count) as f64 / cycles)
+            .fold(0.0, f64::max);
+        println!(
+            "{:?}: latency {:.2} cycles, throughput {:.2} per cycle",
+            op, latency, throughput
+        );
+    }
+
+    for (name, series) in [("Load", load_series), ("Store", store_series)] {
+        LineChart {
+            title: format!("{} Bytes Per Cycle", name),
+            x_label: "Unroll".to_string(),
+            y_label: "Bytes/cycle".to_string(),
+            x_format: AxisFormat::Number,
+            log_x: true,
+            series,
+        }
+        .save(&svg_file_name(
+            &csv_file.replace(".csv", &format!("_{}.csv", name.to_lowercase())),
+        ))?;
+    }
+    println!("\nResults written to {}", csv_file);
+
+    Ok(())
+}
@@ -0,0 +1,196 @@
+use std::error::Error;
+use std::ptr;
+
+use libc::{mmap, munmap, size_t, MAP_ANON, MAP_FAILED, MAP_PRIVATE, PROT_READ, PROT_WRITE};
+
+use perf_course::fault_tracer::{print_trace_summary, save_trace, FaultTracer, TouchTrace};
+use perf_course::repetition_tester::repetition_tester::RepetitionTester;
+use perf_course::report::record_wave;
+use perf_course::residency::ResidencySnapshot;
+
+use crate::Params;
+
+struct TestParams {
+    total_size: size_t,
+    seconds_to_try: u64,
+    buffer: *mut u8,
+    // write every `page_stride`th page, 1 writes them all
+    page_stride: usize,
+}
+
+fn write_all_bytes(tester: &mut RepetitionTester, params: &TestParams) {
+    let page_size = unsafe { libc::sysconf(libc::_SC_PAGESIZE) } as usize;
+    let buffer = unsafe { std::slice::from_raw_parts_mut(params.buffer, params.total_size) };
+    let written_bytes: usize = buffer
+        .chunks(page_size)
+        .step_by(params.page_stride)
+        .map(|page| page.len())
+        .sum();
+
+    tester.start_test_wave(params.seconds_to_try, written_bytes as u64);
+    while tester.is_testing() {
+        tester.begin_time();
+        for (page, bytes) in buffer
+            .chunks_mut(page_size)
+            .enumerate()
+            .step_by(params.page_stride)
+        {
+            for (i, byte) in bytes.iter_mut().enumerate() {
+                *byte = (page * page_size + i) as u8;
+            }
+        }
+        tester.end_time();
+
+        tester.count_bytes(written_bytes as u64);
+    }
+}
+
+// The same writes once more over a fresh mapping registered with userfaultfd,
+// page by page so every fault can be put on the page being written.
+fn trace_write_all_bytes(total_size: usize, trace_file: &str) -> Result<(), Box<dyn Error>> {
+    let page_size = unsafe { libc::sysconf(libc::_SC_PAGESIZE) } as usize;
+    let addr = unsafe {
+        mmap(
+            ptr::null_mut(), // Address at which to start the mapping (nullptr lets the OS choose)
+            total_size,      // Number of bytes to map
+            PROT_READ | PROT_WRITE, // Enable read and write access
+            MAP_PRIVATE | MAP_ANON,
+            -1, // File descriptor not used with MAP_ANON
+            0,  // Offset not used with MAP_ANON
+        )
+    };
+    if addr == MAP_FAILED {
+        return Err("mmap failed".into());
+    }
+
+    let tracer = match FaultTracer::new(addr as *mut u8, total_size) {
+        Ok(tracer) => tracer,
+        Err(error) => {
+            unsafe {
+                munmap(addr, total_size);
+            }
+            return Err(format!("Can't trace faults: {}", error).into());
+        }
+    };
+
+    let buffer = unsafe { std::slice::from_raw_parts_mut(addr as *mut u8, total_size) };
+    let mut traces = Vec::with_capacity(total_size.div_ceil(page_size));
+    for (page, bytes) in buffer.chunks_mut(page_size).enumerate() {
+        for (i, byte) in bytes.iter_mut().enumerate() {
+            *byte = (page * page_size + i) as u8;
+        }
+        traces.push(TouchTrace {
+            page,
+            faults: tracer.take_faults(),
+        });
+    }
+
+    drop(tracer);
+    unsafe {
+        munmap(addr, total_size);
+    }
+
+    save_trace(trace_file, &traces)?;
+    print_trace_summary(&traces);
+    println!("Trace written to {}", trace_file);
+
+    Ok(())
+}
+
+// listing_0110_pagefault_overhead_test [--trace] [--residency] [--partial[=n]] [trace_csv_file]
+//
+// --trace first writes a traced copy of the buffer (Linux only), listing which
+// pages every page written faulted in.
+// --residency maps which pages of the buffer are resident before and after the
+// test, and what changed in between.
+// --partial writes only every nth page (every other one without an n), the
+// residency maps then show whether the kernel filled in the pages between.
+pub fn run(params: &Params) -> Result<(), Box<dyn Error>> {
+    let flag = |name: &str| params.args.iter().any(|arg| arg == name);
+    let trace = flag("--trace");
+    let residency = flag("--residency");
+    let page_stride = match params
+        .args
+        .iter()
+        .find_map(|arg| arg.strip_prefix("--partial"))
+    {
+        None => 1,
+        Some("") => 2,
+        Some(value) => value
+            .strip_prefix('=')
+            .and_then(|n| n.parse::<usize>().ok())
+            .filter(|n| *n > 0)
+            .ok_or("--partial takes a page stride, --partial=4")?,
+    };
+    let args: Vec<&String> = params
+        .args
+        .iter()
+        .filter(|arg| !arg.starts_with("--"))
+        .collect();
+    let trace_file = args
+        .get(1)
+        .map(|s| s.to_string())
+        .unwrap_or("pagefault_trace.csv".to_string());
+
+    let total_size = params.size_or(1024 * 1024 * 1024) as usize;
+    if trace {
+        trace_write_all_bytes(total_size, &trace_file)?;
+    }
+
+    let addr = unsafe {
+        mmap(
+            ptr::null_mut(), // Address at which to start the mapping (nullptr lets the OS choose)
+            total_size,      // Number of bytes to map
+            PROT_READ | PROT_WRITE, // Enable read and write access
+            MAP_PRIVATE | MAP_ANON,
+            -1, // File descriptor not used with MAP_ANON
+            0,  // Offset not used with MAP_ANON
+        )
+    };
+    if addr == MAP_FAILED {
+        return Err("mmap failed".into());
+    }
+
+    let test_params = TestParams {
+        total_size,
+        seconds_to_try: params.seconds_or(2),
+        buffer: addr as *mut u8,
+        page_stride,
+    };
+
+    let before = if residency {
+        Some(ResidencySnapshot::take(addr as *mut u8, total_size)?)
+    } else {
+        None
+    };
+
+    let test_name = if page_stride == 1 {
+        "Write All Bytes Test".to_string()
+    } else {
+        format!("Write 1 In {} Pages Test", page_stride)
+    };
+    println!("\n----- {} -----", test_name);
+    let mut tester = RepetitionTester::new();
+    write_all_bytes(&mut tester, &test_params);
+    record_wave(&test_name, &tester);
+
+    if let Some(before) = before {
+        let after = ResidencySnapshot::take(addr as *mut u8, total_size)?;
+        println!("\n===== Resident Before =====");
+        println!("{}", before.summary());
+        print!("{}", before.render_bitmap());
+        println!("\n===== Resident After =====");
+        println!("{}", after.summary());
+        print!("{}", after.render_bitmap());
+        let diff = before.diff(&after);
+        println!("\n===== Residency Changes =====");
+        println!("{}", diff.summary());
+        print!("{}", diff.render_bitmap());
+    }
+
+    unsafe {
+        munmap(addr, total_size);
+    }
+
+    Ok(())
+}
@@ -0,0 +1,182 @@
+use libc::{mmap, munmap, MAP_ANON, MAP_FAILED, MAP_PRIVATE, PROT_READ, PROT_WRITE};
+use std::error::Error;
+use std::fs::File;
+use std::io::Write;
+use std::ptr;
+
+use perf_course::fault_pattern::{
+    analyze_faults, print_fault_patterns, FaultRow, TouchOrder, FAULT_CSV_HEADER,
+};
+use perf_course::fault_tracer::{print_trace_summary, save_trace, FaultTracer, TouchTrace};
+use perf_course::perf_metrics::{get_page_faults, VirtualAddress};
+
+use crate::Params;
+
+// listing_0119_overfaulting_pages [order,...] [csv_file] [--trace]
+//
+// order is forward, backward, random[:seed] or strided[:pages], every order
+// walks its own mapping and writes csv_file with the order appended to the
+// name: fault_counter_forward.csv, fault_counter_backward.csv...
+//
+// --trace resolves the faults through userfaultfd (Linux only) and also writes
+// which pages every touch faulted to fault_counter_forward_trace.csv...
+pub fn run(params: &Params) -> Result<(), Box<dyn Error>> {
+    let trace = params.args.iter().any(|arg| arg == "--trace");
+    let args: Vec<&String> = params.args.iter().filter(|arg| *arg != "--trace").collect();
+    let orders: Vec<TouchOrder> = args
+        .get(1)
+        .map(|s| {
+            s.split(',')
+                .map(|order| TouchOrder::parse(order).expect("Invalid touch order"))
+                .collect()
+        })
+        .unwrap_or(vec![TouchOrder::Forward]);
+    let csv_file = args
+        .get(2)
+        .map(|s| s.to_string())
+        .unwrap_or("fault_counter.csv".to_string());
+
+    // the analyzer counts in whatever pages the OS uses
+    let page_size = unsafe { libc::sysconf(libc::_SC_PAGESIZE) } as usize;
+    let page_count = params.size_or(16384 * page_size as u64) as usize / page_size;
+
+    let mut patterns = Vec::new();
+    for order in orders {
+        println!("\n----- {} -----", order.name());
+        let (rows, traces) = touch_pages(order, page_size, page_count, trace);
+        if rows.is_empty() {
+            continue;
+        }
+
+        let order_file =
+            csv_file.replace(".csv", &format!("_{}.csv", order.name().replace(' ', "_")));
+        if let Some(traces) = traces {
+            let trace_file = order_file.replace(".csv", "_trace.csv");
+            save_trace(&trace_file, &traces)?;
+            print_trace_summary(&traces);
+            println!("Trace written to {}", trace_file);
+        }
+        let mut file = File::create(&order_file)?;
+        writeln!(file, "{}", FAULT_CSV_HEADER)?;
+        for row in &rows {
+            writeln!(
+                file,
+                "{}, {}, {}, {}",
+                page_count,
+                row.touch_count,
+                row.fault_count,
+                row.fault_count - row.touch_count as i64
+            )?;
+        }
+        println!("Results written to {}", order_file);
+
+        patterns.push((order.name(), analyze_faults(&rows)));
+    }
+
+    print_fault_patterns(&patterns);
+
+    Ok(())
+}
+
+// Writes one byte to every page of a fresh mapping in `order`, the fault count
+// after each write is one row. Traced, the faults of every write too.
+fn touch_pages(
+    order: TouchOrder,
+    page_size: usize,
+    page_count: usize,
+    trace: bool,
+) -> (Vec<FaultRow>, Option<Vec<TouchTrace>>) {
+    let total_size = page_size * page_count;
+    let pid = std::process::id() as i32;
+    let pages = order.pages(page_count);
+
+    let addr = unsafe {
+        mmap(
+            ptr::null_mut(), // Address at which to start the mapping (nullptr lets the OS choose)
+            total_size,      // Number of bytes to map
+            PROT_READ | PROT_WRITE, // Enable read and write access
+            MAP_PRIVATE | MAP_ANON,
+            -1, // File descriptor not used with MAP_ANON
+            0,  // Offset not used with MAP_ANON
+        )
+    };
+
+    if addr == MAP_FAILED {
+        eprintln!("mmap failed");
+        return (Vec::new(), None);
+    }
+
+    let tracer = if trace {
+        match FaultTracer::new(addr as *mut u8, total_size) {
+            Ok(tracer) => Some(tracer),
+            Err(error) => {
+                eprintln!("Can't trace faults: {}", error);
+                None
+            }
+        }
+    } else {
+        None
+    };
+    let mut traces = Vec::with_capacity(if tracer.is_some() { page_count } else { 0 });
+
+    let va = VirtualAddress::from_pointer(addr as usize);
+    va.print();
+
+    let mut rows = Vec::with_capacity(page_count);
+    let mut prior_over_fault_count: i32 = 0;
+    let mut prior_page_index: usize = 0;
+
+    let start_faults_count = get_page_faults(pid);
+    unsafe {
+        let byte_slice = std::slice::from_raw_parts_mut(addr as *mut u8, total_size);
+
+        for (touch_index, &page_index) in pages.iter().enumerate() {
+            let write_index = page_size * page_index;
+            byte_slice[write_index] = page_index as u8;
+            let end_faults_count = get_page_faults(pid);
+            // println!("Writing to page {}", write_index);
+
+            if let Some(tracer) = &tracer {
+                traces.push(TouchTrace {
+                    page: page_index,
+                    faults: tracer.take_faults(),
+                });
+            }
+
+            let over_fault_count = end_faults_count - start_faults_count;
+            rows.push(FaultRow {
+                touch_count: touch_index as u64 + 1,
+                fault_count: over_fault_count as i64,
+            });
+            if over_fault_count > prior_over_fault_count {
+                println!(
+                    "Page {}: {} extra faults ({} page size since increase)",
+                    page_index,
+                    over_fault_count,
+                    page_index.abs_diff(prior_page_index)
+                );
+
+                if touch_index > 0 {
+                    let vaddr =
+                        VirtualAddress::from_pointer(addr as usize + page_size * prior_page_index);
+                    println!("    Previous Pointer: {}", vaddr.format());
+                }
+
+                let vaddr = VirtualAddress::from_pointer(addr as usize + page_size * page_index);
+                println!("    This Pointer: {}", vaddr.format());
+
+                prior_over_fault_count = over_fault_count;
+                prior_page_index = page_index;
+            }
+        }
+    }
+
+    // unregistered before the pages go away
+    let traced = tracer.is_some();
+    drop(tracer);
+    unsafe {
+        munmap(addr, total_size);
+    }
+
+    (rows, if traced { Some(traces) } else { None })
+}
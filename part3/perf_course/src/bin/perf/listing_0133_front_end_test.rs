@@ -1,13 +1,14 @@
-extern crate perf_course;
-
 use std::cell::RefCell;
 use std::error::Error;
 use std::ptr;
 use std::rc::Rc;
 
-use libc::{MAP_ANON, MAP_PRIVATE, mmap, PROT_READ, PROT_WRITE, size_t};
+use libc::{mmap, size_t, MAP_ANON, MAP_PRIVATE, PROT_READ, PROT_WRITE};
 
 use perf_course::repetition_tester::repetition_tester::RepetitionTester;
+use perf_course::report::record_wave;
+
+use crate::Params;
 
 extern "C" {
     fn GarbageLoopExample(count: u64, data: *mut u8);
@@ -36,12 +37,9 @@ fn write_all_bytes(tester: &mut RepetitionTester, params: &TestParams) {
     // let buffer = params.buffer;
     let buffer = unsafe { std::slice::from_raw_parts_mut(params.buffer, total_size) };
     while tester.is_testing() {
-        let mut i = 0;
         tester.begin_time();
-        for i in 0..total_size {
-            unsafe {
-                buffer[i] = i as u8;
-            }
+        for (i, byte) in buffer.iter_mut().enumerate() {
+            *byte = i as u8;
         }
         // }
         tester.end_time();
@@ -56,7 +54,6 @@ fn garbage_loop_example(tester: &mut RepetitionTester, params: &TestParams) {
     // let buffer = params.buffer;
     let buffer = unsafe { std::slice::from_raw_parts_mut(params.buffer, total_size) };
     while tester.is_testing() {
-        let mut i = 0;
         tester.begin_time();
         unsafe {
             GarbageLoopExample(total_size as u64, buffer.as_mut_ptr());
@@ -73,7 +70,6 @@ fn write_full_loop_example(tester: &mut RepetitionTester, params: &TestParams) {
     // let buffer = params.buffer;
     let buffer = unsafe { std::slice::from_raw_parts_mut(params.buffer, total_size) };
     while tester.is_testing() {
-        let mut i = 0;
         tester.begin_time();
         unsafe {
             FullLoopExample(total_size as u64, buffer.as_mut_ptr());
@@ -87,10 +83,7 @@ fn write_full_loop_example(tester: &mut RepetitionTester, params: &TestParams) {
 fn nop_loop_example(tester: &mut RepetitionTester, params: &TestParams) {
     tester.start_test_wave(params.seconds_to_try, params.expected_bytes);
     let total_size = params.expected_bytes as size_t;
-    // let buffer = params.buffer;
-    let buffer = unsafe { std::slice::from_raw_parts_mut(params.buffer, total_size) };
     while tester.is_testing() {
-        let mut i = 0;
         tester.begin_time();
         unsafe {
             NopLoopExample(total_size as u64);
@@ -104,10 +97,7 @@ fn nop_loop_example(tester: &mut RepetitionTester, params: &TestParams) {
 fn just_loop_example(tester: &mut RepetitionTester, params: &TestParams) {
     tester.start_test_wave(params.seconds_to_try, params.expected_bytes);
     let total_size = params.expected_bytes as size_t;
-    // let buffer = params.buffer;
-    let buffer = unsafe { std::slice::from_raw_parts_mut(params.buffer, total_size) };
     while tester.is_testing() {
-        let mut i = 0;
         tester.begin_time();
         unsafe {
             JustLoopExample(total_size as u64);
@@ -121,10 +111,7 @@ fn just_loop_example(tester: &mut RepetitionTester, params: &TestParams) {
 fn dec_loop_example(tester: &mut RepetitionTester, params: &TestParams) {
     tester.start_test_wave(params.seconds_to_try, params.expected_bytes);
     let total_size = params.expected_bytes as size_t;
-    // let buffer = params.buffer;
-    let buffer = unsafe { std::slice::from_raw_parts_mut(params.buffer, total_size) };
     while tester.is_testing() {
-        let mut i = 0;
         tester.begin_time();
         unsafe {
             DecLoopExample(total_size as u64);
@@ -135,21 +122,20 @@ fn dec_loop_example(tester: &mut RepetitionTester, params: &TestParams) {
     }
 }
 
-
-fn main() -> Result<(), Box<dyn Error>> {
-    let total_size = 1024 * 1024 * 1024;
+pub fn run(params: &Params) -> Result<(), Box<dyn Error>> {
+    let total_size = params.size_or(1024 * 1024 * 1024) as usize;
     let addr = unsafe {
         mmap(
-            ptr::null_mut(),   // Address at which to start the mapping (nullptr lets the OS choose)
-            total_size,        // Number of bytes to map
+            ptr::null_mut(), // Address at which to start the mapping (nullptr lets the OS choose)
+            total_size,      // Number of bytes to map
             PROT_READ | PROT_WRITE, // Enable read and write access
             MAP_PRIVATE | MAP_ANON,
-            -1,                // File descriptor not used with MAP_ANON
-            0,                 // Offset not used with MAP_ANON
+            -1, // File descriptor not used with MAP_ANON
+            0,  // Offset not used with MAP_ANON
         )
     };
 
-    let mut functions = vec![
+    let functions = vec![
         TesterFunction {
             name: "Write All Bytes Test",
             function: write_all_bytes,
@@ -184,17 +170,15 @@ fn main() -> Result<(), Box<dyn Error>> {
 
     let params = TestParams {
         expected_bytes: total_size as u64,
-        seconds_to_try: 2,
+        seconds_to_try: params.seconds_or(2),
         buffer: addr as *mut u8,
     };
 
-    loop {
-        for ft in &functions {
-            println!("\n----- {} -----", ft.name);
-            (ft.function)(
-                &mut ft.tester.borrow_mut(),
-                &params,
-            );
-        }
+    for ft in &functions {
+        println!("\n----- {} -----", ft.name);
+        (ft.function)(&mut ft.tester.borrow_mut(), &params);
+        record_wave(ft.name, &ft.tester.borrow());
     }
-}
\ No newline at end of file
+
+    Ok(())
+}
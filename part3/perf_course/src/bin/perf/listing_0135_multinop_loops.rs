@@ -1,43 +1,46 @@
-extern crate perf_course;
-
+use libc::{mmap, size_t, MAP_ANON, MAP_PRIVATE, PROT_READ, PROT_WRITE};
 use std::cell::RefCell;
 use std::error::Error;
 use std::ptr;
 use std::rc::Rc;
-use libc::{MAP_ANON, MAP_PRIVATE, mmap, PROT_READ, PROT_WRITE, size_t};
 
 use perf_course::repetition_tester::repetition_tester::RepetitionTester;
+use perf_course::report::record_wave;
+
+use crate::Params;
 
 extern "C" {
-    fn NOP1AllBytes(count:u64, data: *mut u8);
-    fn NOP3AllBytes(count:u64, data: *mut u8);
-    fn NOP9AllBytes(count:u64, data: *mut u8);
+    fn NOP1AllBytes(count: u64, data: *mut u8);
+    fn NOP3AllBytes(count: u64, data: *mut u8);
+    fn NOP9AllBytes(count: u64, data: *mut u8);
 }
 
+// a test name and the closure running its wave
+type NamedTest = (&'static str, Box<dyn FnMut()>);
 
-fn main() -> Result<(), Box<dyn Error>> {
-    let total_size = 1024 * 1024 * 1024;
+pub fn run(params: &Params) -> Result<(), Box<dyn Error>> {
+    let total_size = params.size_or(1024 * 1024 * 1024) as usize;
     let addr = unsafe {
         mmap(
-            ptr::null_mut(),   // Address at which to start the mapping (nullptr lets the OS choose)
-            total_size,        // Number of bytes to map
+            ptr::null_mut(), // Address at which to start the mapping (nullptr lets the OS choose)
+            total_size,      // Number of bytes to map
             PROT_READ | PROT_WRITE, // Enable read and write access
             MAP_PRIVATE | MAP_ANON,
-            -1,                // File descriptor not used with MAP_ANON
-            0,                 // Offset not used with MAP_ANON
+            -1, // File descriptor not used with MAP_ANON
+            0,  // Offset not used with MAP_ANON
         )
     };
 
-    let testers = vec![
+    let testers = [
         Rc::new(RefCell::new(RepetitionTester::new())),
         Rc::new(RefCell::new(RepetitionTester::new())),
         Rc::new(RefCell::new(RepetitionTester::new())),
     ];
 
-    let seconds_to_try = 2;
+    let seconds_to_try = params.seconds_or(2);
     let expected_bytes = total_size as u64;
 
-    let mut test_functions: Vec<(&str, Box<dyn FnMut()>)> = vec![
+    let mut test_functions: Vec<NamedTest> = vec![
         (
             "NOP1AllBytes",
             Box::new({
@@ -46,12 +49,12 @@ fn main() -> Result<(), Box<dyn Error>> {
                     let mut tester = tester.borrow_mut();
                     tester.start_test_wave(seconds_to_try, expected_bytes);
                     let total_size = expected_bytes as size_t;
-                    let buffer = unsafe { std::slice::from_raw_parts_mut(addr, total_size) };
+                    let buffer =
+                        unsafe { std::slice::from_raw_parts_mut(addr as *mut u8, total_size) };
                     while tester.is_testing() {
-                        let mut i = 0;
                         tester.begin_time();
                         unsafe {
-                            NOP1AllBytes(total_size as u64, buffer.as_mut_ptr() as *mut u8);
+                            NOP1AllBytes(total_size as u64, buffer.as_mut_ptr());
                         }
                         tester.end_time();
 
@@ -68,12 +71,12 @@ fn main() -> Result<(), Box<dyn Error>> {
                     let mut tester = tester.borrow_mut();
                     tester.start_test_wave(seconds_to_try, expected_bytes);
                     let total_size = expected_bytes as size_t;
-                    let buffer = unsafe { std::slice::from_raw_parts_mut(addr, total_size) };
+                    let buffer =
+                        unsafe { std::slice::from_raw_parts_mut(addr as *mut u8, total_size) };
                     while tester.is_testing() {
-                        let mut i = 0;
                         tester.begin_time();
                         unsafe {
-                            NOP3AllBytes(total_size as u64, buffer.as_mut_ptr() as *mut u8);
+                            NOP3AllBytes(total_size as u64, buffer.as_mut_ptr());
                         }
                         tester.end_time();
 
@@ -90,12 +93,12 @@ fn main() -> Result<(), Box<dyn Error>> {
                     let mut tester = tester.borrow_mut();
                     tester.start_test_wave(seconds_to_try, expected_bytes);
                     let total_size = expected_bytes as size_t;
-                    let buffer = unsafe { std::slice::from_raw_parts_mut(addr, total_size) };
+                    let buffer =
+                        unsafe { std::slice::from_raw_parts_mut(addr as *mut u8, total_size) };
                     while tester.is_testing() {
-                        let mut i = 0;
                         tester.begin_time();
                         unsafe {
-                            NOP9AllBytes(total_size as u64, buffer.as_mut_ptr() as *mut u8);
+                            NOP9AllBytes(total_size as u64, buffer.as_mut_ptr());
                         }
                         tester.end_time();
 
@@ -106,10 +109,11 @@ fn main() -> Result<(), Box<dyn Error>> {
         ),
     ];
 
-    loop {
-        for (test_name, test_function) in test_functions.iter_mut() {
-            println!("\n----- {} -----", test_name);
-            test_function();
-        }
+    for (index, (test_name, test_function)) in test_functions.iter_mut().enumerate() {
+        println!("\n----- {} -----", test_name);
+        test_function();
+        record_wave(test_name, &testers[index].borrow());
     }
+
+    Ok(())
 }
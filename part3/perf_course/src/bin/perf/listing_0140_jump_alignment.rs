@@ -1,5 +1,3 @@
-extern crate perf_course;
-
 use std::cell::RefCell;
 use std::error::Error;
 use std::ptr;
@@ -8,6 +6,9 @@ use std::rc::Rc;
 use libc::{mmap, size_t, MAP_ANON, MAP_PRIVATE, PROT_READ, PROT_WRITE};
 
 use perf_course::repetition_tester::repetition_tester::RepetitionTester;
+use perf_course::report::record_wave;
+
+use crate::Params;
 
 extern "C" {
     fn NOPAligned64(count: u64, data: *mut u8);
@@ -28,8 +29,8 @@ struct TesterFunction {
     tester: Rc<RefCell<RepetitionTester>>,
 }
 
-fn main() -> Result<(), Box<dyn Error>> {
-    let total_size = 1024 * 1024 * 1024;
+pub fn run(params: &Params) -> Result<(), Box<dyn Error>> {
+    let total_size = params.size_or(1024 * 1024 * 1024) as usize;
     let addr = unsafe {
         mmap(
             ptr::null_mut(), // Address at which to start the mapping (nullptr lets the OS choose)
@@ -41,7 +42,7 @@ fn main() -> Result<(), Box<dyn Error>> {
         )
     };
 
-    let mut functions = vec![
+    let functions = vec![
         TesterFunction {
             name: "Write All Bytes Test",
             function: NOPAligned64,
@@ -61,27 +62,27 @@ fn main() -> Result<(), Box<dyn Error>> {
 
     let params = TestParams {
         expected_bytes: total_size as u64,
-        seconds_to_try: 2,
+        seconds_to_try: params.seconds_or(2),
         buffer: addr as *mut u8,
     };
 
-    loop {
-        for ft in &functions {
-            println!("\n----- {} -----", ft.name);
-            let mut tester = ft.tester.borrow_mut();
-            tester.start_test_wave(params.seconds_to_try, params.expected_bytes);
-            let total_size = params.expected_bytes as size_t;
-            let buffer = unsafe { std::slice::from_raw_parts_mut(addr, total_size) };
-            while tester.is_testing() {
-                let mut i = 0;
-                tester.begin_time();
-                unsafe {
-                    (ft.function)(total_size as u64, buffer.as_mut_ptr() as *mut u8);
-                }
-                tester.end_time();
-
-                tester.count_bytes(total_size as u64);
+    for ft in &functions {
+        println!("\n----- {} -----", ft.name);
+        let mut tester = ft.tester.borrow_mut();
+        tester.start_test_wave(params.seconds_to_try, params.expected_bytes);
+        let total_size = params.expected_bytes as size_t;
+        let buffer = unsafe { std::slice::from_raw_parts_mut(params.buffer, total_size) };
+        while tester.is_testing() {
+            tester.begin_time();
+            unsafe {
+                (ft.function)(total_size as u64, buffer.as_mut_ptr());
             }
+            tester.end_time();
+
+            tester.count_bytes(total_size as u64);
         }
+        record_wave(ft.name, &tester);
     }
+
+    Ok(())
 }
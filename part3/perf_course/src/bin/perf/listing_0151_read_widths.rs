@@ -1,5 +1,3 @@
-extern crate perf_course;
-
 use std::cell::RefCell;
 use std::error::Error;
 use std::ptr;
@@ -8,7 +6,12 @@ use std::rc::Rc;
 use libc::{mmap, size_t, MAP_ANON, MAP_PRIVATE, PROT_READ, PROT_WRITE};
 
 use perf_course::repetition_tester::repetition_tester::RepetitionTester;
+use perf_course::report::record_wave;
+
+use crate::Params;
 
+// some kernels are only used by the commented out tests
+#[allow(dead_code)]
 extern "C" {
     fn Read_4x3(count: u64, data: *mut u8);
     fn Read_8x3(count: u64, data: *mut u8);
@@ -19,6 +22,8 @@ extern "C" {
     fn Read_32x3(count: u64, data: *mut u8);
     fn Read_64x3(count: u64, data: *mut u8);
     fn Read_128x3(count: u64, data: *mut u8);
+    fn Read_256x3(count: u64, data: *mut u8);
+    fn Read_512x3(count: u64, data: *mut u8);
 }
 
 struct TestParams {
@@ -33,8 +38,8 @@ struct TesterFunction {
     tester: Rc<RefCell<RepetitionTester>>,
 }
 
-fn main() -> Result<(), Box<dyn Error>> {
-    let total_size = 1024 * 1024 * 1024;
+pub fn run(params: &Params) -> Result<(), Box<dyn Error>> {
+    let total_size = params.size_or(1024 * 1024 * 1024) as usize;
     let addr = unsafe {
         mmap(
             ptr::null_mut(), // Address at which to start the mapping (nullptr lets the OS choose)
@@ -97,28 +102,51 @@ fn main() -> Result<(), Box<dyn Error>> {
         // },
     ];
 
+    // the 256 and 512 bit kernels need AVX and AVX-512 on x86-64
+    #[cfg(target_arch = "x86_64")]
+    let (has_256, has_512) = (
+        std::is_x86_feature_detected!("avx"),
+        std::is_x86_feature_detected!("avx512f"),
+    );
+    #[cfg(not(target_arch = "x86_64"))]
+    let (has_256, has_512) = (true, true);
+    if has_256 {
+        functions.push(TesterFunction {
+            name: "Read_256x3",
+            function: Read_256x3,
+            tester: Rc::new(RefCell::new(RepetitionTester::new())),
+        });
+    }
+    if has_512 {
+        functions.push(TesterFunction {
+            name: "Read_512x3",
+            function: Read_512x3,
+            tester: Rc::new(RefCell::new(RepetitionTester::new())),
+        });
+    }
+
     let params = TestParams {
         expected_bytes: total_size as u64,
-        seconds_to_try: 2,
+        seconds_to_try: params.seconds_or(2),
         buffer: addr as *mut u8,
     };
 
-    loop {
-        for ft in &functions {
-            println!("\n----- {} -----", ft.name);
-            let mut tester = ft.tester.borrow_mut();
-            tester.start_test_wave(params.seconds_to_try, params.expected_bytes);
-            let total_size = params.expected_bytes as size_t;
-            let buffer = unsafe { std::slice::from_raw_parts_mut(addr, total_size) };
-            while tester.is_testing() {
-                let mut i = 0;
-                tester.begin_time();
-                unsafe {
-                    (ft.function)(total_size as u64, buffer.as_mut_ptr() as *mut u8);
-                }
-                tester.end_time();
-                tester.count_bytes(total_size as u64);
+    for ft in &functions {
+        println!("\n----- {} -----", ft.name);
+        let mut tester = ft.tester.borrow_mut();
+        tester.start_test_wave(params.seconds_to_try, params.expected_bytes);
+        let total_size = params.expected_bytes as size_t;
+        let buffer = unsafe { std::slice::from_raw_parts_mut(params.buffer, total_size) };
+        while tester.is_testing() {
+            tester.begin_time();
+            unsafe {
+                (ft.function)(total_size as u64, buffer.as_mut_ptr());
             }
+            tester.end_time();
+            tester.count_bytes(total_size as u64);
         }
+        record_wave(ft.name, &tester);
     }
+
+    Ok(())
 }
@@ -0,0 +1,52 @@
+use std::error::Error;
+use std::ptr;
+
+use libc::{mmap, MAP_ANON, MAP_PRIVATE, PROT_READ, PROT_WRITE};
+
+use perf_course::sweep::{ParameterGenerator, Sweep, SweepMetric};
+
+use crate::Params;
+
+extern "C" {
+    fn ReadBufferTest(count: u64, data: *mut u8, mask: u64);
+}
+
+pub fn run(params: &Params) -> Result<(), Box<dyn Error>> {
+    let total_size = params.size_or(1024 * 1024 * 1024) as usize;
+    let addr = unsafe {
+        mmap(
+            ptr::null_mut(), // Address at which to start the mapping (nullptr lets the OS choose)
+            total_size,      // Number of bytes to map
+            PROT_READ | PROT_WRITE, // Enable read and write access
+            MAP_PRIVATE | MAP_ANON,
+            -1, // File descriptor not used with MAP_ANON
+            0,  // Offset not used with MAP_ANON
+        )
+    };
+
+    let buffer = unsafe { std::slice::from_raw_parts_mut(addr as *mut u8, total_size) };
+
+    // the kernel wraps its read offset with `mask`, so every region size has
+    // to be a power of two
+    let sweep = Sweep {
+        name: "Cache Size".to_string(),
+        parameter_label: "Region Size".to_string(),
+        generator: ParameterGenerator::PowerOfTwo {
+            start: 16 * 1024,
+            end: 4 * 1024 * 1024,
+        },
+        metric: SweepMetric::Bandwidth,
+        seconds_to_try: params.seconds_or(2),
+    };
+
+    let results = sweep.run(|region_size| {
+        unsafe {
+            ReadBufferTest(total_size as u64, buffer.as_mut_ptr(), region_size - 1);
+        }
+        total_size as u64
+    });
+
+    results.print_table();
+
+    Ok(())
+}
@@ -1,5 +1,3 @@
-extern crate perf_course;
-
 use std::cell::RefCell;
 use std::error::Error;
 use std::ptr;
@@ -8,7 +6,12 @@ use std::rc::Rc;
 use libc::{mmap, size_t, MAP_ANON, MAP_PRIVATE, PROT_READ, PROT_WRITE};
 
 use perf_course::repetition_tester::repetition_tester::RepetitionTester;
+use perf_course::report::record_wave;
+
+use crate::Params;
 
+// some kernels are only used by the commented out tests
+#[allow(dead_code)]
 extern "C" {
     fn ReadBufferDoubleLoopTest(outer_loop_count: u64, data: *mut u8, inner_loop_count: u64);
     fn ReadBufferDoubleLoopTest2(outer_loop_count: u64, data: *mut u8, inner_loop_count: u64);
@@ -45,15 +48,15 @@ impl TesterFunction {
         TesterFunction {
             name,
             read_size,
-            chunk_count: chunk_count as u64,
+            chunk_count,
             function,
             tester: Rc::new(RefCell::new(RepetitionTester::new())),
         }
     }
 }
 
-fn main() -> Result<(), Box<dyn Error>> {
-    let total_size = 1024 * 1024 * 1024;
+pub fn run(params: &Params) -> Result<(), Box<dyn Error>> {
+    let total_size = params.size_or(1024 * 1024 * 1024) as usize;
     let addr = unsafe {
         mmap(
             ptr::null_mut(), // Address at which to start the mapping (nullptr lets the OS choose)
@@ -88,15 +91,13 @@ fn main() -> Result<(), Box<dyn Error>> {
 
     let params = TestParams {
         expected_bytes: total_size as u64,
-        seconds_to_try: 2,
+        seconds_to_try: params.seconds_or(2),
         buffer: addr as *mut u8,
     };
 
-    let buffer = unsafe { std::slice::from_raw_parts_mut(addr as *mut u8, total_size) };
-    unsafe {
-        for i in 0..total_size {
-            buffer[i] = i as u8;
-        }
+    let buffer = unsafe { std::slice::from_raw_parts_mut(params.buffer, total_size) };
+    for (i, byte) in buffer.iter_mut().enumerate() {
+        *byte = i as u8;
     }
 
     for ft in &functions {
@@ -104,7 +105,7 @@ fn main() -> Result<(), Box<dyn Error>> {
         let mut tester = ft.tester.borrow_mut();
         tester.start_test_wave(params.seconds_to_try, params.expected_bytes);
         let total_size = params.expected_bytes as size_t;
-        let mut pointer = buffer.as_mut_ptr() as *mut u8;
+        let pointer = buffer.as_mut_ptr();
         while tester.is_testing() {
             tester.begin_time();
             unsafe {
@@ -113,6 +114,7 @@ fn main() -> Result<(), Box<dyn Error>> {
             tester.end_time();
             tester.count_bytes(total_size as u64);
         }
+        record_wave(ft.name, &tester);
     }
 
     Ok(())
@@ -0,0 +1,384 @@
+extern crate perf_course;
+
+mod branch_patterns;
+mod cache_size_sweep;
+mod code_alignment;
+mod data_alignment;
+mod detect_cache_hierarchy;
+mod false_sharing;
+mod fp_latency;
+mod infer_cache_associativity;
+mod jit_kernels;
+mod listing_0110_pagefault_overhead_test;
+mod listing_0119_overfaulting_pages;
+mod listing_0133_front_end_test;
+mod listing_0135_multinop_loops;
+mod listing_0140_jump_alignment;
+mod listing_0145_read_unroll;
+mod listing_0151_read_widths;
+mod listing_0152_cache_size;
+mod listing_0153_cache_size_double_loop;
+mod listing_0156_alignment_test;
+mod listing_0157_set_associativity;
+mod memcpy_strategies;
+mod pointer_chase_latency;
+mod prefault_strategies;
+mod prefetch_patterns;
+mod store_bandwidth;
+mod tlb_reach;
+
+use std::env;
+use std::error::Error;
+use std::fs::File;
+use std::io::{self, Write};
+use std::os::unix::io::FromRawFd;
+
+use perf_course::report::{take_waves, OutputFormat};
+
+// What every benchmark gets from the command line. The flags shared by all of
+// them are parsed here, anything after `--` is left to the benchmark.
+pub struct Params {
+    // the benchmark name first, then its own arguments, same layout as
+    // env::args
+    pub args: Vec<String>,
+    pub size: Option<u64>,
+    pub seconds: Option<u64>,
+}
+
+impl Params {
+    // buffer size in bytes, `default` when --size wasn't given
+    pub fn size_or(&self, default: u64) -> u64 {
+        self.size.unwrap_or(default)
+    }
+
+    // seconds each test wave keeps trying to beat its fastest run
+    pub fn seconds_or(&self, default: u64) -> u64 {
+        self.seconds.unwrap_or(default)
+    }
+}
+
+struct Benchmark {
+    name: &'static str,
+    description: &'static str,
+    run: fn(&Params) -> Result<(), Box<dyn Error>>,
+}
+
+const BENCHMARKS: [Benchmark; 26] = [
+    Benchmark {
+        name: "listing_0110_pagefault_overhead_test",
+        description: "write bandwidth of a buffer, optionally tracing its page faults",
+        run: listing_0110_pagefault_overhead_test::run,
+    },
+    Benchmark {
+        name: "listing_0119_overfaulting_pages",
+        description: "page faults taken while touching one byte per page",
+        run: listing_0119_overfaulting_pages::run,
+    },
+    Benchmark {
+        name: "prefault_strategies",
+        description: "allocate and touch with MAP_POPULATE, madvise, mlock and a pool",
+        run: prefault_strategies::run,
+    },
+    Benchmark {
+        name: "listing_0133_front_end_test",
+        description: "write loops against loops that only decode",
+        run: listing_0133_front_end_test::run,
+    },
+    Benchmark {
+        name: "listing_0135_multinop_loops",
+        description: "loops of 1, 3 and 9 byte nops",
+        run: listing_0135_multinop_loops::run,
+    },
+    Benchmark {
+        name: "listing_0140_jump_alignment",
+        description: "the same loop at different jump target alignments",
+        run: listing_0140_jump_alignment::run,
+    },
+    Benchmark {
+        name: "code_alignment",
+        description: "loop timing with the code copied to every offset of a window",
+        run: code_alignment::run,
+    },
+    Benchmark {
+        name: "listing_0145_read_unroll",
+        description: "1 to 4 loads per loop iteration",
+        run: listing_0145_read_unroll::run,
+    },
+    Benchmark {
+        name: "listing_0151_read_widths",
+        description: "4 to 64 byte loads",
+        run: listing_0151_read_widths::run,
+    },
+    Benchmark {
+        name: "listing_0152_cache_size",
+        description: "read bandwidth of power of two regions",
+        run: listing_0152_cache_size::run,
+    },
+    Benchmark {
+        name: "listing_0153_cache_size_double_loop",
+        description: "read bandwidth of any region size",
+        run: listing_0153_cache_size_double_loop::run,
+    },
+    Benchmark {
+        name: "listing_0156_alignment_test",
+        description: "read bandwidth at misaligned offsets",
+        run: listing_0156_alignment_test::run,
+    },
+    Benchmark {
+        name: "data_alignment",
+        description: "load bandwidth at every offset across line and page boundaries",
+        run: data_alignment::run,
+    },
+    Benchmark {
+        name: "listing_0157_set_associativity",
+        description: "reads strided by the cache set size",
+        run: listing_0157_set_associativity::run,
+    },
+    Benchmark {
+        name: "cache_size_sweep",
+        description: "read bandwidth over log spaced region sizes",
+        run: cache_size_sweep::run,
+    },
+    Benchmark {
+        name: "detect_cache_hierarchy",
+        description: "cache levels and sizes from a region sweep",
+        run: detect_cache_hierarchy::run,
+    },
+    Benchmark {
+        name: "infer_cache_associativity",
+        description: "ways per set from strided line counts",
+        run: infer_cache_associativity::run,
+    },
+    Benchmark {
+        name: "pointer_chase_latency",
+        description: "load to use latency over working set sizes",
+        run: pointer_chase_latency::run,
+    },
+    Benchmark {
+        name: "tlb_reach",
+        description: "TLB reach with small and huge pages",
+        run: tlb_reach::run,
+    },
+    Benchmark {
+        name: "branch_patterns",
+        description: "branch prediction of periodic patterns",
+        run: branch_patterns::run,
+    },
+    Benchmark {
+        name: "jit_kernels",
+        description: "generated loops of every kernel shape",
+        run: jit_kernels::run,
+    },
+    Benchmark {
+        name: "store_bandwidth",
+        description: "regular and non-temporal store bandwidth",
+        run: store_bandwidth::run,
+    },
+    Benchmark {
+        name: "memcpy_strategies",
+        description: "copy bandwidth of memcpy strategies by size and alignment",
+        run: memcpy_strategies::run,
+    },
+    Benchmark {
+        name: "prefetch_patterns",
+        description: "hardware prefetching of strided access patterns",
+        run: prefetch_patterns::run,
+    },
+    Benchmark {
+        name: "false_sharing",
+        description: "counter throughput with threads sharing cache lines",
+        run: false_sharing::run,
+    },
+    Benchmark {
+        name: "fp_latency",
+        description: "floating point latency and throughput",
+        run: fp_latency::run,
+    },
+];
+
+fn usage() -> ! {
+    eprintln!("Usage: perf list");
+    eprintln!(
+        "       perf run <benchmark>... [--size <bytes>] [--seconds <n>] [--rounds <n>] [--cpu <n>] [--format text|csv|json] [--output <file>] [-- <benchmark args>]"
+    );
+    eprintln!();
+    eprintln!("A benchmark is picked by its full name or by any part of it (0152, cache_size...).");
+    eprintln!("Sizes take a k, m or g suffix. --rounds 0 runs until interrupted.");
+    eprintln!("csv and json without --output own stdout, the benchmarks print to stderr.");
+    std::process::exit(1);
+}
+
+// 4096, 16k, 256m, 1g
+fn parse_size(value: &str) -> Option<u64> {
+    let value = value.to_lowercase();
+    let (digits, unit) = match value.chars().last()? {
+        'k' => (&value[..value.len() - 1], 1024),
+        'm' => (&value[..value.len() - 1], 1024 * 1024),
+        'g' => (&value[..value.len() - 1], 1024 * 1024 * 1024),
+        _ => (&value[..], 1),
+    };
+    digits.parse::<u64>().ok()?.checked_mul(unit)
+}
+
+// Benchmarks named exactly `pattern`, or else every one containing it.
+fn find_benchmarks(pattern: &str) -> Vec<&'static Benchmark> {
+    if let Some(benchmark) = BENCHMARKS.iter().find(|b| b.name == pattern) {
+        return vec![benchmark];
+    }
+    BENCHMARKS
+        .iter()
+        .filter(|b| b.name.contains(pattern))
+        .collect()
+}
+
+#[cfg(target_os = "linux")]
+fn pin_to_cpu(cpu: usize) -> Result<(), Box<dyn Error>> {
+    unsafe {
+        let mut set: libc::cpu_set_t = std::mem::zeroed();
+        libc::CPU_SET(cpu, &mut set);
+        if libc::sched_setaffinity(0, std::mem::size_of::<libc::cpu_set_t>(), &set) != 0 {
+            return Err(format!("Can't pin to CPU {}: {}", cpu, io::Error::last_os_error()).into());
+        }
+    }
+    Ok(())
+}
+
+// macOS only takes affinity hints per thread group, the scheduler is free to
+// ignore them
+#[cfg(not(target_os = "linux"))]
+fn pin_to_cpu(cpu: usize) -> Result<(), Box<dyn Error>> {
+    eprintln!(
+        "[perf] Pinning to CPU {} isn't supported here, running unpinned",
+        cpu
+    );
+    Ok(())
+}
+
+// Hands back the process's stdout as a File and points fd 1 at stderr, so
+// everything the benchmarks print from here on goes to stderr and the file
+// gets stdout to itself.
+fn take_stdout() -> io::Result<File> {
+    io::stdout().flush()?;
+    unsafe {
+        let fd = libc::dup(libc::STDOUT_FILENO);
+        if fd < 0 {
+            return Err(io::Error::last_os_error());
+        }
+        if libc::dup2(libc::STDERR_FILENO, libc::STDOUT_FILENO) < 0 {
+            let error = io::Error::last_os_error();
+            libc::close(fd);
+            return Err(error);
+        }
+        Ok(File::from_raw_fd(fd))
+    }
+}
+
+fn list() {
+    let width = BENCHMARKS.iter().map(|b| b.name.len()).max().unwrap_or(0);
+    for benchmark in &BENCHMARKS {
+        println!("{:width$}  {}", benchmark.name, benchmark.description);
+    }
+}
+
+fn run(args: &[String]) -> Result<(), Box<dyn Error>> {
+    let mut names = Vec::new();
+    let mut size = None;
+    let mut seconds = None;
+    let mut rounds = 1;
+    let mut cpu = None;
+    let mut format = OutputFormat::Text;
+    let mut output_file = None;
+    let mut benchmark_args = Vec::new();
+
+    let mut i = 0;
+    while i < args.len() {
+        if args[i] == "--" {
+            benchmark_args.extend_from_slice(&args[i + 1..]);
+            break;
+        }
+        if !args[i].starts_with("--") {
+            names.push(args[i].clone());
+            i += 1;
+            continue;
+        }
+
+        let value = args.get(i + 1).cloned().unwrap_or_else(|| usage());
+        match &args[i][..] {
+            "--size" => size = Some(parse_size(&value).unwrap_or_else(|| usage())),
+            "--seconds" => seconds = Some(value.parse().unwrap_or_else(|_| usage())),
+            "--rounds" => rounds = value.parse().unwrap_or_else(|_| usage()),
+            "--cpu" => cpu = Some(value.parse::<usize>().unwrap_or_else(|_| usage())),
+            "--format" => format = OutputFormat::parse(&value).unwrap_or_else(|| usage()),
+            "--output" => output_file = Some(value),
+            _ => usage(),
+        }
+        i += 2;
+    }
+    if names.is_empty() {
+        usage();
+    }
+
+    let mut benchmarks = Vec::new();
+    for name in &names {
+        let found = find_benchmarks(name);
+        if found.is_empty() {
+            eprintln!(
+                "No benchmark matches '{}', `perf list` shows them all",
+                name
+            );
+            std::process::exit(1);
+        }
+        benchmarks.extend(found);
+    }
+
+    if let Some(cpu) = cpu {
+        pin_to_cpu(cpu)?;
+    }
+
+    let mut out: Box<dyn Write> = match &output_file {
+        Some(file_name) => Box::new(File::create(file_name)?),
+        // csv and json on stdout can only be parsed with nothing else there
+        None if format != OutputFormat::Text => Box::new(take_stdout()?),
+        None => Box::new(io::stdout()),
+    };
+    format.write_header(&mut out)?;
+
+    let mut round = 1;
+    while rounds == 0 || round <= rounds {
+        for benchmark in &benchmarks {
+            println!("\n##### {} #####", benchmark.name);
+            let mut args = vec![benchmark.name.to_string()];
+            args.extend_from_slice(&benchmark_args);
+            let params = Params {
+                args,
+                size,
+                seconds,
+            };
+            (benchmark.run)(&params)?;
+
+            format.write_waves(&mut out, benchmark.name, round, &take_waves())?;
+            out.flush()?;
+        }
+        round += 1;
+    }
+
+    if let Some(file_name) = output_file {
+        println!("\nResults written to {}", file_name);
+    }
+
+    Ok(())
+}
+
+// perf list
+// perf run <benchmark>... [options] [-- <benchmark args>]
+fn main() -> Result<(), Box<dyn Error>> {
+    let args: Vec<String> = env::args().collect();
+    match args.get(1).map(|s| &s[..]) {
+        Some("list") => {
+            list();
+            Ok(())
+        }
+        Some("run") => run(&args[2..]),
+        _ => usage(),
+    }
+}
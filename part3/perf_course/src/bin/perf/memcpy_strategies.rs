@@ -0,0 +1,326 @@
+use std::error::Error;
+use std::fs::File;
+use std::hint::black_box;
+use std::io::Write;
+use std::ptr;
+
+use libc::{mmap, MAP_ANON, MAP_PRIVATE, PROT_READ, PROT_WRITE};
+
+use perf_course::svg_chart::{svg_file_name, AxisFormat, LineChart, Series};
+use perf_course::sweep::{format_size, ParameterGenerator, Sweep, SweepMetric};
+
+use crate::Params;
+
+type CopyFunction =
+    unsafe extern "C" fn(pass_count: u64, dest: *mut u8, source: *const u8, size: u64);
+
+extern "C" {
+    fn CopySIMD(pass_count: u64, dest: *mut u8, source: *const u8, size: u64);
+    fn CopyNT(pass_count: u64, dest: *mut u8, source: *const u8, size: u64);
+    #[cfg(target_arch = "x86_64")]
+    fn CopyRepMovsb(pass_count: u64, dest: *mut u8, source: *const u8, size: u64);
+}
+
+// bytes copied per timed run, split in as many passes as the size allows
+const BYTES_PER_RUN: u64 = 256 * 1024 * 1024;
+// room past the end of both buffers for the misaligned copies
+const MAX_OFFSET: u64 = 64;
+const OFFSETS: [u64; 4] = [1, 8, 16, 32];
+// one copy that stays in the caches, the largest size is added to it
+const CACHED_ALIGNMENT_SIZE: u64 = 16 * 1024;
+
+// copy_from_slice behind the same signature as the assembly kernels
+unsafe extern "C" fn copy_from_slice(pass_count: u64, dest: *mut u8, source: *const u8, size: u64) {
+    let dest = std::slice::from_raw_parts_mut(dest, size as usize);
+    let source = std::slice::from_raw_parts(source, size as usize);
+    for _ in 0..pass_count {
+        // keeps the compiler from merging the passes into one copy
+        black_box(&mut *dest).copy_from_slice(source);
+    }
+}
+
+struct CopyStrategy {
+    name: &'static str,
+    function: CopyFunction,
+}
+
+// the x86 SIMD and non-temporal kernels use AVX registers
+#[cfg(target_arch = "x86_64")]
+fn simd_supported() -> bool {
+    std::is_x86_feature_detected!("avx")
+}
+
+#[cfg(not(target_arch = "x86_64"))]
+fn simd_supported() -> bool {
+    true
+}
+
+fn copy_strategies() -> Vec<CopyStrategy> {
+    let mut strategies = vec![CopyStrategy {
+        name: "copy_from_slice",
+        function: copy_from_slice,
+    }];
+    if simd_supported() {
+        strategies.push(CopyStrategy {
+            name: "SIMD Loop",
+            function: CopySIMD,
+        });
+    }
+    #[cfg(target_arch = "x86_64")]
+    strategies.push(CopyStrategy {
+        name: "rep movsb",
+        function: CopyRepMovsb,
+    });
+    if simd_supported() {
+        strategies.push(CopyStrategy {
+            name: "Non-temporal",
+            function: CopyNT,
+        });
+    }
+    strategies
+}
+
+fn map_buffer(size: usize) -> &'static mut [u8] {
+    let addr = unsafe {
+        mmap(
+            ptr::null_mut(), // Address at which to start the mapping (nullptr lets the OS choose)
+            size,            // Number of bytes to map
+            PROT_READ | PROT_WRITE, // Enable read and write access
+            MAP_PRIVATE | MAP_ANON,
+            -1, // File descriptor not used with MAP_ANON
+            0,  // Offset not used with MAP_ANON
+        )
+    };
+    let buffer = unsafe { std::slice::from_raw_parts_mut(addr as *mut u8, size) };
+    // fault every page in up front so no sweep point pays for it
+    buffer.fill(1);
+    buffer
+}
+
+// GB/s of one strategy for every entry of `parameters`, `copy_of` turns a
+// parameter into (size, source offset, destination offset)
+fn run_strategy<F>(
+    strategy: &CopyStrategy,
+    parameter_label: &'static str,
+    parameters: Vec<u64>,
+    dest: &mut [u8],
+    source: &[u8],
+    seconds_to_try: u64,
+    copy_of: F,
+) -> Series
+where
+    F: Fn(u64) -> (u64, u64, u64),
+{
+    let sweep = Sweep {
+        name: strategy.name.to_string(),
+        parameter_label: parameter_label.to_string(),
+        generator: ParameterGenerator::List(parameters),
+        metric: SweepMetric::Bandwidth,
+        seconds_to_try,
+    };
+
+    let results = sweep.run(|parameter| {
+        let (size, source_offset, dest_offset) = copy_of(parameter);
+        let pass_count = (BYTES_PER_RUN / size).max(1);
+        unsafe {
+            (strategy.function)(
+                pass_count,
+                dest.as_mut_ptr().add(dest_offset as usize),
+                source.as_ptr().add(source_offset as usize),
+                size,
+            );
+        }
+        pass_count * size
+    });
+    Series::from(&results)
+}
+
+// memcpy_strategies [points] [csv_file], --size sets the largest size
+pub fn run(params: &Params) -> Result<(), Box<dyn Error>> {
+    let args = &params.args;
+    let max_size = params.size_or(256 * 1024 * 1024);
+    let points = args
+        .get(1)
+        .map(|s| s.parse::<usize>().expect("Invalid point count"))
+        .unwrap_or(20);
+    let csv_file = args
+        .get(2)
+        .cloned()
+        .unwrap_or("memcpy_strategies.csv".to_string());
+
+    let max_size = (max_size / 64).max(2) * 64;
+    let source = map_buffer((max_size + MAX_OFFSET) as usize);
+    let dest = map_buffer((max_size + MAX_OFFSET) as usize);
+    let strategies = copy_strategies();
+
+    // the kernels copy 64 bytes per iteration, the non-temporal one needs two
+    // iterations to line up the destination
+    let generator = ParameterGenerator::LogSpaced {
+        start: 4 * 1024,
+        end: max_size,
+        points,
+    };
+    let sizes = generator.aligned_values(64);
+
+    let mut series = Vec::new();
+    for strategy in &strategies {
+        series.push(run_strategy(
+            strategy,
+            "Copy Size",
+            sizes.clone(),
+            dest,
+            source,
+            params.seconds_or(1),
+            |size| (size, 0, 0),
+        ));
+    }
+
+    // misaligned source only, destination only, then both
+    let mut offsets = vec![(0, 0)];
+    offsets.extend(OFFSETS.iter().map(|offset| (*offset, 0)));
+    offsets.extend(OFFSETS.iter().map(|offset| (0, *offset)));
+    offsets.extend(OFFSETS.iter().map(|offset| (*offset, *offset)));
+
+    let mut alignment_sizes = vec![CACHED_ALIGNMENT_SIZE.min(max_size)];
+    if max_size > CACHED_ALIGNMENT_SIZE {
+        alignment_sizes.push(max_size);
+    }
+
+    // parameter = size index * offset count + offset index
+    let mut alignment_series = Vec::new();
+    for strategy in &strategies {
+        let parameters = (0..(alignment_sizes.len() * offsets.len()) as u64).collect();
+        alignment_series.push(run_strategy(
+            strategy,
+            "Alignment",
+            parameters,
+            dest,
+            source,
+            params.seconds_or(1),
+            |parameter| {
+                let size = alignment_sizes[parameter as usize / offsets.len()];
+                let (source_offset, dest_offset) = offsets[parameter as usize % offsets.len()];
+                (size, source_offset, dest_offset)
+            },
+        ));
+    }
+
+    let names: Vec<&str> = strategies.iter().map(|strategy| strategy.name).collect();
+
+    let mut file = File::create(&csv_file)?;
+    writeln!(file, "Copy Size, {}", names.join(", "))?;
+    println!("\n===== Copy Bandwidth (GB/s) =====");
+    println!("Copy Size, {}", names.join(", "));
+    for (index, size) in sizes.iter().enumerate() {
+        let values: Vec<f64> = series.iter().map(|s| s.points[index].1).collect();
+        let printed: Vec<String> = values.iter().map(|v| format!("{:.2}", v)).collect();
+        let written: Vec<String> = values.iter().map(|v| v.to_string()).collect();
+        println!("{}, {}", format_size(*size), printed.join(", "));
+        writeln!(file, "{}, {}", size, written.join(", "))?;
+    }
+
+    let alignment_csv_file = csv_file.replace(".csv", "_alignment.csv");
+    let mut file = File::create(&alignment_csv_file)?;
+    writeln!(
+        file,
+        "Copy Size, Source Offset, Destination Offset, {}",
+        names.join(", ")
+    )?;
+    println!("\n===== Copy Bandwidth By Alignment (GB/s) =====");
+    println!(
+        "Copy Size, Source Offset, Destination Offset, {}",
+        names.join(", ")
+    );
+    for (index, size) in alignment_sizes.iter().enumerate() {
+        for (offset_index, (source_offset, dest_offset)) in offsets.iter().enumerate() {
+            let point = index * offsets.len() + offset_index;
+            let values: Vec<f64> = alignment_series.iter().map(|s| s.points[point].1).collect();
+            let printed: Vec<String> = values.iter().map(|v| format!("{:.2}", v)).collect();
+            let written: Vec<String> = values.iter().map(|v| v.to_string()).collect();
+            println!(
+                "{}, +{}, +{}, {}",
+                format_size(*size),
+                source_offset,
+                dest_offset,
+                printed.join(", ")
+            );
+            writeln!(
+                file,
+                "{}, {}, {}, {}",
+                size,
+                source_offset,
+                dest_offset,
+                written.join(", ")
+            )?;
+        }
+    }
+
+    LineChart {
+        title: "Copy Bandwidth".to_string(),
+        x_label: "Copy Size".to_string(),
+        y_label: "GB/s".to_string(),
+        x_format: AxisFormat::Size,
+        log_x: true,
+        series: series.clone(),
+    }
+    .save(&svg_file_name(&csv_file))?;
+    println!(
+        "\nResults written to {} and {}",
+        csv_file, alignment_csv_file
+    );
+
+    // every size where the fastest strategy changes
+    println!("\n===== Crossover Points =====");
+    let mut fastest: Option<usize> = None;
+    for (index, size) in sizes.iter().enumerate() {
+        let best = (0..series.len())
+            .max_by(|a, b| {
+                series[*a].points[index]
+                    .1
+                    .total_cmp(&series[*b].points[index].1)
+            })
+            .unwrap();
+        if fastest != Some(best) {
+            match fastest {
+                Some(previous) => println!(
+                    "{}: {} overtakes {} ({:.2} vs {:.2} GB/s)",
+                    format_size(*size),
+                    names[best],
+                    names[previous],
+                    series[best].points[index].1,
+                    series[previous].points[index].1
+                ),
+                None => println!("{}: {} is fastest", format_size(*size), names[best]),
+            }
+            fastest = Some(best);
+        }
+    }
+
+    // the worst misalignment of every strategy at every alignment size
+    println!("\n===== Misalignment Cost =====");
+    for (index, size) in alignment_sizes.iter().enumerate() {
+        for (name, s) in names.iter().zip(alignment_series.iter()) {
+            let points = &s.points[index * offsets.len()..(index + 1) * offsets.len()];
+            let aligned = points[0].1;
+            let (worst, (source_offset, dest_offset)) = points
+                .iter()
+                .zip(offsets.iter())
+                .skip(1)
+                .map(|(point, offset)| (point.1, *offset))
+                .min_by(|a, b| a.0.total_cmp(&b.0))
+                .unwrap();
+            println!(
+                "{} {}: aligned {:.2} GB/s, worst {:.2} GB/s at source +{} destination +{} ({:.0}% slower)",
+                format_size(*size),
+                name,
+                aligned,
+                worst,
+                source_offset,
+                dest_offset,
+                (1.0 - worst / aligned) * 100.0
+            );
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,152 @@
+use std::error::Error;
+use std::fs::File;
+use std::io::Write;
+
+use perf_course::cache_hierarchy::find_plateaus;
+use perf_course::perf_metrics::estimate_cpu_frequency;
+use perf_course::pointer_chase::{chase, ChaseBuffer, ChaseOrder, NODE_SIZE};
+use perf_course::svg_chart::{svg_file_name, AxisFormat, LineChart, Series};
+use perf_course::sweep::{format_size, ParameterGenerator, Sweep, SweepMetric};
+
+use crate::Params;
+
+// dependent loads per timed run
+const STEPS_PER_RUN: u64 = 2 * 1024 * 1024;
+
+fn usage(program: &str) -> ! {
+    eprintln!(
+        "Usage: {} [--order random|page|sequential] [--page-size <bytes>] [--huge] [--ghz <value>] [--csv <file>]",
+        program
+    );
+    std::process::exit(1);
+}
+
+pub fn run(params: &Params) -> Result<(), Box<dyn Error>> {
+    let args = &params.args;
+
+    let mut order_name = "random".to_string();
+    let mut page_size = 4096;
+    let mut huge_pages = false;
+    let max_size = params.size_or(512 * 1024 * 1024) as usize;
+    let mut ghz: Option<f64> = None;
+    let mut csv_file = "pointer_chase_latency.csv".to_string();
+
+    let mut i = 1;
+    while i < args.len() {
+        if args[i] == "--huge" {
+            huge_pages = true;
+            i += 1;
+            continue;
+        }
+
+        let value = args.get(i + 1).cloned().unwrap_or_else(|| usage(&args[0]));
+        match &args[i][..] {
+            "--order" => order_name = value,
+            "--page-size" => page_size = value.parse().expect("Invalid page size"),
+            "--ghz" => ghz = Some(value.parse().expect("Invalid frequency")),
+            "--csv" => csv_file = value,
+            _ => usage(&args[0]),
+        }
+        i += 2;
+    }
+
+    let order = match &order_name[..] {
+        "random" => ChaseOrder::Random,
+        "page" => ChaseOrder::PageLocal { page_size },
+        "sequential" => ChaseOrder::Sequential,
+        _ => usage(&args[0]),
+    };
+
+    let frequency = match ghz {
+        Some(ghz) => ghz * 1e9,
+        None => estimate_cpu_frequency(),
+    };
+    println!("CPU frequency: {:.2} GHz", frequency / 1e9);
+    println!("Order: {}", order.name());
+
+    let mut buffer = match ChaseBuffer::new(max_size, huge_pages) {
+        Some(buffer) => buffer,
+        None => {
+            eprintln!("mmap failed");
+            return Ok(());
+        }
+    };
+
+    let generator = ParameterGenerator::LogSpaced {
+        start: 4 * 1024,
+        end: max_size as u64,
+        points: 64,
+    };
+    let sweep = Sweep {
+        name: "Pointer Chase".to_string(),
+        parameter_label: "Working Set".to_string(),
+        generator: ParameterGenerator::List(generator.aligned_values(NODE_SIZE as u64)),
+        metric: SweepMetric::Seconds,
+        seconds_to_try: params.seconds_or(1),
+    };
+
+    // the chain is rebuilt on the untimed warm-up call of every point
+    let mut built_for = 0;
+    let mut start = std::ptr::null();
+    let results = sweep.run(|working_set| {
+        if working_set != built_for {
+            start = buffer.build(working_set as usize, order, working_set);
+            built_for = working_set;
+        }
+        chase(start, STEPS_PER_RUN);
+        STEPS_PER_RUN * std::mem::size_of::<usize>() as u64
+    });
+
+    let latencies: Vec<(u64, f64)> = results
+        .points
+        .iter()
+        .map(|point| (point.parameter, point.seconds * 1e9 / STEPS_PER_RUN as f64))
+        .collect();
+
+    let mut file = File::create(&csv_file)?;
+    writeln!(file, "Working Set, ns/load, cycles/load")?;
+    println!("\n===== {} =====", results.name);
+    println!("Working Set, ns/load, cycles/load");
+    for (working_set, ns) in &latencies {
+        let cycles = ns * frequency / 1e9;
+        println!("{}, {:.2}, {:.1}", format_size(*working_set), ns, cycles);
+        writeln!(file, "{}, {}, {}", working_set, ns, cycles)?;
+    }
+
+    LineChart {
+        title: format!("Load Latency, {}", order.name()),
+        x_label: "Working Set".to_string(),
+        y_label: "ns/load".to_string(),
+        x_format: AxisFormat::Size,
+        log_x: true,
+        series: vec![Series {
+            name: order.name(),
+            points: latencies
+                .iter()
+                .map(|(size, ns)| (*size as f64, *ns))
+                .collect(),
+        }],
+    }
+    .save(&svg_file_name(&csv_file))?;
+    println!("\nResults written to {}", csv_file);
+
+    println!("\n===== Latency Per Level =====");
+    let plateaus = find_plateaus(&latencies);
+    for (index, plateau) in plateaus.iter().enumerate() {
+        let name = if index + 1 == plateaus.len() && plateaus.len() > 1 {
+            "Memory".to_string()
+        } else {
+            format!("L{}", index + 1)
+        };
+        println!(
+            "{} ({} - {}): {:.2} ns, {:.1} cycles",
+            name,
+            format_size(plateau.first_size),
+            format_size(plateau.last_size),
+            plateau.value,
+            plateau.value * frequency / 1e9
+        );
+    }
+
+    Ok(())
+}
@@ -0,0 +1,314 @@
+use std::error::Error;
+use std::fs::File;
+use std::io::{self, Write};
+use std::ptr;
+
+use libc::{
+    c_int, madvise, mlock, mmap, munmap, MADV_WILLNEED, MAP_ANON, MAP_FAILED, MAP_PRIVATE,
+    PROT_READ, PROT_WRITE,
+};
+
+use perf_course::perf_metrics::get_page_faults;
+use perf_course::repetition_tester::repetition_tester::RepetitionTester;
+use perf_course::report::record_wave;
+use perf_course::svg_chart::{svg_file_name, BarChart};
+use perf_course::sweep::format_size;
+
+use crate::Params;
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+enum Strategy {
+    // every page faults on its first write
+    Plain,
+    // the kernel faults everything in inside mmap
+    Populate,
+    // a hint, the kernel is free to do nothing with it for anonymous memory
+    WillNeed,
+    // Linux 5.14+, faults the range in writable up front
+    PopulateWrite,
+    // locking has to fault every page in and keep it there
+    Mlock,
+    // one mapping faulted in once and handed out again every time, the cost
+    // the other strategies are trying to get to
+    Pool,
+}
+
+const STRATEGIES: [Strategy; 6] = [
+    Strategy::Plain,
+    Strategy::Populate,
+    Strategy::WillNeed,
+    Strategy::PopulateWrite,
+    Strategy::Mlock,
+    Strategy::Pool,
+];
+
+#[cfg(target_os = "linux")]
+const POPULATE_FLAG: Option<c_int> = Some(libc::MAP_POPULATE);
+#[cfg(not(target_os = "linux"))]
+const POPULATE_FLAG: Option<c_int> = None;
+
+#[cfg(target_os = "linux")]
+unsafe fn populate_write(addr: *mut libc::c_void, size: usize) -> bool {
+    madvise(addr, size, libc::MADV_POPULATE_WRITE) == 0
+}
+
+#[cfg(not(target_os = "linux"))]
+unsafe fn populate_write(_addr: *mut libc::c_void, _size: usize) -> bool {
+    false
+}
+
+impl Strategy {
+    fn name(&self) -> &'static str {
+        match self {
+            Strategy::Plain => "mmap",
+            Strategy::Populate => "MAP_POPULATE",
+            Strategy::WillNeed => "MADV_WILLNEED",
+            Strategy::PopulateWrite => "MADV_POPULATE_WRITE",
+            Strategy::Mlock => "mlock",
+            Strategy::Pool => "Prefaulted Pool",
+        }
+    }
+
+    // false when this OS has no such call, as opposed to one that failed
+    fn available(&self) -> bool {
+        match self {
+            Strategy::Populate => POPULATE_FLAG.is_some(),
+            Strategy::PopulateWrite => cfg!(target_os = "linux"),
+            _ => true,
+        }
+    }
+
+    // Maps `size` bytes the way the strategy asks for them, None when this OS
+    // doesn't have it or refuses (no MADV_POPULATE_WRITE, RLIMIT_MEMLOCK...).
+    fn allocate(&self, size: usize) -> Option<*mut u8> {
+        let flags = match self {
+            Strategy::Populate => POPULATE_FLAG?,
+            _ => 0,
+        };
+        let addr = unsafe {
+            mmap(
+                ptr::null_mut(), // Address at which to start the mapping (nullptr lets the OS choose)
+                size,            // Number of bytes to map
+                PROT_READ | PROT_WRITE, // Enable read and write access
+                MAP_PRIVATE | MAP_ANON | flags,
+                -1, // File descriptor not used with MAP_ANON
+                0,  // Offset not used with MAP_ANON
+            )
+        };
+        if addr == MAP_FAILED {
+            return None;
+        }
+
+        let prepared = unsafe {
+            match self {
+                Strategy::WillNeed => madvise(addr, size, MADV_WILLNEED) == 0,
+                Strategy::PopulateWrite => populate_write(addr, size),
+                Strategy::Mlock => mlock(addr, size) == 0,
+                _ => true,
+            }
+        };
+        if !prepared {
+            unsafe {
+                munmap(addr, size);
+            }
+            return None;
+        }
+
+        Some(addr as *mut u8)
+    }
+}
+
+// writes every byte, what the program wanted the memory for in the first place
+fn touch(data: *mut u8, size: usize) {
+    let buffer = unsafe { std::slice::from_raw_parts_mut(data, size) };
+    for (i, byte) in buffer.iter_mut().enumerate() {
+        *byte = i as u8;
+    }
+}
+
+struct StrategyResult {
+    strategy: Strategy,
+    seconds: f64,
+    bandwidth: f64,
+    // all the faults of the fastest run, and the ones left for the writes
+    page_faults: u64,
+    touch_faults: u64,
+}
+
+// Times "get `size` fresh bytes and write all of them" for every way of asking
+// the OS to fault the pages in ahead of the writes. The mapping is released
+// outside the timed block, except for the pool which never gives it back.
+//
+// prefault_strategies [csv_file], --size sets the bytes allocated
+pub fn run(params: &Params) -> Result<(), Box<dyn Error>> {
+    let args = &params.args;
+    let csv_file = args
+        .get(1)
+        .cloned()
+        .unwrap_or("prefault_strategies.csv".to_string());
+    let size = params.size_or(256 * 1024 * 1024) as usize;
+    let page_size = unsafe { libc::sysconf(libc::_SC_PAGESIZE) } as usize;
+    println!(
+        "Allocating and writing {} in {} byte pages",
+        format_size(size as u64),
+        page_size
+    );
+
+    let mut results = Vec::new();
+    for strategy in STRATEGIES {
+        println!("\n----- {} -----", strategy.name());
+
+        let pool = match strategy {
+            Strategy::Pool => {
+                let data = Strategy::Plain.allocate(size);
+                if let Some(data) = data {
+                    touch(data, size);
+                }
+                data
+            }
+            _ => None,
+        };
+
+        // one untimed try, so a refused strategy is reported instead of timed
+        let first = match strategy {
+            Strategy::Pool => pool,
+            _ => strategy.allocate(size),
+        };
+        let Some(data) = first else {
+            if strategy.available() {
+                println!(
+                    "{} not supported here: {}",
+                    strategy.name(),
+                    io::Error::last_os_error()
+                );
+            } else {
+                println!("{} not available on this OS", strategy.name());
+            }
+            continue;
+        };
+        if pool.is_none() {
+            unsafe {
+                munmap(data as *mut libc::c_void, size);
+            }
+        }
+
+        let pid = std::process::id() as i32;
+        let mut touch_faults = 0;
+        let mut run_touch_faults = 0;
+        let mut min_seconds = 0.0;
+        let mut tester = RepetitionTester::new();
+        tester.start_test_wave(params.seconds_or(2), size as u64);
+        loop {
+            // is_testing folds the last run in, its touch faults are kept when
+            // it became the fastest so they match the fastest run's faults
+            let testing = tester.is_testing();
+            if tester.min_seconds() != min_seconds {
+                min_seconds = tester.min_seconds();
+                touch_faults = run_touch_faults;
+            }
+            if !testing {
+                break;
+            }
+
+            tester.begin_time();
+            let data = match pool {
+                Some(data) => data,
+                None => strategy.allocate(size).expect("Allocation failed"),
+            };
+            let faults_before_touch = get_page_faults(pid);
+            touch(data, size);
+            run_touch_faults = (get_page_faults(pid) - faults_before_touch) as u64;
+            tester.end_time();
+            tester.count_bytes(size as u64);
+
+            if pool.is_none() {
+                unsafe {
+                    munmap(data as *mut libc::c_void, size);
+                }
+            }
+        }
+        record_wave(strategy.name(), &tester);
+
+        results.push(StrategyResult {
+            strategy,
+            seconds: tester.min_seconds(),
+            bandwidth: tester.min_bandwidth(),
+            page_faults: tester.min_page_faults(),
+            touch_faults,
+        });
+
+        if let Some(data) = pool {
+            unsafe {
+                munmap(data as *mut libc::c_void, size);
+            }
+        }
+    }
+
+    let pages = size.div_ceil(page_size) as u64;
+    let mut file = File::create(&csv_file)?;
+    writeln!(
+        file,
+        "Strategy, Seconds, GB/s, Page Faults, Faults While Writing, Pages Per Fault"
+    )?;
+    println!(
+        "\n===== Allocate And Touch {} =====",
+        format_size(size as u64)
+    );
+    println!("Strategy, Seconds, GB/s, Page Faults, Faults While Writing, Pages Per Fault");
+    for result in &results {
+        // a pool takes no faults at all while timed
+        let pages_per_fault = if result.page_faults > 0 {
+            format!("{:.1}", pages as f64 / result.page_faults as f64)
+        } else {
+            "-".to_string()
+        };
+        println!(
+            "{}, {:.6}, {:.2}, {}, {}, {}",
+            result.strategy.name(),
+            result.seconds,
+            result.bandwidth,
+            result.page_faults,
+            result.touch_faults,
+            pages_per_fault
+        );
+        writeln!(
+            file,
+            "{}, {}, {}, {}, {}, {}",
+            result.strategy.name(),
+            result.seconds,
+            result.bandwidth,
+            result.page_faults,
+            result.touch_faults,
+            pages_per_fault
+        )?;
+    }
+
+    BarChart {
+        title: format!("Allocate And Touch {}", format_size(size as u64)),
+        y_label: "GB/s".to_string(),
+        bars: results
+            .iter()
+            .map(|result| (result.strategy.name().to_string(), result.bandwidth))
+            .collect(),
+    }
+    .save(&svg_file_name(&csv_file))?;
+    println!("\nResults written to {}", csv_file);
+
+    // The populate flavours move the faults into the mapping call instead of
+    // getting rid of them, the time they save is the per fault overhead of
+    // trapping in and out one page at a time.
+    if let Some(plain) = results.iter().find(|r| r.strategy == Strategy::Plain) {
+        println!("\n===== Against Plain mmap =====");
+        for result in results.iter().filter(|r| r.strategy != Strategy::Plain) {
+            println!(
+                "{}: {:.2}x the time, {} of the {} faults taken before the writes",
+                result.strategy.name(),
+                result.seconds / plain.seconds,
+                result.page_faults.saturating_sub(result.touch_faults),
+                result.page_faults
+            );
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,240 @@
+use std::error::Error;
+use std::fs::File;
+use std::io::Write;
+use std::ptr;
+
+use libc::{mmap, MAP_ANON, MAP_PRIVATE, PROT_READ, PROT_WRITE};
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::SeedableRng;
+
+use perf_course::svg_chart::{svg_file_name, AxisFormat, LineChart, Series};
+use perf_course::sweep::{format_size, ParameterGenerator, Sweep, SweepMetric};
+
+use crate::Params;
+
+extern "C" {
+    fn ReadOffsets(count: u64, data: *const u8, offsets: *const u32);
+}
+
+const LINE_SIZE: u64 = 64;
+// strides in cache lines, from sequential to well past a 16Kb page
+const STRIDE_LINES: [u64; 18] = [
+    1, 2, 3, 4, 6, 8, 12, 16, 24, 32, 48, 64, 96, 128, 192, 256, 384, 512,
+];
+// fewest loads a point is worth timing, the kernel issues them 4 at a time
+const MIN_LOADS: u64 = 4;
+// how much faster than the random walk a pattern has to be to count as
+// prefetched
+const PREFETCHED_SPEEDUP: f64 = 1.5;
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+enum AccessPattern {
+    Forward,
+    Backward,
+    // the two halves of the buffer walked in lockstep, loads alternating
+    TwoStreams,
+    // pages in order, the lines of each page in random order
+    RandomInPage,
+    // every line in random order, nothing for the prefetchers to follow
+    Random,
+}
+
+impl AccessPattern {
+    fn name(&self) -> &'static str {
+        match self {
+            AccessPattern::Forward => "Forward",
+            AccessPattern::Backward => "Backward",
+            AccessPattern::TwoStreams => "Two Streams",
+            AccessPattern::RandomInPage => "Random In Page",
+            AccessPattern::Random => "Random",
+        }
+    }
+
+    // Offsets of the loads walking `size` bytes every `stride` bytes, in the
+    // order of the pattern. The count is rounded down to what the kernel
+    // consumes.
+    fn offsets(&self, size: u64, stride: u64, page_size: u64, rng: &mut StdRng) -> Vec<u32> {
+        let forward = |size: u64| (0..size / stride).map(move |i| (i * stride) as u32);
+
+        let mut offsets: Vec<u32> = match self {
+            AccessPattern::Forward => forward(size).collect(),
+            AccessPattern::Backward => forward(size).rev().collect(),
+            AccessPattern::TwoStreams => {
+                let half = size / 2;
+                forward(half)
+                    .flat_map(|offset| [offset, offset + half as u32])
+                    .collect()
+            }
+            AccessPattern::RandomInPage => {
+                let mut offsets: Vec<u32> = forward(size).collect();
+                for page in
+                    offsets.chunk_by_mut(|a, b| *a as u64 / page_size == *b as u64 / page_size)
+                {
+                    page.shuffle(rng);
+                }
+                offsets
+            }
+            AccessPattern::Random => {
+                let mut offsets: Vec<u32> = forward(size).collect();
+                offsets.shuffle(rng);
+                offsets
+            }
+        };
+
+        offsets.truncate(offsets.len() / 4 * 4);
+        offsets
+    }
+}
+
+// prefetch_patterns [csv_file], --size sets the buffer size
+pub fn run(params: &Params) -> Result<(), Box<dyn Error>> {
+    let args = &params.args;
+    let buffer_size = params.size_or(256 * 1024 * 1024);
+    let csv_file = args
+        .get(1)
+        .cloned()
+        .unwrap_or("prefetch_patterns.csv".to_string());
+
+    // the offsets are 32 bit
+    let buffer_size = buffer_size.min(u32::MAX as u64 + 1);
+    let page_size = unsafe { libc::sysconf(libc::_SC_PAGESIZE) } as u64;
+
+    let total_size = buffer_size as usize;
+    let addr = unsafe {
+        mmap(
+            ptr::null_mut(), // Address at which to start the mapping (nullptr lets the OS choose)
+            total_size,      // Number of bytes to map
+            PROT_READ | PROT_WRITE, // Enable read and write access
+            MAP_PRIVATE | MAP_ANON,
+            -1, // File descriptor not used with MAP_ANON
+            0,  // Offset not used with MAP_ANON
+        )
+    };
+    let buffer = unsafe { std::slice::from_raw_parts_mut(addr as *mut u8, total_size) };
+    // fault every page in up front so no sweep point pays for it
+    buffer.fill(1);
+
+    // two streams walk half the buffer each, every pattern needs MIN_LOADS
+    let strides: Vec<u64> = STRIDE_LINES
+        .iter()
+        .map(|lines| lines * LINE_SIZE)
+        .filter(|stride| buffer_size / 2 / stride >= MIN_LOADS / 2)
+        .collect();
+    if strides.len() < STRIDE_LINES.len() {
+        println!(
+            "Skipping strides with fewer than {} loads in a {} buffer",
+            MIN_LOADS,
+            format_size(buffer_size)
+        );
+    }
+    if strides.is_empty() {
+        return Err(format!("--size {} is too small for any stride", buffer_size).into());
+    }
+    let patterns = [
+        AccessPattern::Forward,
+        AccessPattern::Backward,
+        AccessPattern::TwoStreams,
+        AccessPattern::RandomInPage,
+        AccessPattern::Random,
+    ];
+
+    // effective bandwidth: every load brings in a whole line
+    let mut series = Vec::new();
+    for pattern in patterns {
+        let sweep = Sweep {
+            name: pattern.name().to_string(),
+            parameter_label: "Stride".to_string(),
+            generator: ParameterGenerator::List(strides.clone()),
+            metric: SweepMetric::Bandwidth,
+            seconds_to_try: params.seconds_or(1),
+        };
+
+        // the offsets are generated on the untimed warm-up call of every point
+        let mut rng = StdRng::seed_from_u64(0);
+        let mut built_for = 0;
+        let mut offsets = Vec::new();
+        let results = sweep.run(|stride| {
+            if built_for != stride {
+                offsets = pattern.offsets(buffer_size, stride, page_size, &mut rng);
+                built_for = stride;
+            }
+            unsafe {
+                ReadOffsets(offsets.len() as u64, buffer.as_ptr(), offsets.as_ptr());
+            }
+            offsets.len() as u64 * LINE_SIZE
+        });
+        series.push(Series::from(&results));
+    }
+
+    let names: Vec<&str> = patterns.iter().map(|pattern| pattern.name()).collect();
+    let mut file = File::create(&csv_file)?;
+    writeln!(file, "Stride, {}", names.join(", "))?;
+    println!("\n===== Effective Bandwidth (GB/s) =====");
+    println!("Stride, {}", names.join(", "));
+    for (index, stride) in strides.iter().enumerate() {
+        let values: Vec<f64> = series.iter().map(|s| s.points[index].1).collect();
+        let printed: Vec<String> = values.iter().map(|v| format!("{:.2}", v)).collect();
+        let written: Vec<String> = values.iter().map(|v| v.to_string()).collect();
+        println!("{}, {}", format_size(*stride), printed.join(", "));
+        writeln!(file, "{}, {}", stride, written.join(", "))?;
+    }
+
+    LineChart {
+        title: "Effective Bandwidth By Stride".to_string(),
+        x_label: "Stride".to_string(),
+        y_label: "GB/s".to_string(),
+        x_format: AxisFormat::Size,
+        log_x: true,
+        series: series.clone(),
+    }
+    .save(&svg_file_name(&csv_file))?;
+    println!("\nResults written to {}", csv_file);
+
+    // The random walk is what the loads cost with no prefetching at all, the
+    // speedup over it at the same stride is what the prefetchers bring.
+    let random = series.last().unwrap();
+    println!("\n===== Speedup Over Random =====");
+    println!(
+        "{} byte pages, strides from {} up touch a new page on every load",
+        page_size,
+        format_size(page_size)
+    );
+    println!("Stride, {}", names[..names.len() - 1].join(", "));
+    for (index, stride) in strides.iter().enumerate() {
+        let speedups: Vec<String> = series[..series.len() - 1]
+            .iter()
+            .map(|s| format!("{:.2}x", s.points[index].1 / random.points[index].1))
+            .collect();
+        println!("{}, {}", format_size(*stride), speedups.join(", "));
+    }
+
+    // the largest stride still clearly ahead, single noisy points in between
+    // don't cut the range short
+    println!("\n===== Prefetcher Coverage =====");
+    for s in &series[..series.len() - 1] {
+        let covered = s
+            .points
+            .iter()
+            .zip(random.points.iter())
+            .rfind(|(point, random)| point.1 >= random.1 * PREFETCHED_SPEEDUP);
+        match covered {
+            Some((point, _)) => {
+                let across_pages = if point.0 as u64 >= page_size {
+                    ", across page boundaries"
+                } else {
+                    ""
+                };
+                println!(
+                    "{}: prefetched up to {} strides{}",
+                    s.name,
+                    format_size(point.0 as u64),
+                    across_pages
+                );
+            }
+            None => println!("{}: not prefetched", s.name),
+        }
+    }
+
+    Ok(())
+}
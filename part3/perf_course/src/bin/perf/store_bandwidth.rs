@@ -0,0 +1,257 @@
+use std::error::Error;
+use std::fs::File;
+use std::io::Write;
+use std::ptr;
+
+use libc::{mmap, MAP_ANON, MAP_PRIVATE, PROT_READ, PROT_WRITE};
+
+use perf_course::svg_chart::{svg_file_name, AxisFormat, LineChart, Series};
+use perf_course::sweep::{format_size, ParameterGenerator, Sweep, SweepMetric};
+
+use crate::Params;
+
+extern "C" {
+    fn Write_8(pass_count: u64, data: *mut u8, region_size: u64);
+    fn Write_16(pass_count: u64, data: *mut u8, region_size: u64);
+    fn Write_32(pass_count: u64, data: *mut u8, region_size: u64);
+    fn Write_64(pass_count: u64, data: *mut u8, region_size: u64);
+    fn WriteNT_8(pass_count: u64, data: *mut u8, region_size: u64);
+    fn WriteNT_16(pass_count: u64, data: *mut u8, region_size: u64);
+    fn WriteNT_32(pass_count: u64, data: *mut u8, region_size: u64);
+    fn WriteNT_64(pass_count: u64, data: *mut u8, region_size: u64);
+    fn WriteFill(pass_count: u64, data: *mut u8, region_size: u64);
+}
+
+// bytes written per timed run, split in as many passes as the region allows
+const BYTES_PER_RUN: u64 = 256 * 1024 * 1024;
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+enum StoreKind {
+    Regular,
+    NonTemporal,
+    Fill,
+}
+
+struct StoreKernel {
+    name: &'static str,
+    kind: StoreKind,
+    width: u64,
+    function: unsafe extern "C" fn(pass_count: u64, data: *mut u8, region_size: u64),
+}
+
+#[cfg(target_arch = "x86_64")]
+const FILL_NAME: &str = "rep stosb";
+#[cfg(not(target_arch = "x86_64"))]
+const FILL_NAME: &str = "dc zva";
+
+// the 32 and 64 byte x86 kernels need AVX and AVX-512
+#[cfg(target_arch = "x86_64")]
+fn width_supported(width: u64) -> bool {
+    match width {
+        32 => std::is_x86_feature_detected!("avx"),
+        64 => std::is_x86_feature_detected!("avx512f"),
+        _ => true,
+    }
+}
+
+#[cfg(not(target_arch = "x86_64"))]
+fn width_supported(_width: u64) -> bool {
+    true
+}
+
+// Best bandwidth of the given kind of store at every region size.
+fn best_of(kernels: &[StoreKernel], series: &[Series], kind: StoreKind) -> Vec<(f64, f64)> {
+    let mut best: Vec<(f64, f64)> = Vec::new();
+    for (kernel, s) in kernels.iter().zip(series.iter()) {
+        if kernel.kind != kind {
+            continue;
+        }
+        if best.is_empty() {
+            best = s.points.clone();
+        } else {
+            for (b, p) in best.iter_mut().zip(s.points.iter()) {
+                b.1 = b.1.max(p.1);
+            }
+        }
+    }
+    best
+}
+
+// store_bandwidth [points] [csv_file], --size sets the largest size
+pub fn run(params: &Params) -> Result<(), Box<dyn Error>> {
+    let args = &params.args;
+    let max_size = params.size_or(512 * 1024 * 1024);
+    let points = args
+        .get(1)
+        .map(|s| s.parse::<usize>().expect("Invalid point count"))
+        .unwrap_or(24);
+    let csv_file = args
+        .get(2)
+        .cloned()
+        .unwrap_or("store_bandwidth.csv".to_string());
+
+    let total_size = max_size as usize;
+    let addr = unsafe {
+        mmap(
+            ptr::null_mut(), // Address at which to start the mapping (nullptr lets the OS choose)
+            total_size,      // Number of bytes to map
+            PROT_READ | PROT_WRITE, // Enable read and write access
+            MAP_PRIVATE | MAP_ANON,
+            -1, // File descriptor not used with MAP_ANON
+            0,  // Offset not used with MAP_ANON
+        )
+    };
+    let buffer = unsafe { std::slice::from_raw_parts_mut(addr as *mut u8, total_size) };
+    // fault every page in up front so no sweep point pays for it
+    buffer.fill(1);
+
+    let kernels: Vec<StoreKernel> = vec![
+        StoreKernel {
+            name: "Store 8",
+            kind: StoreKind::Regular,
+            width: 8,
+            function: Write_8,
+        },
+        StoreKernel {
+            name: "Store 16",
+            kind: StoreKind::Regular,
+            width: 16,
+            function: Write_16,
+        },
+        StoreKernel {
+            name: "Store 32",
+            kind: StoreKind::Regular,
+            width: 32,
+            function: Write_32,
+        },
+        StoreKernel {
+            name: "Store 64",
+            kind: StoreKind::Regular,
+            width: 64,
+            function: Write_64,
+        },
+        StoreKernel {
+            name: "NT 8",
+            kind: StoreKind::NonTemporal,
+            width: 8,
+            function: WriteNT_8,
+        },
+        StoreKernel {
+            name: "NT 16",
+            kind: StoreKind::NonTemporal,
+            width: 16,
+            function: WriteNT_16,
+        },
+        StoreKernel {
+            name: "NT 32",
+            kind: StoreKind::NonTemporal,
+            width: 32,
+            function: WriteNT_32,
+        },
+        StoreKernel {
+            name: "NT 64",
+            kind: StoreKind::NonTemporal,
+            width: 64,
+            function: WriteNT_64,
+        },
+        StoreKernel {
+            name: FILL_NAME,
+            kind: StoreKind::Fill,
+            width: 1,
+            function: WriteFill,
+        },
+    ]
+    .into_iter()
+    .filter(|kernel| width_supported(kernel.width))
+    .collect();
+
+    // page multiples keep every region a whole number of fill blocks
+    let generator = ParameterGenerator::LogSpaced {
+        start: 16 * 1024,
+        end: max_size,
+        points,
+    };
+    let sizes = generator.aligned_values(4096);
+
+    let mut series = Vec::new();
+    for kernel in &kernels {
+        let sweep = Sweep {
+            name: kernel.name.to_string(),
+            parameter_label: "Region Size".to_string(),
+            generator: ParameterGenerator::List(sizes.clone()),
+            metric: SweepMetric::Bandwidth,
+            seconds_to_try: params.seconds_or(1),
+        };
+
+        let results = sweep.run(|region_size| {
+            let pass_count = (BYTES_PER_RUN / region_size).max(1);
+            unsafe {
+                (kernel.function)(pass_count, buffer.as_mut_ptr(), region_size);
+            }
+            pass_count * region_size
+        });
+        series.push(Series::from(&results));
+    }
+
+    let mut file = File::create(&csv_file)?;
+    let names: Vec<&str> = kernels.iter().map(|kernel| kernel.name).collect();
+    writeln!(file, "Region Size, {}", names.join(", "))?;
+    println!("\n===== Store Bandwidth (GB/s) =====");
+    println!("Region Size, {}", names.join(", "));
+    for (index, size) in sizes.iter().enumerate() {
+        let values: Vec<f64> = series.iter().map(|s| s.points[index].1).collect();
+        let printed: Vec<String> = values.iter().map(|v| format!("{:.2}", v)).collect();
+        let written: Vec<String> = values.iter().map(|v| v.to_string()).collect();
+        println!("{}, {}", format_size(*size), printed.join(", "));
+        writeln!(file, "{}, {}", size, written.join(", "))?;
+    }
+
+    LineChart {
+        title: "Store Bandwidth".to_string(),
+        x_label: "Region Size".to_string(),
+        y_label: "GB/s".to_string(),
+        x_format: AxisFormat::Size,
+        log_x: true,
+        series: series.clone(),
+    }
+    .save(&svg_file_name(&csv_file))?;
+    println!("\nResults written to {}", csv_file);
+
+    // non-temporal stores win from the first size where they stay ahead
+    let regular = best_of(&kernels, &series, StoreKind::Regular);
+    let non_temporal = best_of(&kernels, &series, StoreKind::NonTemporal);
+    let mut crossover = None;
+    for (r, n) in regular.iter().zip(non_temporal.iter()).rev() {
+        if n.1 <= r.1 {
+            break;
+        }
+        crossover = Some(n.0 as u64);
+    }
+
+    println!("\n===== Non-temporal Stores =====");
+    match crossover {
+        Some(size) => println!("Non-temporal stores win from {} up", format_size(size)),
+        None => println!("Non-temporal stores never win"),
+    }
+
+    // Regular stores read every line before writing it, non-temporal ones
+    // don't, so out of the caches the gap is the write-allocate cost.
+    if let (Some(r), Some(n)) = (regular.last(), non_temporal.last()) {
+        println!(
+            "At {}: regular {:.2} GB/s, non-temporal {:.2} GB/s",
+            format_size(r.0 as u64),
+            r.1,
+            n.1
+        );
+        if n.1 > r.1 {
+            println!(
+                "Write-allocate penalty: ~{:.0}% of the store bandwidth",
+                (1.0 - r.1 / n.1) * 100.0
+            );
+        } else {
+            println!("No write-allocate penalty visible at this size");
+        }
+    }
+
+    Ok(())
+}
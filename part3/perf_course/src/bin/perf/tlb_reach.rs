@@ -0,0 +1,209 @@
+use std::error::Error;
+use std::fs::File;
+use std::io::Write;
+
+use perf_course::cache_hierarchy::find_plateaus;
+use perf_course::perf_metrics::estimate_cpu_frequency;
+use perf_course::pointer_chase::{chase, ChaseBuffer, ChaseOrder};
+use perf_course::svg_chart::{svg_file_name, AxisFormat, LineChart, Series};
+use perf_course::sweep::{format_size, ParameterGenerator, Sweep, SweepMetric};
+
+use crate::Params;
+
+// dependent loads per timed run
+const STEPS_PER_RUN: u64 = 1024 * 1024;
+
+fn usage(program: &str) -> ! {
+    eprintln!(
+        "Usage: {} [--max-pages <count>] [--page-size <bytes>] [--ghz <value>] [--csv <file>]",
+        program
+    );
+    std::process::exit(1);
+}
+
+// Cycles per load of a walk touching one line in each of `pages` pages, in
+// page count order.
+fn run_walk(
+    name: &'static str,
+    buffer: &mut ChaseBuffer,
+    page_size: usize,
+    max_pages: u64,
+    frequency: f64,
+    seconds_to_try: u64,
+) -> Vec<(u64, f64)> {
+    let sweep = Sweep {
+        name: name.to_string(),
+        parameter_label: "Pages".to_string(),
+        generator: ParameterGenerator::LogSpaced {
+            start: 1,
+            end: max_pages,
+            points: 64,
+        },
+        metric: SweepMetric::Seconds,
+        seconds_to_try,
+    };
+
+    let order = ChaseOrder::OnePerPage { page_size };
+    let mut built_for = 0;
+    let mut start = std::ptr::null();
+    let results = sweep.run(|pages| {
+        if pages != built_for {
+            start = buffer.build(pages as usize * page_size, order, pages);
+            built_for = pages;
+        }
+        chase(start, STEPS_PER_RUN);
+        STEPS_PER_RUN * std::mem::size_of::<usize>() as u64
+    });
+
+    results
+        .points
+        .iter()
+        .map(|point| {
+            (
+                point.parameter,
+                point.seconds * frequency / STEPS_PER_RUN as f64,
+            )
+        })
+        .collect()
+}
+
+pub fn run(params: &Params) -> Result<(), Box<dyn Error>> {
+    let args = &params.args;
+
+    let mut max_pages: u64 = 64 * 1024;
+    let mut page_size = unsafe { libc::sysconf(libc::_SC_PAGESIZE) } as usize;
+    let mut ghz: Option<f64> = None;
+    let mut csv_file = "tlb_reach.csv".to_string();
+
+    let mut i = 1;
+    while i < args.len() {
+        let value = args.get(i + 1).cloned().unwrap_or_else(|| usage(&args[0]));
+        match &args[i][..] {
+            "--max-pages" => max_pages = value.parse().expect("Invalid page count"),
+            "--page-size" => page_size = value.parse().expect("Invalid page size"),
+            "--ghz" => ghz = Some(value.parse().expect("Invalid frequency")),
+            "--csv" => csv_file = value,
+            _ => usage(&args[0]),
+        }
+        i += 2;
+    }
+
+    let frequency = match ghz {
+        Some(ghz) => ghz * 1e9,
+        None => estimate_cpu_frequency(),
+    };
+    println!("CPU frequency: {:.2} GHz", frequency / 1e9);
+    println!("Page size: {}", format_size(page_size as u64));
+
+    #[cfg(target_os = "linux")]
+    if let Ok(enabled) = std::fs::read_to_string("/sys/kernel/mm/transparent_hugepage/enabled") {
+        if enabled.contains("[never]") {
+            eprintln!(
+                "Transparent huge pages are disabled, the huge page walk will use small pages"
+            );
+        }
+    }
+
+    let total_size = max_pages as usize * page_size;
+    let (mut small_buffer, mut huge_buffer) = match (
+        ChaseBuffer::new(total_size, false),
+        ChaseBuffer::new(total_size, true),
+    ) {
+        (Some(small_buffer), Some(huge_buffer)) => (small_buffer, huge_buffer),
+        _ => {
+            eprintln!("mmap failed");
+            return Ok(());
+        }
+    };
+
+    // The same walk over small and huge pages touches the same lines, only the
+    // number of translations differs, so the difference is the TLB cost.
+    let small = run_walk(
+        "Small Pages",
+        &mut small_buffer,
+        page_size,
+        max_pages,
+        frequency,
+        params.seconds_or(1),
+    );
+    let huge = run_walk(
+        "Huge Pages",
+        &mut huge_buffer,
+        page_size,
+        max_pages,
+        frequency,
+        params.seconds_or(1),
+    );
+
+    let mut file = File::create(&csv_file)?;
+    writeln!(
+        file,
+        "Pages, Small Page Cycles, Huge Page Cycles, Translation Cycles"
+    )?;
+    println!("\n===== TLB Reach =====");
+    println!("Pages, Reach, Small Page Cycles, Huge Page Cycles, Translation Cycles");
+    for ((pages, small_cycles), (_, huge_cycles)) in small.iter().zip(huge.iter()) {
+        let translation = small_cycles - huge_cycles;
+        println!(
+            "{}, {}, {:.1}, {:.1}, {:.1}",
+            pages,
+            format_size(pages * page_size as u64),
+            small_cycles,
+            huge_cycles,
+            translation
+        );
+        writeln!(
+            file,
+            "{}, {}, {}, {}",
+            pages, small_cycles, huge_cycles, translation
+        )?;
+    }
+
+    LineChart {
+        title: "Cycles Per Access, One Line Per Page".to_string(),
+        x_label: "Pages".to_string(),
+        y_label: "Cycles".to_string(),
+        x_format: AxisFormat::Number,
+        log_x: true,
+        series: vec![
+            Series {
+                name: "Small Pages".to_string(),
+                points: small.iter().map(|(p, c)| (*p as f64, *c)).collect(),
+            },
+            Series {
+                name: "Huge Pages".to_string(),
+                points: huge.iter().map(|(p, c)| (*p as f64, *c)).collect(),
+            },
+        ],
+    }
+    .save(&svg_file_name(&csv_file))?;
+    println!("\nResults written to {}", csv_file);
+
+    // each TLB level shows up as a plateau, the last one is a page walk per access
+    let plateaus = find_plateaus(&small);
+    let huge_plateaus = find_plateaus(&huge);
+    let baseline = huge_plateaus.first().map(|p| p.value).unwrap_or(0.0);
+
+    println!(
+        "\n===== Translation Levels ({} pages) =====",
+        format_size(page_size as u64)
+    );
+    for (index, plateau) in plateaus.iter().enumerate() {
+        let name = if index + 1 == plateaus.len() && plateaus.len() > 1 {
+            "Page Walk".to_string()
+        } else {
+            format!("L{} TLB", index + 1)
+        };
+        println!(
+            "{}: up to {} pages ({} reach), {:.1} cycles, {:.1} over the first level",
+            name,
+            plateau.last_size,
+            format_size(plateau.last_size * page_size as u64),
+            plateau.value,
+            plateau.value - plateaus[0].value
+        );
+    }
+    println!("Huge page baseline: {:.1} cycles", baseline);
+
+    Ok(())
+}
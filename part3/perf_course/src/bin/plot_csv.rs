@@ -0,0 +1,152 @@
+extern crate perf_course;
+
+use std::env;
+use std::error::Error;
+use std::path::Path;
+
+use perf_course::svg_chart::{read_csv, svg_file_name, AxisFormat, BarChart, LineChart, Series};
+
+fn usage(program: &str) -> ! {
+    eprintln!(
+        "Usage: {} <file.csv> [--x <column>] [--y <column>]... [--log-x] [--size] [--bar] [--title <title>]",
+        program
+    );
+    std::process::exit(1);
+}
+
+fn column_index(header: &[String], name: &str) -> usize {
+    match header.iter().position(|column| column == name) {
+        Some(index) => index,
+        None => {
+            eprintln!(
+                "Unknown column '{}', available: {}",
+                name,
+                header.join(", ")
+            );
+            std::process::exit(1);
+        }
+    }
+}
+
+// Renders a CSV written by the listings or the sweeps (for example
+// fault_counter.csv) into an SVG saved next to it:
+//
+//   plot_csv ../../part2/rust-parser/fault_counter.csv --x "Touch Count" --y "Fault Count"
+//   plot_csv cache_size_sweep.csv --log-x --size
+fn main() -> Result<(), Box<dyn Error>> {
+    let args: Vec<String> = env::args().collect();
+    if args.len() < 2 {
+        usage(&args[0]);
+    }
+
+    let csv_file = &args[1];
+    let mut x_column: Option<String> = None;
+    let mut y_columns: Vec<String> = Vec::new();
+    let mut log_x = false;
+    let mut x_format = AxisFormat::Number;
+    let mut bar = false;
+    let mut title: Option<String> = None;
+
+    let mut i = 2;
+    while i < args.len() {
+        match &args[i][..] {
+            "--x" => {
+                i += 1;
+                x_column = Some(args.get(i).cloned().unwrap_or_else(|| usage(&args[0])));
+            }
+            "--y" => {
+                i += 1;
+                y_columns.push(args.get(i).cloned().unwrap_or_else(|| usage(&args[0])));
+            }
+            "--title" => {
+                i += 1;
+                title = Some(args.get(i).cloned().unwrap_or_else(|| usage(&args[0])));
+            }
+            "--log-x" => log_x = true,
+            "--size" => x_format = AxisFormat::Size,
+            "--bar" => bar = true,
+            _ => usage(&args[0]),
+        }
+        i += 1;
+    }
+
+    let (header, rows) = read_csv(csv_file)?;
+    if header.is_empty() {
+        eprintln!("{} has no header line", csv_file);
+        std::process::exit(1);
+    }
+
+    let x_index = column_index(&header, x_column.as_deref().unwrap_or(&header[0]));
+    let y_indexes: Vec<usize> = if y_columns.is_empty() {
+        (0..header.len())
+            .filter(|index| *index != x_index)
+            .collect()
+    } else {
+        y_columns
+            .iter()
+            .map(|column| column_index(&header, column))
+            .collect()
+    };
+
+    let title = title.unwrap_or_else(|| {
+        Path::new(csv_file)
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or(csv_file.to_string())
+    });
+    let output_file = svg_file_name(csv_file);
+
+    if bar {
+        // one bar per row, labelled by the x column, valued by the first y column
+        let Some(&y_index) = y_indexes.first() else {
+            return Err("--bar needs a y column".into());
+        };
+        let bars = rows
+            .iter()
+            .filter_map(|row| {
+                let value = row.get(y_index)?.parse::<f64>().ok()?;
+                Some((row.get(x_index)?.clone(), value))
+            })
+            .collect();
+
+        BarChart {
+            title,
+            y_label: header[y_index].clone(),
+            bars,
+        }
+        .save(&output_file)?;
+    } else {
+        let series = y_indexes
+            .iter()
+            .map(|y_index| Series {
+                name: header[*y_index].clone(),
+                points: rows
+                    .iter()
+                    .filter_map(|row| {
+                        let x = row.get(x_index)?.parse::<f64>().ok()?;
+                        let y = row.get(*y_index)?.parse::<f64>().ok()?;
+                        Some((x, y))
+                    })
+                    .collect(),
+            })
+            .collect();
+
+        LineChart {
+            title,
+            x_label: header[x_index].clone(),
+            y_label: y_indexes
+                .iter()
+                .map(|y_index| header[*y_index].clone())
+                .collect::<Vec<String>>()
+                .join(", "),
+            x_format,
+            log_x,
+            series,
+        }
+        .save(&output_file)?;
+    }
+
+    println!("Chart written to {}", output_file);
+
+    Ok(())
+}
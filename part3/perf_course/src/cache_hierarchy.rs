@@ -0,0 +1,421 @@
+use crate::sweep::format_size;
+
+// Two neighbouring points belong to the same plateau when their values are
+// within this fraction of each other.
+const PLATEAU_TOLERANCE: f64 = 0.08;
+// A plateau needs at least this many points, anything shorter is a transition.
+const MIN_PLATEAU_POINTS: usize = 3;
+// Plateaus closer than this are noise on the same level, real levels are at
+// least a third apart in both bandwidth and latency.
+const MERGE_TOLERANCE: f64 = 0.25;
+
+#[derive(Debug, Clone)]
+pub struct Plateau {
+    pub first_size: u64,
+    pub last_size: u64,
+    // median of the plateau, GB/s for bandwidth sweeps, ns for latency sweeps
+    pub value: f64,
+    pub point_count: usize,
+}
+
+#[derive(Debug, Clone)]
+pub struct DetectedLevel {
+    pub name: String,
+    // largest working set that still ran at the plateau bandwidth, None for memory
+    pub size: Option<u64>,
+    pub bandwidth: f64,
+}
+
+// What the OS reports about one cache, used to cross-check the measurements.
+#[derive(Debug, Clone)]
+pub struct SystemCache {
+    pub level: u32,
+    pub cache_type: String,
+    pub size: u64,
+    pub ways: Option<u64>,
+    pub sets: Option<u64>,
+    pub line_size: Option<u64>,
+}
+
+impl SystemCache {
+    pub fn name(&self) -> String {
+        match &self.cache_type[..] {
+            "Data" => format!("L{}D", self.level),
+            "Instruction" => format!("L{}I", self.level),
+            _ => format!("L{}", self.level),
+        }
+    }
+}
+
+fn median(values: &mut [f64]) -> f64 {
+    values.sort_by(|a, b| a.total_cmp(b));
+    values[values.len() / 2]
+}
+
+// |to - from| relative to `from`, a failed wave reports 0 so anything moving
+// away from 0 counts as a full change instead of dividing by it
+fn relative_change(from: f64, to: f64) -> f64 {
+    if from == 0.0 {
+        if to == 0.0 {
+            0.0
+        } else {
+            f64::INFINITY
+        }
+    } else {
+        (to - from).abs() / from.abs()
+    }
+}
+
+// Groups (working set size, value) points, sorted by size, into runs of
+// roughly constant value. Each sample is replaced by the median of its
+// neighbours first so a single noisy wave does not split a plateau.
+pub fn find_plateaus(points: &[(u64, f64)]) -> Vec<Plateau> {
+    let smoothed: Vec<f64> = (0..points.len())
+        .map(|i| {
+            let from = i.saturating_sub(1);
+            let to = (i + 2).min(points.len());
+            let mut window: Vec<f64> = points[from..to].iter().map(|(_, b)| *b).collect();
+            median(&mut window)
+        })
+        .collect();
+
+    let mut plateaus: Vec<Plateau> = Vec::new();
+    let mut start = 0;
+    for i in 1..=points.len() {
+        let split = i == points.len() || {
+            relative_change(smoothed[i - 1], smoothed[i]) > PLATEAU_TOLERANCE
+        };
+
+        if split {
+            if i - start >= MIN_PLATEAU_POINTS {
+                let mut values = smoothed[start..i].to_vec();
+                let plateau = Plateau {
+                    first_size: points[start].0,
+                    last_size: points[i - 1].0,
+                    value: median(&mut values),
+                    point_count: i - start,
+                };
+
+                // noise or a slow drift can cut a level in two, glue it back together
+                match plateaus.last_mut() {
+                    Some(last) if relative_change(last.value, plateau.value) <= MERGE_TOLERANCE => {
+                        last.last_size = plateau.last_size;
+                        last.point_count += plateau.point_count;
+                    }
+                    _ => plateaus.push(plateau),
+                }
+            }
+            start = i;
+        }
+    }
+
+    plateaus
+}
+
+// Every plateau but the last is a cache level whose size is where its
+// plateau ends, the last one is main memory.
+pub fn detect_cache_levels(points: &[(u64, f64)]) -> Vec<DetectedLevel> {
+    let plateaus = find_plateaus(points);
+    let level_count = plateaus.len();
+
+    plateaus
+        .iter()
+        .enumerate()
+        .map(|(index, plateau)| {
+            if index + 1 == level_count && level_count > 1 {
+                DetectedLevel {
+                    name: "Memory".to_string(),
+                    size: None,
+                    bandwidth: plateau.value,
+                }
+            } else {
+                DetectedLevel {
+                    name: format!("L{}", index + 1),
+                    size: Some(plateau.last_size),
+                    bandwidth: plateau.value,
+                }
+            }
+        })
+        .collect()
+}
+
+#[cfg(target_os = "linux")]
+pub fn system_caches() -> Vec<SystemCache> {
+    use std::fs;
+
+    let read = |path: &str| fs::read_to_string(path).ok().map(|s| s.trim().to_string());
+    // sysfs sizes look like "48K" or "32M"
+    let parse_size = |value: String| -> Option<u64> {
+        let (number, multiplier) = match value.chars().last()? {
+            'K' => (&value[..value.len() - 1], 1024),
+            'M' => (&value[..value.len() - 1], 1024 * 1024),
+            'G' => (&value[..value.len() - 1], 1024 * 1024 * 1024),
+            _ => (&value[..], 1),
+        };
+        number.parse::<u64>().ok().map(|n| n * multiplier)
+    };
+
+    let mut caches = Vec::new();
+    for index in 0.. {
+        let base = format!("/sys/devices/system/cpu/cpu0/cache/index{}", index);
+        let level = match read(&format!("{}/level", base)).and_then(|l| l.parse::<u32>().ok()) {
+            Some(level) => level,
+            None => break,
+        };
+
+        caches.push(SystemCache {
+            level,
+            cache_type: read(&format!("{}/type", base)).unwrap_or_default(),
+            size: read(&format!("{}/size", base))
+                .and_then(parse_size)
+                .unwrap_or(0),
+            ways: read(&format!("{}/ways_of_associativity", base)).and_then(|v| v.parse().ok()),
+            sets: read(&format!("{}/number_of_sets", base)).and_then(|v| v.parse().ok()),
+            line_size: read(&format!("{}/coherency_line_size", base)).and_then(|v| v.parse().ok()),
+        });
+    }
+
+    caches
+}
+
+#[cfg(target_os = "macos")]
+pub fn system_caches() -> Vec<SystemCache> {
+    use std::ffi::CString;
+
+    let sysctl = |name: &str| -> Option<u64> {
+        let name = CString::new(name).ok()?;
+        let mut value: u64 = 0;
+        let mut size = std::mem::size_of::<u64>();
+        let result = unsafe {
+            libc::sysctlbyname(
+                name.as_ptr(),
+                &mut value as *mut u64 as *mut libc::c_void,
+                &mut size,
+                std::ptr::null_mut(),
+                0,
+            )
+        };
+        if result == 0 && value > 0 {
+            Some(value)
+        } else {
+            None
+        }
+    };
+
+    // Apple silicon reports the performance cores under hw.perflevel0
+    let line_size = sysctl("hw.cachelinesize");
+    let entries = [
+        (1, "Data", "l1dcachesize"),
+        (1, "Instruction", "l1icachesize"),
+        (2, "Unified", "l2cachesize"),
+        (3, "Unified", "l3cachesize"),
+    ];
+
+    entries
+        .iter()
+        .filter_map(|(level, cache_type, name)| {
+            let size = sysctl(&format!("hw.perflevel0.{}", name))
+                .or_else(|| sysctl(&format!("hw.{}", name)))?;
+            Some(SystemCache {
+                level: *level,
+                cache_type: cache_type.to_string(),
+                size,
+                ways: None,
+                sets: None,
+                line_size,
+            })
+        })
+        .collect()
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "macos")))]
+pub fn system_caches() -> Vec<SystemCache> {
+    Vec::new()
+}
+
+pub fn print_report(levels: &[DetectedLevel], system: &[SystemCache]) {
+    println!("\n===== Detected Cache Hierarchy =====");
+    for level in levels {
+        match level.size {
+            Some(size) => println!(
+                "{}: ~{} at {:.2} GB/s",
+                level.name,
+                format_size(size),
+                level.bandwidth
+            ),
+            None => println!("{}: {:.2} GB/s", level.name, level.bandwidth),
+        }
+    }
+
+    if system.is_empty() {
+        println!("\nNo cache information available from the OS");
+        return;
+    }
+
+    println!("\n===== Reported By The OS =====");
+    let data_caches: Vec<&SystemCache> = system
+        .iter()
+        .filter(|cache| cache.cache_type != "Instruction")
+        .collect();
+
+    for cache in &data_caches {
+        let detected = levels
+            .iter()
+            .find(|level| level.name == format!("L{}", cache.level))
+            .and_then(|level| level.size);
+
+        print!("{}: {}", cache.name(), format_size(cache.size));
+        match detected {
+            Some(size) => {
+                let ratio = size as f64 / cache.size as f64;
+                print!(" (detected {}, {:.0}%", format_size(size), ratio * 100.0);
+                if (0.5..=1.5).contains(&ratio) {
+                    println!(", matches)");
+                } else {
+                    println!(", MISMATCH)");
+                }
+            }
+            None => println!(" (not detected)"),
+        }
+    }
+}
+
+// Throughput of touching `lines` cache lines spaced `stride` bytes apart.
+#[derive(Debug, Clone)]
+pub struct StrideCurve {
+    pub stride: u64,
+    pub points: Vec<(u64, f64)>,
+}
+
+#[derive(Debug, Clone)]
+pub struct AssociativityEstimate {
+    pub name: String,
+    pub ways: u64,
+    pub sets: u64,
+    pub line_size: u64,
+}
+
+impl AssociativityEstimate {
+    pub fn size(&self) -> u64 {
+        self.ways * self.sets * self.line_size
+    }
+}
+
+// Lines that fit in each level before the throughput drops, for one stride.
+// The last plateau never ran out of room inside the sweep so it is left out.
+fn level_capacities(curve: &StrideCurve) -> Vec<u64> {
+    let plateaus = find_plateaus(&curve.points);
+    let level_count = plateaus.len().saturating_sub(1);
+    plateaus
+        .iter()
+        .take(level_count)
+        .map(|plateau| plateau.last_size)
+        .collect()
+}
+
+// With a stride of a multiple of (sets * line size) every touched line falls in
+// the same set, so a level only holds `ways` of them. Below that stride the lines
+// spread over several sets and the level holds more. So for each level, the
+// capacity at the largest strides is the way count, and the smallest stride that
+// already hits that capacity is sets * line size.
+pub fn infer_associativity(curves: &[StrideCurve], line_size: u64) -> Vec<AssociativityEstimate> {
+    let capacities: Vec<(u64, Vec<u64>)> = curves
+        .iter()
+        .map(|curve| (curve.stride, level_capacities(curve)))
+        .collect();
+    let level_count = capacities
+        .iter()
+        .map(|(_, levels)| levels.len())
+        .max()
+        .unwrap_or(0);
+
+    let mut estimates = Vec::new();
+    for level in 0..level_count {
+        let mut by_stride: Vec<(u64, u64)> = capacities
+            .iter()
+            .filter_map(|(stride, levels)| levels.get(level).map(|lines| (*stride, *lines)))
+            .collect();
+        by_stride.sort_by_key(|(stride, _)| *stride);
+
+        let ways = match by_stride.last() {
+            Some((_, lines)) => *lines,
+            None => continue,
+        };
+
+        // walk back from the largest stride while the capacity stays at `ways`
+        let mut set_span = by_stride.last().unwrap().0;
+        for (stride, lines) in by_stride.iter().rev() {
+            if *lines != ways {
+                break;
+            }
+            set_span = *stride;
+        }
+
+        estimates.push(AssociativityEstimate {
+            name: format!("L{}", level + 1),
+            ways,
+            sets: (set_span / line_size).max(1),
+            line_size,
+        });
+    }
+
+    estimates
+}
+
+pub fn print_stride_matrix(curves: &[StrideCurve], value_label: &str) {
+    let line_counts: Vec<u64> = match curves.first() {
+        Some(curve) => curve.points.iter().map(|(lines, _)| *lines).collect(),
+        None => return,
+    };
+
+    println!(
+        "\n===== {} (rows: stride, columns: lines touched) =====",
+        value_label
+    );
+    print!("{:>8}", "");
+    for lines in &line_counts {
+        print!("{:>6}", lines);
+    }
+    println!();
+
+    for curve in curves {
+        print!("{:>8}", format_size(curve.stride));
+        for (_, value) in &curve.points {
+            print!("{:>6.2}", value);
+        }
+        println!();
+    }
+}
+
+pub fn print_associativity_report(estimates: &[AssociativityEstimate], system: &[SystemCache]) {
+    println!("\n===== Inferred Associativity =====");
+    if estimates.is_empty() {
+        println!("No level ran out of ways inside the sweep, try more lines or larger strides");
+    }
+
+    for estimate in estimates {
+        let level = estimate.name[1..].parse::<u32>().unwrap_or(0);
+        let reported = system
+            .iter()
+            .find(|cache| cache.level == level && cache.cache_type != "Instruction");
+
+        let name = reported
+            .map(|cache| cache.name())
+            .unwrap_or(estimate.name.clone());
+        print!(
+            "{}: {}-way, {} sets ({})",
+            name,
+            estimate.ways,
+            estimate.sets,
+            format_size(estimate.size())
+        );
+
+        match reported.and_then(|cache| Some((cache.ways?, cache.sets?))) {
+            Some((ways, sets)) if ways == estimate.ways && sets == estimate.sets => {
+                println!(", OS reports the same")
+            }
+            Some((ways, sets)) => println!(", OS reports {}-way, {} sets", ways, sets),
+            None => println!(),
+        }
+    }
+}
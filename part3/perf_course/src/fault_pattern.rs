@@ -0,0 +1,236 @@
+use std::io;
+
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::SeedableRng;
+
+use crate::svg_chart::read_csv;
+
+// the rows listings 112 and 119 print, and the fault_counter CSVs carry
+pub const FAULT_CSV_HEADER: &str = "Page Count, Touch Count, Fault Count, Extra Faults";
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum TouchOrder {
+    // first page to last
+    Forward,
+    // last page to first, fault-around may only map pages ahead of the fault
+    Backward,
+    // every page once in a shuffled order, nothing for the kernel to guess
+    Random { seed: u64 },
+    // every `stride`th page, then the same pass again one page further along
+    Strided { stride: usize },
+}
+
+impl TouchOrder {
+    // forward, backward, random[:seed] or strided[:pages]
+    pub fn parse(value: &str) -> Option<TouchOrder> {
+        let (name, argument) = match value.split_once(':') {
+            Some((name, argument)) => (name, Some(argument.parse::<u64>().ok()?)),
+            None => (value, None),
+        };
+        match (name, argument) {
+            ("forward", None) => Some(TouchOrder::Forward),
+            ("backward", None) => Some(TouchOrder::Backward),
+            ("random", seed) => Some(TouchOrder::Random {
+                seed: seed.unwrap_or(0),
+            }),
+            ("strided", stride) => Some(TouchOrder::Strided {
+                stride: stride.unwrap_or(16).max(1) as usize,
+            }),
+            _ => None,
+        }
+    }
+
+    pub fn name(&self) -> String {
+        match self {
+            TouchOrder::Forward => "forward".to_string(),
+            TouchOrder::Backward => "backward".to_string(),
+            TouchOrder::Random { .. } => "random".to_string(),
+            TouchOrder::Strided { stride } => format!("strided {}", stride),
+        }
+    }
+
+    // Page indices in the order they get touched, every one of the
+    // `page_count` pages exactly once.
+    pub fn pages(&self, page_count: usize) -> Vec<usize> {
+        match self {
+            TouchOrder::Forward => (0..page_count).collect(),
+            TouchOrder::Backward => (0..page_count).rev().collect(),
+            TouchOrder::Random { seed } => {
+                let mut pages: Vec<usize> = (0..page_count).collect();
+                pages.shuffle(&mut StdRng::seed_from_u64(*seed));
+                pages
+            }
+            TouchOrder::Strided { stride } => (0..*stride)
+                .flat_map(|start| (start..page_count).step_by(*stride))
+                .collect(),
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone)]
+pub struct FaultRow {
+    pub touch_count: u64,
+    pub fault_count: i64,
+}
+
+// Reads the Touch Count and Fault Count columns of a fault counter CSV. Lines
+// that aren't rows, like the addresses listing 112 prints between them, are
+// skipped.
+pub fn read_fault_csv(file_name: &str) -> io::Result<Vec<FaultRow>> {
+    let (header, rows) = read_csv(file_name)?;
+    let column = |name: &str| {
+        header.iter().position(|c| c == name).ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("{} has no '{}' column", file_name, name),
+            )
+        })
+    };
+    let touch_column = column("Touch Count")?;
+    let fault_column = column("Fault Count")?;
+
+    Ok(rows
+        .iter()
+        .filter_map(|row| {
+            Some(FaultRow {
+                touch_count: row.get(touch_column)?.parse::<u64>().ok()?,
+                fault_count: row.get(fault_column)?.parse::<i64>().ok()?,
+            })
+        })
+        .collect())
+}
+
+#[derive(Debug, Clone)]
+pub struct FaultPattern {
+    pub touch_count: u64,
+    pub fault_count: i64,
+    // the usual number of pages touched between two faults, how many pages
+    // the kernel maps per fault
+    pub pages_per_fault: Option<u64>,
+    // touch counts where one more page cost more than one fault
+    pub over_faults: Vec<u64>,
+    // the usual number of pages between two of them
+    pub over_fault_period: Option<u64>,
+}
+
+// the most frequent value, the smallest one on a tie
+fn most_common(values: &[u64]) -> Option<u64> {
+    let mut sorted = values.to_vec();
+    sorted.sort();
+    let mut best: Option<(u64, usize)> = None;
+    for group in sorted.chunk_by(|a, b| a == b) {
+        if best.is_none_or(|(_, count)| group.len() > count) {
+            best = Some((group[0], group.len()));
+        }
+    }
+    best.map(|(value, _)| value)
+}
+
+fn gaps(positions: &[u64]) -> Vec<u64> {
+    positions.windows(2).map(|pair| pair[1] - pair[0]).collect()
+}
+
+// Works on both kinds of rows: listing 112 maps fresh memory for every touch
+// count, listing 119 keeps counting over one walk. Either way a row whose
+// fault count went up by one is where the next fault landed, and by more than
+// one is an over-fault.
+pub fn analyze_faults(rows: &[FaultRow]) -> FaultPattern {
+    let mut rows = rows.to_vec();
+    rows.sort_by_key(|row| row.touch_count);
+
+    let mut faults = Vec::new();
+    let mut over_faults = Vec::new();
+    let mut prior_fault_count = 0;
+    for row in &rows {
+        let new_faults = row.fault_count - prior_fault_count;
+        if new_faults >= 1 {
+            faults.push(row.touch_count);
+        }
+        if new_faults > 1 {
+            over_faults.push(row.touch_count);
+        }
+        prior_fault_count = row.fault_count;
+    }
+
+    // a single fault still says how far it reached
+    let pages_per_fault = match faults.len() {
+        0 => None,
+        1 => rows.last().map(|row| row.touch_count),
+        _ => most_common(&gaps(&faults)),
+    };
+
+    let last = rows.last().copied().unwrap_or(FaultRow {
+        touch_count: 0,
+        fault_count: 0,
+    });
+    FaultPattern {
+        touch_count: last.touch_count,
+        fault_count: last.fault_count,
+        pages_per_fault,
+        over_fault_period: most_common(&gaps(&over_faults)),
+        over_faults,
+    }
+}
+
+// The per pattern details, then the one line summary:
+// "kernel maps 16 pages per fault forward, 1 backward"
+pub fn print_fault_patterns(patterns: &[(String, FaultPattern)]) {
+    println!("\n===== Fault Patterns =====");
+    println!("Order, Touch Count, Fault Count, Pages Per Fault, Average Pages Per Fault, Over-faults, Over-fault Period");
+    for (label, pattern) in patterns {
+        let average = if pattern.fault_count > 0 {
+            format!(
+                "{:.2}",
+                pattern.touch_count as f64 / pattern.fault_count as f64
+            )
+        } else {
+            "-".to_string()
+        };
+        let optional = |value: Option<u64>| match value {
+            Some(value) => value.to_string(),
+            None => "-".to_string(),
+        };
+        println!(
+            "{}, {}, {}, {}, {}, {}, {}",
+            label,
+            pattern.touch_count,
+            pattern.fault_count,
+            optional(pattern.pages_per_fault),
+            average,
+            pattern.over_faults.len(),
+            optional(pattern.over_fault_period)
+        );
+    }
+
+    // only the first order spells the unit out
+    let granularity: Vec<String> = patterns
+        .iter()
+        .enumerate()
+        .map(|(index, (label, pattern))| {
+            let unit = match (index, pattern.pages_per_fault) {
+                (0, Some(1)) => " page per fault",
+                (0, _) => " pages per fault",
+                _ => "",
+            };
+            match pattern.pages_per_fault {
+                Some(pages) => format!("{}{} {}", pages, unit, label),
+                None => format!("no faults {}", label),
+            }
+        })
+        .collect();
+    println!("\nkernel maps {}", granularity.join(", "));
+    for (label, pattern) in patterns {
+        match (pattern.over_faults.len(), pattern.over_fault_period) {
+            (0, _) => println!("{}: no over-faults", label),
+            (count, Some(period)) => println!(
+                "{}: {} over-faults, one every {} pages",
+                label, count, period
+            ),
+            (_, None) => println!(
+                "{}: one over-fault at page {}",
+                label, pattern.over_faults[0]
+            ),
+        }
+    }
+}
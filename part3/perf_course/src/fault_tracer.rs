@@ -0,0 +1,341 @@
+use std::fs::File;
+use std::io::{self, Write};
+use std::time::Duration;
+
+// One fault the handler resolved. `page` is counted in OS pages from the start
+// of the traced buffer, `time` from when tracing started.
+#[derive(Debug, Copy, Clone)]
+pub struct FaultEvent {
+    pub page: usize,
+    pub address: usize,
+    pub write: bool,
+    pub time: Duration,
+}
+
+// The faults one touch took, in the order the handler saw them. `page` is the
+// OS page the touch wrote to, counted like FaultEvent::page.
+#[derive(Debug, Clone)]
+pub struct TouchTrace {
+    pub page: usize,
+    pub faults: Vec<FaultEvent>,
+}
+
+#[cfg(target_os = "linux")]
+pub use linux::FaultTracer;
+
+// userfaultfd is Linux only, everywhere else there is nothing to trace with
+#[cfg(not(target_os = "linux"))]
+pub struct FaultTracer;
+
+#[cfg(not(target_os = "linux"))]
+impl FaultTracer {
+    pub fn new(_buffer: *mut u8, _size: usize) -> io::Result<FaultTracer> {
+        Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            "fault tracing needs userfaultfd, which is Linux only",
+        ))
+    }
+
+    pub fn take_faults(&self) -> Vec<FaultEvent> {
+        Vec::new()
+    }
+}
+
+#[cfg(target_os = "linux")]
+mod linux {
+    use std::io;
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::sync::{Arc, Mutex};
+    use std::thread::{self, JoinHandle};
+
+    use libc::{c_int, c_ulong, c_void};
+
+    use super::FaultEvent;
+    use crate::perf_metrics::high_resolution_clock;
+
+    // linux/userfaultfd.h, the same on x86_64 and arm64
+    const UFFD_API: u64 = 0xAA;
+    const UFFDIO_API: c_ulong = 0xC018_AA3F;
+    const UFFDIO_REGISTER: c_ulong = 0xC020_AA00;
+    const UFFDIO_UNREGISTER: c_ulong = 0x8010_AA01;
+    const UFFDIO_COPY: c_ulong = 0xC028_AA03;
+    const UFFDIO_REGISTER_MODE_MISSING: u64 = 1;
+    const UFFD_EVENT_PAGEFAULT: u8 = 0x12;
+    const UFFD_PAGEFAULT_FLAG_WRITE: u64 = 1;
+    // how long the handler waits for a fault before checking if it should stop
+    const POLL_MILLISECONDS: c_int = 50;
+
+    #[repr(C)]
+    struct UffdioApi {
+        api: u64,
+        features: u64,
+        ioctls: u64,
+    }
+
+    #[repr(C)]
+    struct UffdioRange {
+        start: u64,
+        len: u64,
+    }
+
+    #[repr(C)]
+    struct UffdioRegister {
+        range: UffdioRange,
+        mode: u64,
+        ioctls: u64,
+    }
+
+    #[repr(C)]
+    struct UffdioCopy {
+        dst: u64,
+        src: u64,
+        len: u64,
+        mode: u64,
+        copy: i64,
+    }
+
+    // uffd_msg with the pagefault arm of its union, the only event registered
+    // for
+    #[repr(C)]
+    struct UffdMsg {
+        event: u8,
+        reserved1: u8,
+        reserved2: u16,
+        reserved3: u32,
+        flags: u64,
+        address: u64,
+        ptid: u32,
+        padding: u32,
+    }
+
+    // Registers a buffer with userfaultfd and resolves its missing page faults
+    // on a handler thread, noting the address and time of each before the
+    // faulting thread is let go. The buffer has to be page aligned and not
+    // touched yet, only missing pages trap.
+    //
+    // Every fault is resolved one page at a time with a zeroed page, so the
+    // kernel doesn't get to map more than the page that faulted (no huge
+    // pages either) while traced.
+    pub struct FaultTracer {
+        fd: c_int,
+        start: usize,
+        size: usize,
+        events: Arc<Mutex<Vec<FaultEvent>>>,
+        stop: Arc<AtomicBool>,
+        handler: Option<JoinHandle<()>>,
+    }
+
+    impl FaultTracer {
+        pub fn new(buffer: *mut u8, size: usize) -> io::Result<FaultTracer> {
+            let page_size = unsafe { libc::sysconf(libc::_SC_PAGESIZE) } as usize;
+            let start = buffer as usize;
+            if !start.is_multiple_of(page_size) || !size.is_multiple_of(page_size) {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    "the traced buffer has to be whole pages",
+                ));
+            }
+
+            let fd =
+                unsafe { libc::syscall(libc::SYS_userfaultfd, libc::O_CLOEXEC | libc::O_NONBLOCK) }
+                    as c_int;
+            if fd < 0 {
+                // EPERM when vm.unprivileged_userfaultfd is 0 and we aren't root
+                return Err(io::Error::last_os_error());
+            }
+
+            let mut api = UffdioApi {
+                api: UFFD_API,
+                features: 0,
+                ioctls: 0,
+            };
+            let mut register = UffdioRegister {
+                range: UffdioRange {
+                    start: start as u64,
+                    len: size as u64,
+                },
+                mode: UFFDIO_REGISTER_MODE_MISSING,
+                ioctls: 0,
+            };
+            let registered = unsafe {
+                libc::ioctl(fd, UFFDIO_API, &mut api) == 0
+                    && libc::ioctl(fd, UFFDIO_REGISTER, &mut register) == 0
+            };
+            if !registered {
+                let error = io::Error::last_os_error();
+                unsafe {
+                    libc::close(fd);
+                }
+                return Err(error);
+            }
+
+            let events = Arc::new(Mutex::new(Vec::new()));
+            let stop = Arc::new(AtomicBool::new(false));
+            let handler = {
+                let events = Arc::clone(&events);
+                let stop = Arc::clone(&stop);
+                thread::spawn(move || handle_faults(fd, start, page_size, &events, &stop))
+            };
+
+            Ok(FaultTracer {
+                fd,
+                start,
+                size,
+                events,
+                stop,
+                handler: Some(handler),
+            })
+        }
+
+        // Every fault resolved since the last call. A faulting thread only
+        // continues once its fault is recorded, so right after a touch this
+        // is exactly what the touch faulted.
+        pub fn take_faults(&self) -> Vec<FaultEvent> {
+            std::mem::take(&mut *self.events.lock().unwrap())
+        }
+    }
+
+    impl Drop for FaultTracer {
+        fn drop(&mut self) {
+            self.stop.store(true, Ordering::Relaxed);
+            if let Some(handler) = self.handler.take() {
+                let _ = handler.join();
+            }
+            let mut range = UffdioRange {
+                start: self.start as u64,
+                len: self.size as u64,
+            };
+            unsafe {
+                libc::ioctl(self.fd, UFFDIO_UNREGISTER, &mut range);
+                libc::close(self.fd);
+            }
+        }
+    }
+
+    fn handle_faults(
+        fd: c_int,
+        start: usize,
+        page_size: usize,
+        events: &Mutex<Vec<FaultEvent>>,
+        stop: &AtomicBool,
+    ) {
+        let zero_page = vec![0u8; page_size];
+        let trace_start = high_resolution_clock();
+        let mut poll_fd = libc::pollfd {
+            fd,
+            events: libc::POLLIN,
+            revents: 0,
+        };
+
+        while !stop.load(Ordering::Relaxed) {
+            if unsafe { libc::poll(&mut poll_fd, 1, POLL_MILLISECONDS) } <= 0 {
+                continue;
+            }
+
+            let mut message: UffdMsg = unsafe { std::mem::zeroed() };
+            let size = std::mem::size_of::<UffdMsg>();
+            let read = unsafe { libc::read(fd, &mut message as *mut _ as *mut c_void, size) };
+            if read != size as isize || message.event != UFFD_EVENT_PAGEFAULT {
+                continue;
+            }
+
+            let address = message.address as usize & !(page_size - 1);
+            events.lock().unwrap().push(FaultEvent {
+                page: (address - start) / page_size,
+                address: message.address as usize,
+                write: message.flags & UFFD_PAGEFAULT_FLAG_WRITE != 0,
+                time: high_resolution_clock() - trace_start,
+            });
+
+            // EEXIST means another thread faulted the same page and won, the
+            // page is there either way
+            let mut copy = UffdioCopy {
+                dst: address as u64,
+                src: zero_page.as_ptr() as u64,
+                len: page_size as u64,
+                mode: 0,
+                copy: 0,
+            };
+            unsafe {
+                libc::ioctl(fd, UFFDIO_COPY, &mut copy);
+            }
+        }
+    }
+}
+
+// touch, touched page, how many pages it faulted, which ones, and when
+// (microseconds into the trace) the first of them was resolved
+pub fn save_trace(file_name: &str, traces: &[TouchTrace]) -> io::Result<()> {
+    let mut file = File::create(file_name)?;
+    writeln!(
+        file,
+        "Touch, Page, Fault Count, Faulted Pages, Microseconds"
+    )?;
+    for (index, trace) in traces.iter().enumerate() {
+        let pages: Vec<String> = trace
+            .faults
+            .iter()
+            .map(|fault| fault.page.to_string())
+            .collect();
+        let microseconds = match trace.faults.first() {
+            Some(fault) => format!("{:.3}", fault.time.as_secs_f64() * 1e6),
+            None => "-".to_string(),
+        };
+        writeln!(
+            file,
+            "{}, {}, {}, {}, {}",
+            index + 1,
+            trace.page,
+            trace.faults.len(),
+            pages.join(" "),
+            microseconds
+        )?;
+    }
+    Ok(())
+}
+
+// How the touches spread over fault counts, and how far the faulting pages
+// strayed from the touched one.
+pub fn print_trace_summary(traces: &[TouchTrace]) {
+    let faults: Vec<&FaultEvent> = traces.iter().flat_map(|t| &t.faults).collect();
+    println!("\n===== Fault Trace =====");
+    println!("Touches: {}", traces.len());
+    println!("Faults: {}", faults.len());
+
+    let mut by_count: Vec<(usize, usize)> = Vec::new();
+    for trace in traces {
+        match by_count
+            .iter_mut()
+            .find(|(count, _)| *count == trace.faults.len())
+        {
+            Some(group) => group.1 += 1,
+            None => by_count.push((trace.faults.len(), 1)),
+        }
+    }
+    by_count.sort();
+    for (count, touches) in &by_count {
+        println!("{} touches took {} faults", touches, count);
+    }
+
+    let writes = faults.iter().filter(|fault| fault.write).count();
+    println!("{} of the faults were writes", writes);
+
+    // pages the handler saw faulting that aren't the page being touched
+    let elsewhere: usize = traces
+        .iter()
+        .map(|trace| trace.faults.iter().filter(|f| f.page != trace.page).count())
+        .sum();
+    println!(
+        "{} faults landed on another page than the touched one",
+        elsewhere
+    );
+
+    if faults.len() > 1 {
+        let first = faults.first().unwrap().time;
+        let last = faults.last().unwrap().time;
+        println!(
+            "Average time between faults: {:.3} us",
+            (last - first).as_secs_f64() * 1e6 / (faults.len() - 1) as f64
+        );
+    }
+}
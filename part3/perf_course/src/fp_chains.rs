@@ -0,0 +1,127 @@
+use std::ptr;
+
+use crate::jit::{ChainOp, JitKernel, KernelShape};
+use crate::sweep::{ParameterGenerator, Sweep, SweepMetric};
+
+// loop iterations per timed run
+const ITERATIONS: u64 = 64 * 1024;
+// dependent ops per chain per loop iteration
+pub const CHAIN_LENGTH: usize = 16;
+// the ports count as saturated once the chains get this close to the best
+// throughput
+const SATURATION: f64 = 0.95;
+
+pub const FLOAT_OPS: [ChainOp; 5] = [
+    ChainOp::Fma,
+    ChainOp::FMul,
+    ChainOp::FAdd,
+    ChainOp::FDiv,
+    ChainOp::FSqrt,
+];
+
+pub struct ChainResults {
+    pub op: ChainOp,
+    // (chains, cycles per loop iteration), one point per chain count the
+    // machine has registers for
+    pub points: Vec<(u64, f64)>,
+}
+
+impl ChainResults {
+    pub fn ops_per_cycle(chains: u64, cycles: f64) -> f64 {
+        (chains * CHAIN_LENGTH as u64) as f64 / cycles
+    }
+
+    // cycles per op of a single chain, every op waits for the one before it
+    pub fn latency(&self) -> f64 {
+        self.points
+            .first()
+            .map(|(_, cycles)| cycles / CHAIN_LENGTH as f64)
+            .unwrap_or(0.0)
+    }
+
+    // best ops per cycle over all the chain counts
+    pub fn throughput(&self) -> f64 {
+        self.points
+            .iter()
+            .map(|(chains, cycles)| ChainResults::ops_per_cycle(*chains, *cycles))
+            .fold(0.0, f64::max)
+    }
+
+    // cycles between two independent ops at full throughput
+    pub fn reciprocal_throughput(&self) -> f64 {
+        1.0 / self.throughput()
+    }
+
+    // fewest chains that keep the ports busy
+    pub fn saturating_chains(&self) -> u64 {
+        let best = self.throughput();
+        self.points
+            .iter()
+            .find(|(chains, cycles)| {
+                ChainResults::ops_per_cycle(*chains, *cycles) >= best * SATURATION
+            })
+            .map(|(chains, _)| *chains)
+            .unwrap_or(0)
+    }
+}
+
+// Times `op` chains with 1 to `max_chains` independent accumulators, None when
+// the op can't be generated here (no FMA on the CPU...).
+pub fn run_chains(
+    op: ChainOp,
+    max_chains: u64,
+    seconds_to_try: u64,
+    frequency: f64,
+) -> Option<ChainResults> {
+    let shape = |chains: u64| KernelShape::DependencyChain {
+        op,
+        length: CHAIN_LENGTH,
+        chains: chains as usize,
+    };
+    let supported: Vec<u64> = (1..=max_chains)
+        .filter(|chains| shape(*chains).emit().is_some())
+        .collect();
+    if supported.is_empty() {
+        return None;
+    }
+
+    let sweep = Sweep {
+        name: format!("{:?}", op),
+        parameter_label: "Chains".to_string(),
+        generator: ParameterGenerator::List(supported),
+        metric: SweepMetric::Seconds,
+        seconds_to_try,
+    };
+
+    // the kernel is generated on the untimed warm-up call of every point
+    let mut built_for = None;
+    let mut kernel: Option<JitKernel> = None;
+    let results = sweep.run(|chains| {
+        if built_for != Some(chains) {
+            kernel = shape(chains).emit().and_then(|code| JitKernel::new(&code));
+            built_for = Some(chains);
+        }
+
+        // the float chains only use registers, data is never touched
+        if let Some(kernel) = &kernel {
+            unsafe {
+                (kernel.function())(ITERATIONS, ptr::null_mut());
+            }
+        }
+        ITERATIONS
+    });
+
+    Some(ChainResults {
+        op,
+        points: results
+            .points
+            .iter()
+            .map(|point| {
+                (
+                    point.parameter,
+                    point.seconds * frequency / ITERATIONS as f64,
+                )
+            })
+            .collect(),
+    })
+}
@@ -0,0 +1,520 @@
+use std::ptr;
+
+use libc::{
+    mmap, mprotect, munmap, sysconf, _SC_PAGESIZE, MAP_ANON, MAP_FAILED, MAP_PRIVATE, PROT_EXEC,
+    PROT_READ, PROT_WRITE,
+};
+
+// Same calling convention as the hand written kernels in asm/: a count and a
+// data pointer.
+pub type KernelFunction = unsafe extern "C" fn(count: u64, data: *mut u8);
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum ChainOp {
+    Add,
+    Mul,
+    // scalar double ops, every chain starts at 1.0 and stays there (x * 1.0,
+    // x + 0.0, x / 1.0, sqrt(x), x * 1.0 + 0.0) so no denormals slow them down
+    FAdd,
+    FMul,
+    FDiv,
+    FSqrt,
+    Fma,
+}
+
+impl ChainOp {
+    pub fn is_float(&self) -> bool {
+        !matches!(self, ChainOp::Add | ChainOp::Mul)
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum KernelShape {
+    // `nop_count` nops of `nop_width` bytes each per iteration, count = iterations.
+    // arm64 nops are always 4 bytes.
+    NopLoop {
+        nop_count: usize,
+        nop_width: usize,
+    },
+    // `unroll` loads of `width` bytes from data, data + width, ... per
+    // iteration, count = bytes, decremented by width * unroll per iteration
+    Load {
+        width: usize,
+        unroll: usize,
+    },
+    // same as Load, with stores
+    Store {
+        width: usize,
+        unroll: usize,
+    },
+    // `chains` independent chains of `length` dependent ops per iteration,
+    // count = iterations
+    DependencyChain {
+        op: ChainOp,
+        length: usize,
+        chains: usize,
+    },
+}
+
+impl KernelShape {
+    pub fn name(&self) -> String {
+        match self {
+            KernelShape::NopLoop {
+                nop_count,
+                nop_width,
+            } => {
+                format!("NOP {}x{}b", nop_count, nop_width)
+            }
+            KernelShape::Load { width, unroll } => format!("Load {}x{}", width, unroll),
+            KernelShape::Store { width, unroll } => format!("Store {}x{}", width, unroll),
+            KernelShape::DependencyChain { op, length, chains } => {
+                format!("{:?} Chain {}x{}", op, chains, length)
+            }
+        }
+    }
+
+    // how much `count` goes down per loop iteration
+    pub fn count_per_iteration(&self) -> u64 {
+        match self {
+            KernelShape::Load { width, unroll } | KernelShape::Store { width, unroll } => {
+                (width * unroll) as u64
+            }
+            _ => 1,
+        }
+    }
+
+    // Machine code for the shape on the architecture we run on, None when the
+    // shape can't be encoded here (too wide, too unrolled, missing AVX...).
+    pub fn emit(&self) -> Option<Vec<u8>> {
+        encoder::emit(self)
+    }
+}
+
+// A page aligned RX mapping holding generated code.
+pub struct JitKernel {
+    addr: *mut u8,
+    size: usize,
+    entry: usize,
+}
+
+impl JitKernel {
+    pub fn new(code: &[u8]) -> Option<Self> {
+        JitKernel::at_offset(code, 0)
+    }
+
+    // Places the code `offset` bytes past the start of the mapping, so the
+    // entry point can land anywhere within a page.
+    pub fn at_offset(code: &[u8], offset: usize) -> Option<Self> {
+        let page_size = unsafe { sysconf(_SC_PAGESIZE) } as usize;
+        let size = (offset + code.len()).next_multiple_of(page_size);
+
+        let addr = unsafe {
+            mmap(
+                ptr::null_mut(),
+                size,
+                PROT_READ | PROT_WRITE,
+                MAP_PRIVATE | MAP_ANON,
+                -1,
+                0,
+            )
+        };
+        if addr == MAP_FAILED {
+            return None;
+        }
+
+        unsafe {
+            ptr::copy_nonoverlapping(code.as_ptr(), (addr as *mut u8).add(offset), code.len());
+            if mprotect(addr, size, PROT_READ | PROT_EXEC) != 0 {
+                munmap(addr, size);
+                return None;
+            }
+            flush_instruction_cache(addr as *mut u8, size);
+        }
+
+        Some(JitKernel {
+            addr: addr as *mut u8,
+            size,
+            entry: offset,
+        })
+    }
+
+    pub fn entry_address(&self) -> usize {
+        self.addr as usize + self.entry
+    }
+
+    pub fn function(&self) -> KernelFunction {
+        unsafe { std::mem::transmute::<*mut u8, KernelFunction>(self.addr.add(self.entry)) }
+    }
+}
+
+impl Drop for JitKernel {
+    fn drop(&mut self) {
+        unsafe {
+            munmap(self.addr as *mut libc::c_void, self.size);
+        }
+    }
+}
+
+#[cfg(all(target_arch = "aarch64", target_os = "macos"))]
+unsafe fn flush_instruction_cache(addr: *mut u8, size: usize) {
+    extern "C" {
+        fn sys_icache_invalidate(start: *mut libc::c_void, len: libc::size_t);
+    }
+    sys_icache_invalidate(addr as *mut libc::c_void, size);
+}
+
+#[cfg(all(target_arch = "aarch64", not(target_os = "macos")))]
+unsafe fn flush_instruction_cache(addr: *mut u8, size: usize) {
+    extern "C" {
+        fn __clear_cache(start: *mut libc::c_char, end: *mut libc::c_char);
+    }
+    __clear_cache(
+        addr as *mut libc::c_char,
+        addr.add(size) as *mut libc::c_char,
+    );
+}
+
+// x86 keeps the instruction cache coherent by itself
+#[cfg(not(target_arch = "aarch64"))]
+unsafe fn flush_instruction_cache(_addr: *mut u8, _size: usize) {}
+
+#[cfg(target_arch = "x86_64")]
+mod encoder {
+    use super::{ChainOp, KernelShape};
+
+    // recommended multi byte nops, indexed by width - 1
+    const NOPS: [&[u8]; 9] = [
+        &[0x90],
+        &[0x66, 0x90],
+        &[0x0F, 0x1F, 0x00],
+        &[0x0F, 0x1F, 0x40, 0x00],
+        &[0x0F, 0x1F, 0x44, 0x00, 0x00],
+        &[0x66, 0x0F, 0x1F, 0x44, 0x00, 0x00],
+        &[0x0F, 0x1F, 0x80, 0x00, 0x00, 0x00, 0x00],
+        &[0x0F, 0x1F, 0x84, 0x00, 0x00, 0x00, 0x00, 0x00],
+        &[0x66, 0x0F, 0x1F, 0x84, 0x00, 0x00, 0x00, 0x00, 0x00],
+    ];
+
+    // rax, rcx, rdx, r8 - r11: the caller saved registers not holding arguments
+    const CHAIN_REGISTERS: [u8; 7] = [0, 1, 2, 8, 9, 10, 11];
+    // xmm0 - xmm13, xmm14 holds 1.0 and xmm15 0.0
+    const FLOAT_CHAIN_REGISTERS: [u8; 14] = [0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13];
+    const ONE: u8 = 14;
+    const ZERO: u8 = 15;
+
+    // three byte VEX prefix, opcode and a register to register modrm, L = 0
+    #[allow(clippy::too_many_arguments)]
+    fn vex(code: &mut Vec<u8>, map: u8, pp: u8, w: u8, reg: u8, vvvv: u8, rm: u8, opcode: u8) {
+        let r = (!reg >> 3) & 1;
+        let b = (!rm >> 3) & 1;
+        code.extend_from_slice(&[
+            0xC4,
+            r << 7 | 1 << 6 | b << 5 | map,
+            w << 7 | (!vvvv & 0xF) << 3 | pp,
+            opcode,
+            0xC0 | (reg & 7) << 3 | (rm & 7),
+        ]);
+    }
+
+    // xmm14 = 1.0, xmm15 = 0.0 and every chain register = 1.0
+    fn float_prologue(code: &mut Vec<u8>, chains: usize) {
+        // mov rax, 1.0 / vmovq xmm14, rax / vxorpd xmm15, xmm15, xmm15
+        code.extend_from_slice(&[0x48, 0xB8]);
+        code.extend_from_slice(&1.0f64.to_bits().to_le_bytes());
+        vex(code, 1, 1, 1, ONE, 0, 0, 0x6E);
+        vex(code, 1, 1, 0, ZERO, ZERO, ZERO, 0x57);
+        for register in &FLOAT_CHAIN_REGISTERS[..chains] {
+            // vmovapd xmmr, xmm14
+            vex(code, 1, 1, 0, *register, 0, ONE, 0x28);
+        }
+    }
+
+    fn float_op(code: &mut Vec<u8>, op: ChainOp, r: u8) {
+        match op {
+            // vaddsd / vmulsd / vdivsd xmmr, xmmr, xmm15 / xmm14 / xmm14
+            ChainOp::FAdd => vex(code, 1, 3, 0, r, r, ZERO, 0x58),
+            ChainOp::FMul => vex(code, 1, 3, 0, r, r, ONE, 0x59),
+            ChainOp::FDiv => vex(code, 1, 3, 0, r, r, ONE, 0x5E),
+            // vsqrtsd xmmr, xmmr, xmmr
+            ChainOp::FSqrt => vex(code, 1, 3, 0, r, r, r, 0x51),
+            // vfmadd132sd xmmr, xmm15, xmm14: xmmr = xmmr * xmm14 + xmm15
+            ChainOp::Fma => vex(code, 2, 1, 1, r, ZERO, ONE, 0x99),
+            ChainOp::Add | ChainOp::Mul => unreachable!(),
+        }
+    }
+
+    fn float_supported(op: ChainOp) -> bool {
+        match op {
+            ChainOp::Fma => std::is_x86_feature_detected!("fma"),
+            _ => std::is_x86_feature_detected!("avx"),
+        }
+    }
+
+    // opcode bytes of a load or store between rax/xmm0/ymm0/zmm0 and
+    // [rsi + disp32], without the modrm byte
+    fn memory_opcode(width: usize, store: bool) -> Option<&'static [u8]> {
+        let opcode: &'static [u8] = match (width, store) {
+            (1, false) => &[0x0F, 0xB6],                    // movzx eax, byte
+            (2, false) => &[0x0F, 0xB7],                    // movzx eax, word
+            (4, false) => &[0x8B],                          // mov eax
+            (8, false) => &[0x48, 0x8B],                    // mov rax
+            (16, false) => &[0xF3, 0x0F, 0x6F],             // movdqu xmm0
+            (32, false) => &[0xC5, 0xFE, 0x6F],             // vmovdqu ymm0
+            (64, false) => &[0x62, 0xF1, 0xFE, 0x48, 0x6F], // vmovdqu64 zmm0
+            (1, true) => &[0x88],
+            (2, true) => &[0x66, 0x89],
+            (4, true) => &[0x89],
+            (8, true) => &[0x48, 0x89],
+            (16, true) => &[0xF3, 0x0F, 0x7F],
+            (32, true) => &[0xC5, 0xFE, 0x7F],
+            (64, true) => &[0x62, 0xF1, 0xFE, 0x48, 0x7F],
+            _ => return None,
+        };
+
+        let supported = match width {
+            32 => std::is_x86_feature_detected!("avx"),
+            64 => std::is_x86_feature_detected!("avx512f"),
+            _ => true,
+        };
+        if supported {
+            Some(opcode)
+        } else {
+            None
+        }
+    }
+
+    // sub rdi, step / ja loop_start / [vzeroupper] / ret
+    fn close_loop(code: &mut Vec<u8>, loop_start: usize, step: u32, vector: bool) {
+        code.extend_from_slice(&[0x48, 0x81, 0xEF]);
+        code.extend_from_slice(&step.to_le_bytes());
+
+        let rel = loop_start as i64 - (code.len() as i64 + 6);
+        code.extend_from_slice(&[0x0F, 0x87]);
+        code.extend_from_slice(&(rel as i32).to_le_bytes());
+
+        if vector {
+            code.extend_from_slice(&[0xC5, 0xF8, 0x77]);
+        }
+        code.push(0xC3);
+    }
+
+    pub fn emit(shape: &KernelShape) -> Option<Vec<u8>> {
+        let mut code = Vec::new();
+        let loop_start = 0;
+
+        match *shape {
+            KernelShape::NopLoop {
+                nop_count,
+                nop_width,
+            } => {
+                let nop = NOPS.get(nop_width.checked_sub(1)?)?;
+                for _ in 0..nop_count {
+                    code.extend_from_slice(nop);
+                }
+                close_loop(&mut code, loop_start, 1, false);
+            }
+            KernelShape::Load { width, unroll } | KernelShape::Store { width, unroll } => {
+                let store = matches!(shape, KernelShape::Store { .. });
+                let opcode = memory_opcode(width, store)?;
+                for i in 0..unroll {
+                    code.extend_from_slice(opcode);
+                    code.push(0x86); // [rsi + disp32], reg 0
+                    code.extend_from_slice(&((i * width) as u32).to_le_bytes());
+                }
+                close_loop(&mut code, loop_start, (width * unroll) as u32, width >= 32);
+            }
+            KernelShape::DependencyChain { op, length, chains } if op.is_float() => {
+                if chains == 0 || chains > FLOAT_CHAIN_REGISTERS.len() || !float_supported(op) {
+                    return None;
+                }
+                float_prologue(&mut code, chains);
+                let loop_start = code.len();
+                for _ in 0..length {
+                    for register in &FLOAT_CHAIN_REGISTERS[..chains] {
+                        float_op(&mut code, op, *register);
+                    }
+                }
+                close_loop(&mut code, loop_start, 1, true);
+            }
+            KernelShape::DependencyChain { op, length, chains } => {
+                if chains == 0 || chains > CHAIN_REGISTERS.len() {
+                    return None;
+                }
+                for _ in 0..length {
+                    for register in &CHAIN_REGISTERS[..chains] {
+                        let low = register & 7;
+                        let high = register >> 3;
+                        match op {
+                            // add r, 1
+                            ChainOp::Add => {
+                                code.extend_from_slice(&[0x48 | high, 0x83, 0xC0 | low, 0x01])
+                            }
+                            // imul r, r
+                            ChainOp::Mul => code.extend_from_slice(&[
+                                0x48 | high << 2 | high,
+                                0x0F,
+                                0xAF,
+                                0xC0 | low << 3 | low,
+                            ]),
+                            _ => unreachable!(),
+                        }
+                    }
+                }
+                close_loop(&mut code, loop_start, 1, false);
+            }
+        }
+
+        Some(code)
+    }
+}
+
+#[cfg(target_arch = "aarch64")]
+mod encoder {
+    use super::{ChainOp, KernelShape};
+
+    const NOP: u32 = 0xD503201F;
+    const RET: u32 = 0xD65F03C0;
+    // x4 - x15, caller saved and not used by the loop
+    const CHAIN_REGISTERS: [u32; 12] = [4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15];
+    // d0 - d7 and d16 - d29, d8 - d15 are callee saved. d30 holds 1.0 and d31 0.0
+    const FLOAT_CHAIN_REGISTERS: [u32; 22] = [
+        0, 1, 2, 3, 4, 5, 6, 7, 16, 17, 18, 19, 20, 21, 22, 23, 24, 25, 26, 27, 28, 29,
+    ];
+    const ONE: u32 = 30;
+    const ZERO: u32 = 31;
+    // fmov dr, #1.0
+    const FMOV_ONE: u32 = 0x1E6E1000;
+
+    fn push(code: &mut Vec<u8>, instruction: u32) {
+        code.extend_from_slice(&instruction.to_le_bytes());
+    }
+
+    // ldr/str of `width` bytes between w2/x2/q0 (q0, q1 for 32) and [x1, #offset]
+    fn memory_instruction(width: usize, store: bool, offset: usize) -> Option<u32> {
+        let scaled = (offset / width.min(16)) as u32;
+        let (base, limit) = match (width, store) {
+            (1, false) => (0x39400002, 4095),  // ldrb w2
+            (2, false) => (0x79400002, 4095),  // ldrh w2
+            (4, false) => (0xB9400002, 4095),  // ldr w2
+            (8, false) => (0xF9400002, 4095),  // ldr x2
+            (16, false) => (0x3DC00000, 4095), // ldr q0
+            (32, false) => (0xAD400400, 63),   // ldp q0, q1
+            (1, true) => (0x39000002, 4095),
+            (2, true) => (0x79000002, 4095),
+            (4, true) => (0xB9000002, 4095),
+            (8, true) => (0xF9000002, 4095),
+            (16, true) => (0x3D800000, 4095),
+            (32, true) => (0xAD000400, 63),
+            _ => return None,
+        };
+        if scaled > limit {
+            return None;
+        }
+
+        // Rn = x1, the pair forms keep their 7 bit offset at bit 15
+        let shift = if width == 32 { 15 } else { 10 };
+        Some(base | scaled << shift | 1 << 5)
+    }
+
+    // subs x0, x0, x3 / b.hi loop_start / ret, with x3 = step set up front
+    fn close_loop(code: &mut Vec<u8>, loop_start: usize) {
+        push(code, 0xEB030000);
+        let rel = (loop_start as i64 - code.len() as i64) / 4;
+        push(code, 0x54000008 | ((rel as u32) & 0x7FFFF) << 5);
+        push(code, RET);
+    }
+
+    // movz x3, #step
+    fn set_step(code: &mut Vec<u8>, step: usize) -> Option<()> {
+        if step > 0xFFFF {
+            return None;
+        }
+        push(code, 0xD2800003 | (step as u32) << 5);
+        Some(())
+    }
+
+    fn float_op(op: ChainOp, r: u32) -> u32 {
+        match op {
+            // fadd dr, dr, d31 / fmul dr, dr, d30 / fdiv dr, dr, d30
+            ChainOp::FAdd => 0x1E602800 | ZERO << 16 | r << 5 | r,
+            ChainOp::FMul => 0x1E600800 | ONE << 16 | r << 5 | r,
+            ChainOp::FDiv => 0x1E601800 | ONE << 16 | r << 5 | r,
+            // fsqrt dr, dr
+            ChainOp::FSqrt => 0x1E61C000 | r << 5 | r,
+            // fmadd dr, dr, d30, d31: dr = dr * d30 + d31
+            ChainOp::Fma => 0x1F400000 | ONE << 16 | ZERO << 10 | r << 5 | r,
+            ChainOp::Add | ChainOp::Mul => unreachable!(),
+        }
+    }
+
+    pub fn emit(shape: &KernelShape) -> Option<Vec<u8>> {
+        let mut code = Vec::new();
+
+        match *shape {
+            KernelShape::NopLoop {
+                nop_count,
+                nop_width,
+            } => {
+                if nop_width != 4 {
+                    return None;
+                }
+                set_step(&mut code, 1)?;
+                let loop_start = code.len();
+                for _ in 0..nop_count {
+                    push(&mut code, NOP);
+                }
+                close_loop(&mut code, loop_start);
+            }
+            KernelShape::Load { width, unroll } | KernelShape::Store { width, unroll } => {
+                let store = matches!(shape, KernelShape::Store { .. });
+                set_step(&mut code, width * unroll)?;
+                let loop_start = code.len();
+                for i in 0..unroll {
+                    push(&mut code, memory_instruction(width, store, i * width)?);
+                }
+                close_loop(&mut code, loop_start);
+            }
+            KernelShape::DependencyChain { op, length, chains } if op.is_float() => {
+                if chains == 0 || chains > FLOAT_CHAIN_REGISTERS.len() {
+                    return None;
+                }
+                // movi d31, #0 and every register used = 1.0
+                push(&mut code, 0x2F00E400 | ZERO);
+                push(&mut code, FMOV_ONE | ONE);
+                for register in &FLOAT_CHAIN_REGISTERS[..chains] {
+                    push(&mut code, FMOV_ONE | register);
+                }
+                set_step(&mut code, 1)?;
+                let loop_start = code.len();
+                for _ in 0..length {
+                    for register in &FLOAT_CHAIN_REGISTERS[..chains] {
+                        push(&mut code, float_op(op, *register));
+                    }
+                }
+                close_loop(&mut code, loop_start);
+            }
+            KernelShape::DependencyChain { op, length, chains } => {
+                if chains == 0 || chains > CHAIN_REGISTERS.len() {
+                    return None;
+                }
+                set_step(&mut code, 1)?;
+                let loop_start = code.len();
+                for _ in 0..length {
+                    for register in &CHAIN_REGISTERS[..chains] {
+                        let r = *register;
+                        match op {
+                            // add xr, xr, #1
+                            ChainOp::Add => push(&mut code, 0x91000400 | r << 5 | r),
+                            // mul xr, xr, xr
+                            ChainOp::Mul => push(&mut code, 0x9B007C00 | r << 16 | r << 5 | r),
+                            _ => unreachable!(),
+                        }
+                    }
+                }
+                close_loop(&mut code, loop_start);
+            }
+        }
+
+        Some(code)
+    }
+}
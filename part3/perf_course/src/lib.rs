@@ -1,3 +1,13 @@
+pub mod cache_hierarchy;
+pub mod fault_pattern;
+pub mod fault_tracer;
+pub mod fp_chains;
+pub mod jit;
 pub mod naive_profiler;
 pub mod perf_metrics;
-pub mod repetition_tester;
\ No newline at end of file
+pub mod pointer_chase;
+pub mod repetition_tester;
+pub mod report;
+pub mod residency;
+pub mod svg_chart;
+pub mod sweep;
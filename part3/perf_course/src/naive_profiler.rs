@@ -70,21 +70,21 @@ impl NaiveProfiler {
     }
 }
 
-pub static mut NAIVE_PROFILER: Lazy<NaiveProfiler> = Lazy::new(|| NaiveProfiler::new());
+pub static mut NAIVE_PROFILER: Lazy<NaiveProfiler> = Lazy::new(NaiveProfiler::new);
 
 pub fn start_profiling() {
-    let profiler = unsafe { &mut NAIVE_PROFILER };
+    let profiler = unsafe { &mut *std::ptr::addr_of_mut!(NAIVE_PROFILER) };
     profiler.start_profiling();
 }
 
 pub fn stop_profiling() {
-    let profiler = unsafe { &mut NAIVE_PROFILER };
+    let profiler = unsafe { &mut *std::ptr::addr_of_mut!(NAIVE_PROFILER) };
     profiler.stop_profiling();
 }
 
 #[cfg(feature = "profiler")]
 pub fn start_span(label: &str) -> usize {
-    let profiler = unsafe { &mut NAIVE_PROFILER };
+    let profiler = unsafe { &mut *std::ptr::addr_of_mut!(NAIVE_PROFILER) };
     let idx = profiler.time_points.iter().position(|p| p.label == label);
 
     let index = match idx {
@@ -120,7 +120,7 @@ pub fn start_span(label: &str) -> usize {
 
 #[cfg(feature = "profiler")]
 pub fn stop_span(index: usize, bytes_processed: u64) {
-    let profiler = unsafe { &mut NAIVE_PROFILER };
+    let profiler = unsafe { &mut *std::ptr::addr_of_mut!(NAIVE_PROFILER) };
     if let Some(time_point) = profiler.time_points.get_mut(index) {
         let elapsed = time_point.mark_span(bytes_processed);
         // let label = &time_point.label.clone();
@@ -152,10 +152,10 @@ pub fn start_span(_: &str) -> usize {
 }
 
 #[cfg(not(feature = "profiler"))]
-pub fn stop_span(_: usize, bytes_processed: u64) {}
+pub fn stop_span(_: usize, _bytes_processed: u64) {}
 
 pub fn report() {
-    let profiler = unsafe { &mut NAIVE_PROFILER };
+    let profiler = unsafe { &mut *std::ptr::addr_of_mut!(NAIVE_PROFILER) };
     let time_info = high_resolution_info();
     let total_time = Duration::from_nanos(
         (profiler.elapsed_time.unwrap() * time_info.numer as u64) / time_info.denom as u64,
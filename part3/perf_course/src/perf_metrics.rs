@@ -1,13 +1,25 @@
 extern crate libc;
+#[cfg(target_os = "macos")]
 extern crate mach;
 
+#[cfg(target_os = "macos")]
 use std::mem;
 use std::time::Duration;
 
-use libc::{c_int, c_void, pid_t};
+use libc::pid_t;
+#[cfg(target_os = "macos")]
+use libc::{c_int, c_void};
+#[cfg(target_os = "macos")]
 use mach::mach_time::{mach_absolute_time, mach_timebase_info};
 
-#[repr(C)]
+// ticks * numer / denom = nanoseconds
+#[derive(Debug, Copy, Clone)]
+pub struct TimebaseInfo {
+    pub numer: u32,
+    pub denom: u32,
+}
+
+#[cfg(target_os = "macos")]
 #[repr(C)]
 struct ProcTaskInfo {
     pti_virtual_size: u64,      // virtual memory size (bytes)
@@ -30,6 +42,7 @@ struct ProcTaskInfo {
     pti_priority: i32,          // task priority
 }
 
+#[cfg(target_os = "macos")]
 extern "C" {
     fn proc_pidinfo(
         pid: pid_t,
@@ -40,6 +53,7 @@ extern "C" {
     ) -> c_int;
 }
 
+#[cfg(target_os = "macos")]
 pub fn get_page_faults(pid: pid_t) -> i32 {
     let mut task_info = ProcTaskInfo {
         pti_virtual_size: 0,
@@ -84,28 +98,100 @@ pub fn get_page_faults(pid: pid_t) -> i32 {
     }
 }
 
-pub fn high_resolution_info() -> mach_timebase_info {
+// getrusage only reports on the calling process, the pid is kept for the
+// macOS signature
+#[cfg(not(target_os = "macos"))]
+pub fn get_page_faults(_pid: pid_t) -> i32 {
+    let mut usage: libc::rusage = unsafe { std::mem::zeroed() };
+    if unsafe { libc::getrusage(libc::RUSAGE_SELF, &mut usage) } == 0 {
+        (usage.ru_minflt + usage.ru_majflt) as i32
+    } else {
+        eprintln!("Failed to get process info");
+        -1
+    }
+}
+
+#[cfg(target_os = "macos")]
+pub fn high_resolution_info() -> TimebaseInfo {
     unsafe {
         let mut info = mach_timebase_info { numer: 0, denom: 0 };
         mach_timebase_info(&mut info);
-        info
+        TimebaseInfo {
+            numer: info.numer,
+            denom: info.denom,
+        }
     }
 }
 
+#[cfg(target_os = "macos")]
 pub fn high_resolution_time() -> u64 {
     unsafe { mach_absolute_time() }
 }
 
+// clock_gettime already counts in nanoseconds
+#[cfg(not(target_os = "macos"))]
+pub fn high_resolution_info() -> TimebaseInfo {
+    TimebaseInfo { numer: 1, denom: 1 }
+}
+
+#[cfg(not(target_os = "macos"))]
+pub fn high_resolution_time() -> u64 {
+    let mut time = libc::timespec {
+        tv_sec: 0,
+        tv_nsec: 0,
+    };
+    unsafe {
+        libc::clock_gettime(libc::CLOCK_MONOTONIC_RAW, &mut time);
+    }
+    time.tv_sec as u64 * 1_000_000_000 + time.tv_nsec as u64
+}
+
 pub fn high_resolution_clock() -> Duration {
+    let info = high_resolution_info();
+    let nanos = high_resolution_time() * info.numer as u64 / info.denom as u64;
+    Duration::from_nanos(nanos)
+}
+
+// One decrement + branch per iteration, the decrement chain makes it take
+// exactly one cycle per iteration on the cores we test on.
+#[inline(never)]
+fn dependent_decrement_loop(count: u64) {
     unsafe {
-        let time = mach_absolute_time();
-        let mut info = mach_timebase_info { numer: 0, denom: 0 };
-        mach_timebase_info(&mut info);
-        let nanos = time * info.numer as u64 / info.denom as u64;
-        Duration::from_nanos(nanos)
+        #[cfg(target_arch = "aarch64")]
+        std::arch::asm!(
+            "2:",
+            "subs {count}, {count}, #1",
+            "b.ne 2b",
+            count = inout(reg) count => _,
+            options(nomem, nostack)
+        );
+
+        #[cfg(target_arch = "x86_64")]
+        std::arch::asm!(
+            "2:",
+            "dec {count}",
+            "jnz 2b",
+            count = inout(reg) count => _,
+            options(nomem, nostack)
+        );
     }
 }
 
+// Estimates the core clock in Hz by timing the decrement loop, keeping the
+// fastest of a few tries so a late frequency ramp up does not skew it.
+pub fn estimate_cpu_frequency() -> f64 {
+    let iterations: u64 = 200_000_000;
+    let mut best = 0.0_f64;
+
+    for _ in 0..5 {
+        let start = high_resolution_clock();
+        dependent_decrement_loop(iterations);
+        let elapsed = high_resolution_clock() - start;
+        best = best.max(iterations as f64 / elapsed.as_secs_f64());
+    }
+
+    best
+}
 
 #[derive(Debug)]
 pub struct VirtualAddress {
@@ -128,22 +214,16 @@ impl VirtualAddress {
     }
 
     pub fn print(&self) {
-        println!("{} | {} | {} | {} | {}",
-                 self.l1_index,
-                 self.l2_index,
-                 self.l3_index,
-                 self.l4_index,
-                 self.offset
+        println!(
+            "{} | {} | {} | {} | {}",
+            self.l1_index, self.l2_index, self.l3_index, self.l4_index, self.offset
         );
     }
 
     pub fn format(&self) -> String {
-        format!("{} | {} | {} | {} | {}",
-                 self.l1_index,
-                 self.l2_index,
-                 self.l3_index,
-                 self.l4_index,
-                 self.offset
+        format!(
+            "{} | {} | {} | {} | {}",
+            self.l1_index, self.l2_index, self.l3_index, self.l4_index, self.offset
         )
     }
 }
@@ -0,0 +1,174 @@
+use std::ptr;
+
+use libc::{mmap, munmap, MAP_ANON, MAP_FAILED, MAP_PRIVATE, PROT_READ, PROT_WRITE};
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::{Rng, SeedableRng};
+
+pub const NODE_SIZE: usize = 64;
+// transparent huge pages only back 2MB aligned ranges
+const HUGE_PAGE_SIZE: usize = 2 * 1024 * 1024;
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum ChaseOrder {
+    // every node in address order, the prefetchers see right through it
+    Sequential,
+    // one random cycle over all the nodes, most loads also miss the TLB
+    Random,
+    // pages in random order, and all the nodes of a page in random order before
+    // moving to the next page, so TLB misses are paid once per page
+    PageLocal { page_size: usize },
+    // a single node per page, pages in random order. The node sits at a random
+    // line of its page so the lines spread over the cache sets instead of
+    // piling up in one.
+    OnePerPage { page_size: usize },
+}
+
+impl ChaseOrder {
+    pub fn name(&self) -> String {
+        match self {
+            ChaseOrder::Sequential => "Sequential".to_string(),
+            ChaseOrder::Random => "Random".to_string(),
+            ChaseOrder::PageLocal { page_size } => format!("Page Local ({}b pages)", page_size),
+            ChaseOrder::OnePerPage { page_size } => format!("One Per Page ({}b pages)", page_size),
+        }
+    }
+}
+
+// An mmap'd buffer of cache line sized nodes, each one starting with a pointer
+// to the next node of the walk.
+pub struct ChaseBuffer {
+    addr: *mut u8,
+    size: usize,
+    map_addr: *mut libc::c_void,
+    map_size: usize,
+    node_count: usize,
+}
+
+impl ChaseBuffer {
+    pub fn new(size: usize, huge_pages: bool) -> Option<Self> {
+        let map_size = if huge_pages {
+            size + HUGE_PAGE_SIZE
+        } else {
+            size
+        };
+        let map_addr = unsafe {
+            mmap(
+                ptr::null_mut(),
+                map_size,
+                PROT_READ | PROT_WRITE,
+                MAP_PRIVATE | MAP_ANON,
+                -1,
+                0,
+            )
+        };
+
+        if map_addr == MAP_FAILED {
+            return None;
+        }
+
+        let addr = if huge_pages {
+            let aligned = (map_addr as usize).next_multiple_of(HUGE_PAGE_SIZE);
+            aligned as *mut libc::c_void
+        } else {
+            map_addr
+        };
+
+        #[cfg(target_os = "linux")]
+        if huge_pages {
+            unsafe {
+                libc::madvise(addr, size, libc::MADV_HUGEPAGE);
+            }
+        }
+        #[cfg(not(target_os = "linux"))]
+        if huge_pages {
+            eprintln!("[ChaseBuffer] Huge pages are only requested on Linux");
+        }
+
+        Some(ChaseBuffer {
+            addr: addr as *mut u8,
+            size,
+            map_addr,
+            map_size,
+            node_count: 0,
+        })
+    }
+
+    pub fn node_count(&self) -> usize {
+        self.node_count
+    }
+
+    // Links the first `working_set` bytes into a single cycle in the given
+    // order and returns the first node of the walk.
+    pub fn build(&mut self, working_set: usize, order: ChaseOrder, seed: u64) -> *const usize {
+        let node_count = (working_set.min(self.size) / NODE_SIZE).max(1);
+        let mut rng = StdRng::seed_from_u64(seed);
+
+        let visit_order: Vec<usize> = match order {
+            ChaseOrder::Sequential => (0..node_count).collect(),
+            ChaseOrder::Random => {
+                let mut nodes: Vec<usize> = (0..node_count).collect();
+                nodes.shuffle(&mut rng);
+                nodes
+            }
+            ChaseOrder::PageLocal { page_size } => {
+                let nodes_per_page = (page_size / NODE_SIZE).max(1);
+                let page_count = node_count.div_ceil(nodes_per_page);
+                let mut pages: Vec<usize> = (0..page_count).collect();
+                pages.shuffle(&mut rng);
+
+                let mut nodes = Vec::with_capacity(node_count);
+                for page in pages {
+                    let first = page * nodes_per_page;
+                    let last = (first + nodes_per_page).min(node_count);
+                    let mut page_nodes: Vec<usize> = (first..last).collect();
+                    page_nodes.shuffle(&mut rng);
+                    nodes.extend(page_nodes);
+                }
+                nodes
+            }
+            ChaseOrder::OnePerPage { page_size } => {
+                let nodes_per_page = (page_size / NODE_SIZE).max(1);
+                let page_count = (node_count / nodes_per_page).max(1);
+                let mut pages: Vec<usize> = (0..page_count).collect();
+                pages.shuffle(&mut rng);
+                pages
+                    .iter()
+                    .map(|page| page * nodes_per_page + rng.gen_range(0..nodes_per_page))
+                    .collect()
+            }
+        };
+
+        let node_count = visit_order.len();
+        for i in 0..node_count {
+            let from = visit_order[i];
+            let to = visit_order[(i + 1) % node_count];
+            unsafe {
+                let node = self.addr.add(from * NODE_SIZE) as *mut usize;
+                *node = self.addr.add(to * NODE_SIZE) as usize;
+            }
+        }
+
+        self.node_count = node_count;
+        unsafe { self.addr.add(visit_order[0] * NODE_SIZE) as *const usize }
+    }
+}
+
+impl Drop for ChaseBuffer {
+    fn drop(&mut self) {
+        unsafe {
+            munmap(self.map_addr, self.map_size);
+        }
+    }
+}
+
+// Follows `steps` next pointers, every load depends on the one before it so
+// the time per step is the load latency.
+#[inline(never)]
+pub fn chase(start: *const usize, steps: u64) -> *const usize {
+    let mut node = start;
+    for _ in 0..steps {
+        node = unsafe { *node as *const usize };
+    }
+    std::hint::black_box(node)
+}
@@ -1,6 +1,8 @@
+#[allow(clippy::module_inception)]
 pub mod repetition_tester {
-    use crate::perf_metrics::{get_page_faults, high_resolution_info, high_resolution_time};
-    use mach::mach_time::mach_timebase_info;
+    use crate::perf_metrics::{
+        get_page_faults, high_resolution_info, high_resolution_time, TimebaseInfo,
+    };
     use std::io::Write;
     use std::time::Duration;
 
@@ -34,7 +36,7 @@ pub mod repetition_tester {
         state: State,
         start_time: u64,
         time_to_wait: u64,
-        cpu_timebase_info: mach_timebase_info,
+        cpu_timebase_info: TimebaseInfo,
 
         open_blocks_count: u32,
         close_blocks_count: u32,
@@ -47,6 +49,12 @@ pub mod repetition_tester {
         pid: i32,
     }
 
+    impl Default for RepetitionTester {
+        fn default() -> Self {
+            Self::new()
+        }
+    }
+
     impl RepetitionTester {
         pub fn new() -> Self {
             RepetitionTester {
@@ -174,13 +182,41 @@ pub mod repetition_tester {
             self.state == State::Testing
         }
 
+        pub fn results(&self) -> RepetitionTesterResults {
+            self.results
+        }
+
+        pub fn min_seconds(&self) -> f64 {
+            self.time_as_seconds(self.results.min[RepetitionTesterMetrics::Time as usize])
+                .as_secs_f64()
+        }
+
+        pub fn min_bytes(&self) -> u64 {
+            self.results.min[RepetitionTesterMetrics::ByteCount as usize]
+        }
+
+        pub fn min_page_faults(&self) -> u64 {
+            self.results.min[RepetitionTesterMetrics::PageFaults as usize]
+        }
+
+        // bandwidth of the fastest run in GB/s
+        pub fn min_bandwidth(&self) -> f64 {
+            let seconds = self.min_seconds();
+            if seconds == 0.0 {
+                return 0.0;
+            }
+
+            let gb_processed = self.min_bytes() as f64 / (1024.0 * 1024.0 * 1024.0);
+            gb_processed / seconds
+        }
+
         // helper functions
         fn time_from_seconds(&mut self, seconds: u64) -> u64 {
-            seconds * 1_000_000_00 * self.cpu_timebase_info.denom as u64
-                / self.cpu_timebase_info.denom as u64
+            seconds * 1_000_000_000 * self.cpu_timebase_info.denom as u64
+                / self.cpu_timebase_info.numer as u64
         }
 
-        fn time_as_seconds(&mut self, time: u64) -> Duration {
+        fn time_as_seconds(&self, time: u64) -> Duration {
             Duration::from_nanos(
                 time * self.cpu_timebase_info.numer as u64 / self.cpu_timebase_info.denom as u64,
             )
@@ -196,7 +232,7 @@ pub mod repetition_tester {
             let test_count = value[RepetitionTesterMetrics::TestCount as usize];
             let mut local_value = [0; RepetitionTesterMetrics::Count as usize];
             for i in 0..RepetitionTesterMetrics::Count as usize {
-                local_value[i] = value[i] / test_count as u64;
+                local_value[i] = value[i] / test_count;
             }
 
             let time = local_value[RepetitionTesterMetrics::Time as usize];
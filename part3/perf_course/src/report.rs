@@ -0,0 +1,130 @@
+use std::io::{self, Write};
+use std::sync::Mutex;
+
+use crate::repetition_tester::repetition_tester::RepetitionTester;
+
+// Fastest run of one test wave, kept so a driver can report every wave of a
+// benchmark in one format once it is done.
+#[derive(Debug, Clone)]
+pub struct WaveResult {
+    pub test: String,
+    pub min_seconds: f64,
+    pub min_bytes: u64,
+    pub gb_per_second: f64,
+    pub min_page_faults: u64,
+}
+
+static WAVES: Mutex<Vec<WaveResult>> = Mutex::new(Vec::new());
+
+// Called after a wave finished, `test` names it in the report.
+pub fn record_wave(test: &str, tester: &RepetitionTester) {
+    WAVES.lock().unwrap().push(WaveResult {
+        test: test.to_string(),
+        min_seconds: tester.min_seconds(),
+        min_bytes: tester.min_bytes(),
+        gb_per_second: tester.min_bandwidth(),
+        min_page_faults: tester.min_page_faults(),
+    });
+}
+
+// Every wave recorded since the last call.
+pub fn take_waves() -> Vec<WaveResult> {
+    std::mem::take(&mut *WAVES.lock().unwrap())
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum OutputFormat {
+    Text,
+    Csv,
+    Json,
+}
+
+impl OutputFormat {
+    pub fn parse(name: &str) -> Option<OutputFormat> {
+        match name {
+            "text" => Some(OutputFormat::Text),
+            "csv" => Some(OutputFormat::Csv),
+            "json" => Some(OutputFormat::Json),
+            _ => None,
+        }
+    }
+
+    // csv gets its header once, before the first benchmark
+    pub fn write_header(&self, out: &mut dyn Write) -> io::Result<()> {
+        if *self == OutputFormat::Csv {
+            writeln!(
+                out,
+                "Benchmark, Round, Test, Seconds, Bytes, GB/s, Page Faults"
+            )?;
+        }
+        Ok(())
+    }
+
+    pub fn write_waves(
+        &self,
+        out: &mut dyn Write,
+        benchmark: &str,
+        round: u64,
+        waves: &[WaveResult],
+    ) -> io::Result<()> {
+        match self {
+            OutputFormat::Text => {
+                writeln!(out, "\n===== {} (round {}) =====", benchmark, round)?;
+                writeln!(out, "Test, Seconds, GB/s, Page Faults")?;
+                for wave in waves {
+                    writeln!(
+                        out,
+                        "{}, {:.6}, {:.4}, {}",
+                        wave.test, wave.min_seconds, wave.gb_per_second, wave.min_page_faults
+                    )?;
+                }
+            }
+            OutputFormat::Csv => {
+                for wave in waves {
+                    writeln!(
+                        out,
+                        "{}, {}, {}, {}, {}, {}, {}",
+                        benchmark,
+                        round,
+                        wave.test.replace(',', ";"),
+                        wave.min_seconds,
+                        wave.min_bytes,
+                        wave.gb_per_second,
+                        wave.min_page_faults
+                    )?;
+                }
+            }
+            // one object per line, so runs can be appended and streamed
+            OutputFormat::Json => {
+                for wave in waves {
+                    writeln!(
+                        out,
+                        "{{\"benchmark\": \"{}\", \"round\": {}, \"test\": \"{}\", \"seconds\": {}, \"bytes\": {}, \"gb_per_second\": {}, \"page_faults\": {}}}",
+                        json_escape(benchmark),
+                        round,
+                        json_escape(&wave.test),
+                        wave.min_seconds,
+                        wave.min_bytes,
+                        wave.gb_per_second,
+                        wave.min_page_faults
+                    )?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+fn json_escape(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '\\' => escaped.push_str("\\\\"),
+            '"' => escaped.push_str("\\\""),
+            // JSON strings can't hold raw control characters
+            c if (c as u32) < 0x20 => escaped.push_str(&format!("\\u{:04x}", c as u32)),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
@@ -0,0 +1,183 @@
+use std::io;
+
+// pages per bitmap row when a buffer is small enough to show page by page
+const BITMAP_COLUMNS: usize = 64;
+// rows the bitmap is squeezed into for bigger buffers, a 1GB buffer of 4k
+// pages ends up at 128 pages per character
+const BITMAP_ROWS: usize = 32;
+
+// Which pages of a buffer were in memory when the snapshot was taken, from
+// mincore.
+#[derive(Debug, Clone)]
+pub struct ResidencySnapshot {
+    pub page_size: usize,
+    pub resident: Vec<bool>,
+}
+
+impl ResidencySnapshot {
+    // `addr` has to be page aligned, mmap'd buffers always are.
+    pub fn take(addr: *mut u8, size: usize) -> io::Result<ResidencySnapshot> {
+        let page_size = unsafe { libc::sysconf(libc::_SC_PAGESIZE) } as usize;
+        let mut pages = vec![0u8; size.div_ceil(page_size)];
+        let result = unsafe {
+            libc::mincore(
+                addr as *mut libc::c_void,
+                size,
+                pages.as_mut_ptr() as *mut _,
+            )
+        };
+        if result != 0 {
+            return Err(io::Error::last_os_error());
+        }
+
+        Ok(ResidencySnapshot {
+            page_size,
+            // only the lowest bit means resident, the rest is reserved
+            resident: pages.iter().map(|page| page & 1 != 0).collect(),
+        })
+    }
+
+    pub fn resident_count(&self) -> usize {
+        self.resident.iter().filter(|resident| **resident).count()
+    }
+
+    // "12800 of 262144 pages resident (4.9%, 50.0MB) in 3 runs, longest 12288 pages"
+    pub fn summary(&self) -> String {
+        let resident = self.resident_count();
+        let runs = resident_runs(&self.resident);
+        let longest = runs.iter().map(|(_, length)| *length).max().unwrap_or(0);
+        format!(
+            "{} of {} pages resident ({:.1}%, {:.1}MB) in {} runs, longest {} pages",
+            resident,
+            self.resident.len(),
+            percent(resident, self.resident.len()),
+            (resident * self.page_size) as f64 / (1024.0 * 1024.0),
+            runs.len(),
+            longest
+        )
+    }
+
+    // One character per group of pages: '.' when none of them are resident,
+    // '#' when all are, 1 to 9 for the tenths in between.
+    pub fn render_bitmap(&self) -> String {
+        render(self.resident.len(), |pages| {
+            let resident = pages.clone().filter(|page| self.resident[*page]).count();
+            if resident == 0 {
+                '.'
+            } else if resident == pages.len() {
+                '#'
+            } else {
+                let tenths = (resident * 10).div_ceil(pages.len()).min(9);
+                char::from_digit(tenths as u32, 10).unwrap()
+            }
+        })
+    }
+
+    // What changed between this snapshot and a `later` one of the same buffer.
+    pub fn diff(&self, later: &ResidencySnapshot) -> ResidencyDiff {
+        let pages = self.resident.len().min(later.resident.len());
+        let mut changes = Vec::with_capacity(pages);
+        for page in 0..pages {
+            changes.push(match (self.resident[page], later.resident[page]) {
+                (false, true) => PageChange::Faulted,
+                (true, false) => PageChange::Evicted,
+                (true, true) => PageChange::StayedResident,
+                (false, false) => PageChange::StayedOut,
+            });
+        }
+        ResidencyDiff { changes }
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum PageChange {
+    Faulted,
+    Evicted,
+    StayedResident,
+    StayedOut,
+}
+
+pub struct ResidencyDiff {
+    pub changes: Vec<PageChange>,
+}
+
+impl ResidencyDiff {
+    pub fn count(&self, change: PageChange) -> usize {
+        self.changes.iter().filter(|c| **c == change).count()
+    }
+
+    pub fn summary(&self) -> String {
+        format!(
+            "{} pages faulted in, {} evicted, {} stayed resident, {} stayed out",
+            self.count(PageChange::Faulted),
+            self.count(PageChange::Evicted),
+            self.count(PageChange::StayedResident),
+            self.count(PageChange::StayedOut)
+        )
+    }
+
+    // '+' for groups that only gained pages, '-' for ones that only lost
+    // some, '*' for both, and the snapshot characters ('#', '.') where
+    // nothing changed.
+    pub fn render_bitmap(&self) -> String {
+        render(self.changes.len(), |pages| {
+            let count =
+                |change: PageChange| pages.clone().filter(|p| self.changes[*p] == change).count();
+            match (count(PageChange::Faulted), count(PageChange::Evicted)) {
+                (0, 0) if count(PageChange::StayedResident) > 0 => '#',
+                (0, 0) => '.',
+                (_, 0) => '+',
+                (0, _) => '-',
+                _ => '*',
+            }
+        })
+    }
+}
+
+fn percent(count: usize, total: usize) -> f64 {
+    if total == 0 {
+        0.0
+    } else {
+        count as f64 * 100.0 / total as f64
+    }
+}
+
+// (first page, length) of every run of resident pages
+fn resident_runs(resident: &[bool]) -> Vec<(usize, usize)> {
+    let mut runs = Vec::new();
+    let mut start = None;
+    for (page, is_resident) in resident.iter().chain([&false]).enumerate() {
+        match (start, *is_resident) {
+            (None, true) => start = Some(page),
+            (Some(first), false) => {
+                runs.push((first, page - first));
+                start = None;
+            }
+            _ => {}
+        }
+    }
+    runs
+}
+
+// Lays `page_count` pages out in rows of BITMAP_COLUMNS characters, each
+// character covering as many pages as it takes to fit BITMAP_ROWS rows. Every
+// row starts with the first page it covers.
+fn render<F>(page_count: usize, character: F) -> String
+where
+    F: Fn(std::ops::Range<usize>) -> char,
+{
+    let pages_per_character = page_count.div_ceil(BITMAP_COLUMNS * BITMAP_ROWS).max(1);
+    let pages_per_row = pages_per_character * BITMAP_COLUMNS;
+    let label_width = page_count.to_string().len();
+
+    let mut bitmap = format!("({} pages per character)\n", pages_per_character);
+    for row_start in (0..page_count).step_by(pages_per_row) {
+        let row_end = (row_start + pages_per_row).min(page_count);
+        let row: String = (row_start..row_end)
+            .step_by(pages_per_character)
+            .map(|first| character(first..(first + pages_per_character).min(row_end)))
+            .collect();
+        bitmap.push_str(&format!("{:>label_width$} {}\n", row_start, row));
+    }
+    bitmap
+}
@@ -0,0 +1,540 @@
+use std::fmt::Write as FmtWrite;
+use std::fs;
+use std::io;
+
+use crate::sweep::{format_size, SweepResults};
+
+const WIDTH: f64 = 860.0;
+const HEIGHT: f64 = 500.0;
+const MARGIN_LEFT: f64 = 80.0;
+const MARGIN_RIGHT: f64 = 190.0;
+const MARGIN_TOP: f64 = 50.0;
+const MARGIN_BOTTOM: f64 = 70.0;
+
+const COLORS: [&str; 8] = [
+    "#1f77b4", "#ff7f0e", "#2ca02c", "#d62728", "#9467bd", "#8c564b", "#e377c2", "#17becf",
+];
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum AxisFormat {
+    Number,
+    // values are byte counts, labelled as 16Kb, 4Mb...
+    Size,
+}
+
+#[derive(Debug, Clone)]
+pub struct Series {
+    pub name: String,
+    pub points: Vec<(f64, f64)>,
+}
+
+impl From<&SweepResults> for Series {
+    fn from(results: &SweepResults) -> Self {
+        Series {
+            name: results.name.to_string(),
+            points: results
+                .points
+                .iter()
+                .map(|point| (point.parameter as f64, point.metric))
+                .collect(),
+        }
+    }
+}
+
+pub struct LineChart {
+    pub title: String,
+    pub x_label: String,
+    pub y_label: String,
+    pub x_format: AxisFormat,
+    pub log_x: bool,
+    pub series: Vec<Series>,
+}
+
+impl LineChart {
+    pub fn from_sweep(results: &SweepResults, x_format: AxisFormat, log_x: bool) -> Self {
+        LineChart {
+            title: results.name.to_string(),
+            x_label: results.parameter_label.to_string(),
+            y_label: results.metric.label().to_string(),
+            x_format,
+            log_x,
+            series: vec![Series::from(results)],
+        }
+    }
+
+    pub fn render(&self) -> String {
+        let points = self
+            .series
+            .iter()
+            .flat_map(|series| series.points.iter())
+            .filter(|(x, _)| !self.log_x || *x > 0.0);
+
+        let mut x_min = f64::MAX;
+        let mut x_max = f64::MIN;
+        let mut y_max = 0.0_f64;
+        let mut y_min = 0.0_f64;
+        for (x, y) in points {
+            x_min = x_min.min(*x);
+            x_max = x_max.max(*x);
+            y_min = y_min.min(*y);
+            y_max = y_max.max(*y);
+        }
+        if x_min > x_max {
+            x_min = 0.0;
+            x_max = 1.0;
+        }
+        if x_min == x_max {
+            x_max = x_min + 1.0;
+        }
+
+        let y_ticks = nice_ticks(y_min, y_max, 8);
+        let y_min = y_ticks[0];
+        let y_max = *y_ticks.last().unwrap();
+
+        let x_ticks = if self.log_x {
+            log_ticks(x_min, x_max, 12)
+        } else {
+            nice_ticks(x_min, x_max, 10)
+        };
+        let (x_min, x_max) = if self.log_x {
+            (x_min.log2(), x_max.log2())
+        } else {
+            (x_ticks[0], *x_ticks.last().unwrap())
+        };
+
+        let plot_width = WIDTH - MARGIN_LEFT - MARGIN_RIGHT;
+        let plot_height = HEIGHT - MARGIN_TOP - MARGIN_BOTTOM;
+        let map_x = |x: f64| {
+            let x = if self.log_x { x.log2() } else { x };
+            MARGIN_LEFT + (x - x_min) / (x_max - x_min) * plot_width
+        };
+        let map_y = |y: f64| MARGIN_TOP + plot_height - (y - y_min) / (y_max - y_min) * plot_height;
+
+        let mut svg = svg_header(&self.title);
+
+        for tick in &y_ticks {
+            let y = map_y(*tick);
+            grid_line(&mut svg, MARGIN_LEFT, y, MARGIN_LEFT + plot_width, y);
+            text(
+                &mut svg,
+                MARGIN_LEFT - 8.0,
+                y + 4.0,
+                "end",
+                &format_number(*tick),
+            );
+        }
+        for tick in &x_ticks {
+            let x = map_x(*tick);
+            grid_line(&mut svg, x, MARGIN_TOP, x, MARGIN_TOP + plot_height);
+            let label = match self.x_format {
+                AxisFormat::Number => format_number(*tick),
+                AxisFormat::Size => format_size(*tick as u64),
+            };
+            text(
+                &mut svg,
+                x,
+                MARGIN_TOP + plot_height + 18.0,
+                "middle",
+                &label,
+            );
+        }
+        axes(
+            &mut svg,
+            plot_width,
+            plot_height,
+            &self.x_label,
+            &self.y_label,
+        );
+
+        for (index, series) in self.series.iter().enumerate() {
+            let color = COLORS[index % COLORS.len()];
+            let coordinates: Vec<(f64, f64)> = series
+                .points
+                .iter()
+                .filter(|(x, _)| !self.log_x || *x > 0.0)
+                .map(|(x, y)| (map_x(*x), map_y(*y)))
+                .collect();
+            let path: Vec<String> = coordinates
+                .iter()
+                .map(|(x, y)| format!("{:.2},{:.2}", x, y))
+                .collect();
+
+            let _ = writeln!(
+                svg,
+                r#"<polyline fill="none" stroke="{}" stroke-width="2" points="{}"/>"#,
+                color,
+                path.join(" ")
+            );
+            for (x, y) in &coordinates {
+                let _ = writeln!(
+                    svg,
+                    r#"<circle cx="{:.2}" cy="{:.2}" r="2.5" fill="{}"/>"#,
+                    x, y, color
+                );
+            }
+
+            let legend_y = MARGIN_TOP + 10.0 + index as f64 * 20.0;
+            let legend_x = MARGIN_LEFT + plot_width + 15.0;
+            let _ = writeln!(
+                svg,
+                r#"<rect x="{:.2}" y="{:.2}" width="12" height="12" fill="{}"/>"#,
+                legend_x,
+                legend_y - 10.0,
+                color
+            );
+            text(&mut svg, legend_x + 18.0, legend_y, "start", &series.name);
+        }
+
+        svg.push_str("</svg>\n");
+        svg
+    }
+
+    pub fn save(&self, file_name: &str) -> io::Result<()> {
+        fs::write(file_name, self.render())
+    }
+}
+
+pub struct BarChart {
+    pub title: String,
+    pub y_label: String,
+    pub bars: Vec<(String, f64)>,
+}
+
+impl BarChart {
+    pub fn render(&self) -> String {
+        let y_max = self
+            .bars
+            .iter()
+            .fold(0.0_f64, |max, (_, value)| max.max(*value));
+        let y_ticks = nice_ticks(0.0, y_max, 8);
+        let y_max = *y_ticks.last().unwrap();
+
+        let plot_width = WIDTH - MARGIN_LEFT - MARGIN_RIGHT;
+        let plot_height = HEIGHT - MARGIN_TOP - MARGIN_BOTTOM;
+        let map_y = |y: f64| MARGIN_TOP + plot_height - y / y_max * plot_height;
+
+        let mut svg = svg_header(&self.title);
+
+        for tick in &y_ticks {
+            let y = map_y(*tick);
+            grid_line(&mut svg, MARGIN_LEFT, y, MARGIN_LEFT + plot_width, y);
+            text(
+                &mut svg,
+                MARGIN_LEFT - 8.0,
+                y + 4.0,
+                "end",
+                &format_number(*tick),
+            );
+        }
+        axes(&mut svg, plot_width, plot_height, "", &self.y_label);
+
+        let slot_width = plot_width / self.bars.len().max(1) as f64;
+        let bar_width = slot_width * 0.7;
+        for (index, (name, value)) in self.bars.iter().enumerate() {
+            let color = COLORS[index % COLORS.len()];
+            let x = MARGIN_LEFT + slot_width * index as f64 + (slot_width - bar_width) / 2.0;
+            let y = map_y(*value);
+            let _ = writeln!(
+                svg,
+                r#"<rect x="{:.2}" y="{:.2}" width="{:.2}" height="{:.2}" fill="{}"/>"#,
+                x,
+                y,
+                bar_width,
+                MARGIN_TOP + plot_height - y,
+                color
+            );
+            text(
+                &mut svg,
+                x + bar_width / 2.0,
+                y - 5.0,
+                "middle",
+                &format_number(*value),
+            );
+            text(
+                &mut svg,
+                x + bar_width / 2.0,
+                MARGIN_TOP + plot_height + 18.0,
+                "middle",
+                name,
+            );
+        }
+
+        svg.push_str("</svg>\n");
+        svg
+    }
+
+    pub fn save(&self, file_name: &str) -> io::Result<()> {
+        fs::write(file_name, self.render())
+    }
+}
+
+// Grid of values, one row per y value and one column per x value, colored from
+// light (lowest) to dark (highest). Missing cells stay blank.
+pub struct HeatMap {
+    pub title: String,
+    pub x_label: String,
+    pub y_label: String,
+    pub value_label: String,
+    pub x_values: Vec<String>,
+    pub y_values: Vec<String>,
+    pub cells: Vec<Vec<Option<f64>>>,
+}
+
+impl HeatMap {
+    pub fn render(&self) -> String {
+        let values = self.cells.iter().flatten().flatten();
+        let min = values.clone().fold(f64::MAX, |min, value| min.min(*value));
+        let max = values.fold(f64::MIN, |max, value| max.max(*value));
+        let range = if max > min { max - min } else { 1.0 };
+
+        let plot_width = WIDTH - MARGIN_LEFT - MARGIN_RIGHT;
+        let plot_height = HEIGHT - MARGIN_TOP - MARGIN_BOTTOM;
+        let cell_width = plot_width / self.x_values.len().max(1) as f64;
+        let cell_height = plot_height / self.y_values.len().max(1) as f64;
+
+        let mut svg = svg_header(&self.title);
+
+        for (row, cells) in self.cells.iter().enumerate() {
+            let y = MARGIN_TOP + cell_height * row as f64;
+            for (column, value) in cells.iter().enumerate() {
+                let Some(value) = value else {
+                    continue;
+                };
+                let _ = writeln!(
+                    svg,
+                    r#"<rect x="{:.2}" y="{:.2}" width="{:.2}" height="{:.2}" fill="{}"><title>{}</title></rect>"#,
+                    MARGIN_LEFT + cell_width * column as f64,
+                    y,
+                    cell_width,
+                    cell_height,
+                    heat_color((value - min) / range),
+                    format_number(*value)
+                );
+            }
+        }
+
+        for (row, label) in self.y_values.iter().enumerate() {
+            let y = MARGIN_TOP + cell_height * (row as f64 + 0.5) + 4.0;
+            text(&mut svg, MARGIN_LEFT - 8.0, y, "end", label);
+        }
+        // about 16 labels along x whatever the column count
+        let every = self.x_values.len().div_ceil(16).max(1);
+        for (column, label) in self.x_values.iter().enumerate().step_by(every) {
+            let x = MARGIN_LEFT + cell_width * (column as f64 + 0.5);
+            text(
+                &mut svg,
+                x,
+                MARGIN_TOP + plot_height + 18.0,
+                "middle",
+                label,
+            );
+        }
+        axes(
+            &mut svg,
+            plot_width,
+            plot_height,
+            &self.x_label,
+            &self.y_label,
+        );
+
+        // color scale, lowest value at the bottom
+        let legend_x = MARGIN_LEFT + plot_width + 15.0;
+        let steps = 20;
+        let step_height = plot_height / steps as f64;
+        for step in 0..steps {
+            let _ = writeln!(
+                svg,
+                r#"<rect x="{:.2}" y="{:.2}" width="16" height="{:.2}" fill="{}"/>"#,
+                legend_x,
+                MARGIN_TOP + step_height * step as f64,
+                step_height + 0.5,
+                heat_color(1.0 - step as f64 / (steps - 1) as f64)
+            );
+        }
+        text(
+            &mut svg,
+            legend_x + 22.0,
+            MARGIN_TOP + 10.0,
+            "start",
+            &format_number(max),
+        );
+        text(
+            &mut svg,
+            legend_x + 22.0,
+            MARGIN_TOP + plot_height,
+            "start",
+            &format_number(min),
+        );
+        text(
+            &mut svg,
+            legend_x + 22.0,
+            MARGIN_TOP + plot_height / 2.0,
+            "start",
+            &self.value_label,
+        );
+
+        svg.push_str("</svg>\n");
+        svg
+    }
+
+    pub fn save(&self, file_name: &str) -> io::Result<()> {
+        fs::write(file_name, self.render())
+    }
+}
+
+// results.csv -> results.svg, so the chart ends up next to the CSV it came from
+pub fn svg_file_name(csv_file: &str) -> String {
+    match csv_file.strip_suffix(".csv") {
+        Some(base_name) => format!("{}.svg", base_name),
+        None => format!("{}.svg", csv_file),
+    }
+}
+
+// Reads a CSV with a header line, like the ones the listings print. Returns the
+// trimmed column names and the trimmed values of every row.
+pub fn read_csv(file_name: &str) -> io::Result<(Vec<String>, Vec<Vec<String>>)> {
+    let content = fs::read_to_string(file_name)?;
+    let mut lines = content.lines().filter(|line| !line.trim().is_empty());
+
+    let split = |line: &str| -> Vec<String> {
+        line.split(',')
+            .map(|value| value.trim().to_string())
+            .collect()
+    };
+
+    let header = lines.next().map(split).unwrap_or_default();
+    let rows = lines.map(split).collect();
+
+    Ok((header, rows))
+}
+
+fn svg_header(title: &str) -> String {
+    let mut svg = String::new();
+    let _ = writeln!(
+        svg,
+        r#"<svg xmlns="http://www.w3.org/2000/svg" width="{}" height="{}" viewBox="0 0 {} {}" font-family="sans-serif" font-size="12">"#,
+        WIDTH, HEIGHT, WIDTH, HEIGHT
+    );
+    let _ = writeln!(svg, r#"<rect width="100%" height="100%" fill="white"/>"#);
+    let _ = writeln!(
+        svg,
+        r#"<text x="{}" y="28" text-anchor="middle" font-size="16" font-weight="bold">{}</text>"#,
+        WIDTH / 2.0,
+        escape(title)
+    );
+    svg
+}
+
+fn axes(svg: &mut String, plot_width: f64, plot_height: f64, x_label: &str, y_label: &str) {
+    let bottom = MARGIN_TOP + plot_height;
+    let _ = writeln!(
+        svg,
+        r#"<polyline fill="none" stroke="black" points="{},{} {},{} {},{}"/>"#,
+        MARGIN_LEFT,
+        MARGIN_TOP,
+        MARGIN_LEFT,
+        bottom,
+        MARGIN_LEFT + plot_width,
+        bottom
+    );
+    text(
+        svg,
+        MARGIN_LEFT + plot_width / 2.0,
+        bottom + 45.0,
+        "middle",
+        x_label,
+    );
+    let _ = writeln!(
+        svg,
+        r#"<text x="20" y="{:.2}" text-anchor="middle" transform="rotate(-90 20 {:.2})">{}</text>"#,
+        MARGIN_TOP + plot_height / 2.0,
+        MARGIN_TOP + plot_height / 2.0,
+        escape(y_label)
+    );
+}
+
+fn grid_line(svg: &mut String, x1: f64, y1: f64, x2: f64, y2: f64) {
+    let _ = writeln!(
+        svg,
+        r##"<line x1="{:.2}" y1="{:.2}" x2="{:.2}" y2="{:.2}" stroke="#dddddd"/>"##,
+        x1, y1, x2, y2
+    );
+}
+
+fn text(svg: &mut String, x: f64, y: f64, anchor: &str, value: &str) {
+    let _ = writeln!(
+        svg,
+        r#"<text x="{:.2}" y="{:.2}" text-anchor="{}">{}</text>"#,
+        x,
+        y,
+        anchor,
+        escape(value)
+    );
+}
+
+// 0.0 -> pale yellow, 1.0 -> dark blue
+fn heat_color(fraction: f64) -> String {
+    let low = [255.0, 247.0, 188.0];
+    let high = [8.0, 48.0, 107.0];
+    let fraction = fraction.clamp(0.0, 1.0);
+    let channel = |index: usize| (low[index] + (high[index] - low[index]) * fraction) as u8;
+    format!("#{:02x}{:02x}{:02x}", channel(0), channel(1), channel(2))
+}
+
+fn escape(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
+fn format_number(value: f64) -> String {
+    if value == 0.0 || value.abs() >= 100.0 {
+        format!("{:.0}", value)
+    } else if value.abs() >= 1.0 {
+        format!("{:.2}", value)
+            .trim_end_matches('0')
+            .trim_end_matches('.')
+            .to_string()
+    } else {
+        format!("{:.3}", value)
+    }
+}
+
+// 1, 2, 5 * 10^n steps covering [min, max] with at most `max_ticks` ticks
+fn nice_ticks(min: f64, max: f64, max_ticks: usize) -> Vec<f64> {
+    let range = if max > min { max - min } else { 1.0 };
+    let raw_step = range / max_ticks as f64;
+    let magnitude = 10.0_f64.powf(raw_step.log10().floor());
+    let step = [1.0, 2.0, 5.0, 10.0]
+        .iter()
+        .map(|factor| factor * magnitude)
+        .find(|step| *step >= raw_step)
+        .unwrap_or(10.0 * magnitude);
+
+    let start = (min / step).floor() * step;
+    let end = (max / step).ceil() * step;
+    let mut ticks = Vec::new();
+    let mut tick = start;
+    while tick <= end + step / 2.0 {
+        ticks.push(tick);
+        tick += step;
+    }
+    if ticks.len() < 2 {
+        ticks.push(start + step);
+    }
+    ticks
+}
+
+// powers of two between min and max, thinned out to at most `max_ticks`
+fn log_ticks(min: f64, max: f64, max_ticks: usize) -> Vec<f64> {
+    let first = min.log2().ceil() as i32;
+    let last = max.log2().floor() as i32;
+    let count = (last - first + 1).max(1) as usize;
+    let every = count.div_ceil(max_ticks).max(1);
+
+    (first..=last.max(first))
+        .step_by(every)
+        .map(|power| 2.0_f64.powi(power))
+        .collect()
+}
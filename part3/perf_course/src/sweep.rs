@@ -0,0 +1,204 @@
+use std::fs::File;
+use std::io::{self, Write};
+
+use crate::repetition_tester::repetition_tester::RepetitionTester;
+use crate::report::record_wave;
+
+#[derive(Debug, Clone)]
+pub enum ParameterGenerator {
+    // start, start + step, ... up to and including end
+    Linear { start: u64, end: u64, step: u64 },
+    // every power of two between start and end
+    PowerOfTwo { start: u64, end: u64 },
+    // `points` values spread evenly on a log scale between start and end
+    LogSpaced { start: u64, end: u64, points: usize },
+    List(Vec<u64>),
+}
+
+impl ParameterGenerator {
+    pub fn values(&self) -> Vec<u64> {
+        let mut values = Vec::new();
+
+        match self {
+            ParameterGenerator::Linear { start, end, step } => {
+                let mut value = *start;
+                while value <= *end {
+                    values.push(value);
+                    value += (*step).max(1);
+                }
+            }
+            ParameterGenerator::PowerOfTwo { start, end } => {
+                let mut value = (*start).max(1).next_power_of_two();
+                while value <= *end {
+                    values.push(value);
+                    value *= 2;
+                }
+            }
+            // nothing when end is below start, like the other ranges
+            ParameterGenerator::LogSpaced { start, end, points } => {
+                let start = (*start).max(1);
+                if *end >= start {
+                    let (start, end) = (start as f64, *end as f64);
+                    let points = (*points).max(2);
+                    let ratio = (end / start).ln() / (points - 1) as f64;
+                    for i in 0..points {
+                        values.push((start * (ratio * i as f64).exp()).round() as u64);
+                    }
+                    values.dedup();
+                }
+            }
+            ParameterGenerator::List(list) => {
+                values.extend_from_slice(list);
+            }
+        }
+
+        values
+    }
+
+    // same values rounded down to a multiple of `multiple`, useful for kernels
+    // that consume the buffer in fixed size blocks
+    pub fn aligned_values(&self, multiple: u64) -> Vec<u64> {
+        let mut values: Vec<u64> = self
+            .values()
+            .iter()
+            .map(|value| (value / multiple).max(1) * multiple)
+            .collect();
+        values.dedup();
+        values
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum SweepMetric {
+    // GB/s of the fastest run
+    Bandwidth,
+    // duration of the fastest run
+    Seconds,
+    // page faults of the fastest run
+    PageFaults,
+}
+
+impl SweepMetric {
+    pub fn label(&self) -> &'static str {
+        match self {
+            SweepMetric::Bandwidth => "GB/s",
+            SweepMetric::Seconds => "Seconds",
+            SweepMetric::PageFaults => "Page Faults",
+        }
+    }
+
+    fn value(&self, tester: &RepetitionTester) -> f64 {
+        match self {
+            SweepMetric::Bandwidth => tester.min_bandwidth(),
+            SweepMetric::Seconds => tester.min_seconds(),
+            SweepMetric::PageFaults => tester.min_page_faults() as f64,
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone)]
+pub struct SweepPoint {
+    pub parameter: u64,
+    pub metric: f64,
+    pub seconds: f64,
+    pub bytes: u64,
+}
+
+pub struct Sweep {
+    pub name: String,
+    pub parameter_label: String,
+    pub generator: ParameterGenerator,
+    pub metric: SweepMetric,
+    pub seconds_to_try: u64,
+}
+
+impl Sweep {
+    // Runs one test wave per generated parameter. The kernel gets the parameter
+    // and returns the number of bytes it processed. It is called once untimed
+    // before each wave to warm up the buffer and learn the expected byte count.
+    pub fn run<F>(&self, mut kernel: F) -> SweepResults
+    where
+        F: FnMut(u64) -> u64,
+    {
+        let mut points = Vec::new();
+
+        for parameter in self.generator.values() {
+            println!(
+                "\n----- {} {}: {} -----",
+                self.name, self.parameter_label, parameter
+            );
+
+            let expected_bytes = kernel(parameter);
+            let mut tester = RepetitionTester::new();
+            tester.start_test_wave(self.seconds_to_try, expected_bytes);
+            while tester.is_testing() {
+                tester.begin_time();
+                let bytes = kernel(parameter);
+                tester.end_time();
+                tester.count_bytes(bytes);
+            }
+            record_wave(
+                &format!("{} {} {}", self.name, self.parameter_label, parameter),
+                &tester,
+            );
+
+            points.push(SweepPoint {
+                parameter,
+                metric: self.metric.value(&tester),
+                seconds: tester.min_seconds(),
+                bytes: tester.min_bytes(),
+            });
+        }
+
+        SweepResults {
+            name: self.name.clone(),
+            parameter_label: self.parameter_label.clone(),
+            metric: self.metric,
+            points,
+        }
+    }
+}
+
+pub struct SweepResults {
+    pub name: String,
+    pub parameter_label: String,
+    pub metric: SweepMetric,
+    pub points: Vec<SweepPoint>,
+}
+
+impl SweepResults {
+    pub fn print_table(&self) {
+        println!("\n===== {} =====", self.name);
+        println!("{}, {}", self.parameter_label, self.metric.label());
+        for point in &self.points {
+            println!("{}, {:.4}", point.parameter, point.metric);
+        }
+    }
+
+    pub fn write_csv(&self, file_name: &str) -> io::Result<()> {
+        let mut file = File::create(file_name)?;
+        writeln!(file, "{}, {}", self.parameter_label, self.metric.label())?;
+        for point in &self.points {
+            writeln!(file, "{}, {}", point.parameter, point.metric)?;
+        }
+        Ok(())
+    }
+}
+
+// 16384 -> "16Kb", 4194304 -> "4Mb", same naming the listings use
+pub fn format_size(bytes: u64) -> String {
+    let units = [
+        (1024 * 1024 * 1024, "Gb"),
+        (1024 * 1024, "Mb"),
+        (1024, "Kb"),
+    ];
+    for (unit, suffix) in units {
+        if bytes >= unit {
+            if bytes.is_multiple_of(unit) {
+                return format!("{}{}", bytes / unit, suffix);
+            }
+            return format!("{:.1}{}", bytes as f64 / unit as f64, suffix);
+        }
+    }
+    format!("{}b", bytes)
+}